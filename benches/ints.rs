@@ -0,0 +1,24 @@
+//! Run with `cargo bench --bench ints`.
+
+use std::time::Instant;
+
+fn main() {
+    let values: Vec<i64> = (0..1_000_000i64)
+        .map(|i| i * 7919 - 3_000_000_000)
+        .collect();
+    let text = serde_pyliteral::to_string(&values).unwrap();
+
+    let rounds = 10;
+    let start = Instant::now();
+    for _ in 0..rounds {
+        let parsed: Vec<i64> = serde_pyliteral::from_str(&text).unwrap();
+        assert_eq!(parsed.len(), values.len());
+    }
+    let elapsed = start.elapsed() / rounds;
+    println!(
+        "deserialize {} ints ({} bytes): {:?} per round",
+        values.len(),
+        text.len(),
+        elapsed
+    );
+}
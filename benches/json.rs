@@ -0,0 +1,44 @@
+//! Run with `cargo bench --bench json --features json`.
+
+use serde_json::json;
+use serde_json::Value;
+use std::time::Instant;
+
+fn bench<T>(name: &str, len: usize, f: impl Fn() -> T, check: impl Fn(&T)) {
+    let rounds = 10;
+    let start = Instant::now();
+    for _ in 0..rounds {
+        check(&f());
+    }
+    let elapsed = start.elapsed() / rounds;
+    println!("{} ({} bytes): {:?} per round", name, len, elapsed);
+}
+
+fn main() {
+    let records: Vec<Value> = (0..20_000)
+        .map(|i| {
+            json!({
+                "id": i,
+                "name": format!("item {}", i),
+                "score": i as f64 * 0.37,
+                "tags": ["a", "b", null],
+                "ok": i % 2 == 0,
+            })
+        })
+        .collect();
+    let value = Value::Array(records);
+    let text = serde_pyliteral::json::json_value_to_pyliteral_string(&value).unwrap();
+
+    bench(
+        "json_value_to_pyliteral_string",
+        text.len(),
+        || serde_pyliteral::json::json_value_to_pyliteral_string(&value).unwrap(),
+        |s| assert_eq!(s.len(), text.len()),
+    );
+    bench(
+        "pyliteral_string_to_json_value",
+        text.len(),
+        || serde_pyliteral::json::pyliteral_string_to_json_value(&text).unwrap(),
+        |v| assert_eq!(v, &value),
+    );
+}
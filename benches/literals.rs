@@ -0,0 +1,39 @@
+//! Run with `cargo bench --bench literals`.
+
+use std::time::Instant;
+
+fn bench<T: serde::de::DeserializeOwned>(name: &str, text: &str, check: impl Fn(&T)) {
+    let rounds = 10;
+    let start = Instant::now();
+    for _ in 0..rounds {
+        let parsed: T = serde_pyliteral::from_str(text).unwrap();
+        check(&parsed);
+    }
+    let elapsed = start.elapsed() / rounds;
+    println!(
+        "deserialize {} ({} bytes): {:?} per round",
+        name,
+        text.len(),
+        elapsed
+    );
+}
+
+fn main() {
+    let floats: Vec<f64> = (0..1_000_000).map(|i| i as f64 * 0.37 - 1e5).collect();
+    let text = serde_pyliteral::to_string(&floats).unwrap();
+    bench("1000000 floats", &text, |v: &Vec<f64>| {
+        assert_eq!(v.len(), floats.len())
+    });
+
+    let strings: Vec<String> = (0..10_000).map(|i| format!("{:0>100}", i)).collect();
+    let text = serde_pyliteral::to_string(&strings).unwrap();
+    bench("10000 strings", &text, |v: &Vec<String>| {
+        assert_eq!(v.len(), strings.len())
+    });
+
+    let large: String = (0..1_000_000).map(|i| format!("{}\n", i)).collect();
+    let text = serde_pyliteral::to_string(&large).unwrap();
+    bench("1 large string", &text, |v: &String| {
+        assert_eq!(v.len(), large.len())
+    });
+}
@@ -0,0 +1,228 @@
+//! Serialize `Duration` and `SystemTime` as Python `datetime` expressions.
+//!
+//! Use `#[serde(with = "serde_pyliteral::datetime::timedelta")]` on a
+//! `Duration` field to write `datetime.timedelta(seconds=5)`, and
+//! `#[serde(with = "serde_pyliteral::datetime::fromtimestamp")]` on a
+//! `SystemTime` field to write `datetime.datetime.fromtimestamp(1.5)`.
+//! Both read back what they write.
+//!
+//! Like `base64.b64decode`, these are Python expressions, not literals.
+//! `ast.literal_eval` cannot evaluate them, `eval` with `datetime` imported
+//! can. Other serializers see the expression as a str.
+
+use serde::de;
+use serde::ser;
+use std::convert::TryFrom;
+use std::fmt;
+
+/// Name passed to `serialize_newtype_struct` and
+/// `deserialize_newtype_struct`. The crate's `Serializer` writes the str
+/// inside as-is, and the crate's `Deserializer` reads a call expression,
+/// like `f(1, x=2)`, as a str.
+pub(crate) const EXPR_TOKEN: &str = "$serde_pyliteral::private::Expr";
+
+/// `Duration` as `datetime.timedelta(days=..., seconds=..., microseconds=...)`,
+/// like `repr(timedelta)`. Nanoseconds are truncated to microseconds.
+///
+/// Positional and keyword arguments of `timedelta`, including `weeks`,
+/// `hours`, `minutes` and `milliseconds`, can be read. Negative durations
+/// are rejected.
+pub mod timedelta {
+    use super::*;
+    use std::time::Duration;
+
+    pub fn serialize<S: ser::Serializer>(
+        value: &Duration,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let secs = value.as_secs();
+        let parts = [
+            ("days", secs / 86400),
+            ("seconds", secs % 86400),
+            ("microseconds", value.subsec_micros() as u64),
+        ];
+        let args: Vec<String> = parts
+            .iter()
+            .filter(|(_, v)| *v > 0)
+            .map(|(name, v)| format!("{}={}", name, v))
+            .collect();
+        let args = if args.is_empty() {
+            "0".to_string()
+        } else {
+            args.join(", ")
+        };
+        let expr = format!("datetime.timedelta({})", args);
+        serializer.serialize_newtype_struct(EXPR_TOKEN, &expr)
+    }
+
+    pub fn deserialize<'de, D: de::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Duration, D::Error> {
+        deserializer.deserialize_newtype_struct(EXPR_TOKEN, ExprVisitor(parse))
+    }
+
+    // Positional order of `timedelta` arguments, and microseconds per unit.
+    const UNITS: &[(&str, f64)] = &[
+        ("days", 86400e6),
+        ("seconds", 1e6),
+        ("microseconds", 1.0),
+        ("milliseconds", 1e3),
+        ("minutes", 60e6),
+        ("hours", 3600e6),
+        ("weeks", 604800e6),
+    ];
+
+    fn parse(expr: &str) -> Result<Duration, String> {
+        let args = parse_call(expr, &["datetime.timedelta", "timedelta"])?;
+        let mut micros: i128 = 0;
+        for (i, (name, value)) in args.into_iter().enumerate() {
+            let scale = match name {
+                Some(name) => UNITS.iter().find(|(n, _)| *n == name).map(|(_, s)| *s),
+                None => UNITS.get(i).map(|(_, s)| *s),
+            };
+            let scale =
+                scale.ok_or_else(|| format!("unexpected timedelta argument in {}", expr))?;
+            micros += match value.replace('_', "").parse::<i64>() {
+                Ok(v) => v as i128 * scale as i128,
+                Err(_) => {
+                    let v: f64 = value
+                        .parse()
+                        .map_err(|_| format!("invalid number {:?} in {}", value, expr))?;
+                    (v * scale).round() as i128
+                }
+            };
+        }
+        let micros = u64::try_from(micros).map_err(|_| format!("out of range: {}", expr))?;
+        Ok(Duration::from_micros(micros))
+    }
+}
+
+/// `SystemTime` as `datetime.datetime.fromtimestamp(...)`. The timestamp
+/// is written with up to 6 decimal places, so nanoseconds are truncated
+/// to microseconds.
+///
+/// A second argument, like `datetime.timezone.utc`, is ignored when
+/// reading, since the timestamp is absolute.
+pub mod fromtimestamp {
+    use super::*;
+    use std::time::Duration;
+    use std::time::SystemTime;
+
+    pub fn serialize<S: ser::Serializer>(
+        value: &SystemTime,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let (sign, d) = match value.duration_since(SystemTime::UNIX_EPOCH) {
+            Ok(d) => ("", d),
+            Err(e) => ("-", e.duration()),
+        };
+        let mut ts = format!("{}{}", sign, d.as_secs());
+        let micros = d.subsec_micros();
+        if micros > 0 {
+            let frac = format!("{:06}", micros);
+            ts.push('.');
+            ts.push_str(frac.trim_end_matches('0'));
+        }
+        let expr = format!("datetime.datetime.fromtimestamp({})", ts);
+        serializer.serialize_newtype_struct(EXPR_TOKEN, &expr)
+    }
+
+    pub fn deserialize<'de, D: de::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<SystemTime, D::Error> {
+        deserializer.deserialize_newtype_struct(EXPR_TOKEN, ExprVisitor(parse))
+    }
+
+    fn parse(expr: &str) -> Result<SystemTime, String> {
+        let names = ["datetime.datetime.fromtimestamp", "datetime.fromtimestamp"];
+        let args = parse_call(expr, &names)?;
+        let ts = match args.first() {
+            Some((None, ts)) | Some((Some("timestamp"), ts)) if args.len() <= 2 => *ts,
+            _ => return Err(format!("expect a timestamp in {}", expr)),
+        };
+        let invalid = || format!("invalid timestamp {:?} in {}", ts, expr);
+        let (negative, unsigned) = match ts.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, ts),
+        };
+        let (secs, frac) = match unsigned.find('.') {
+            Some(i) => (&unsigned[..i], &unsigned[i + 1..]),
+            None => (unsigned, ""),
+        };
+        let secs: u64 = match secs {
+            "" => 0,
+            s => s.parse().map_err(|_| invalid())?,
+        };
+        if frac.len() > 9 || !frac.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(invalid());
+        }
+        let nanos: u32 = format!("{:0<9}", frac).parse().map_err(|_| invalid())?;
+        let d = Duration::new(secs, nanos);
+        let time = if negative {
+            SystemTime::UNIX_EPOCH.checked_sub(d)
+        } else {
+            SystemTime::UNIX_EPOCH.checked_add(d)
+        };
+        time.ok_or_else(|| format!("out of range: {}", expr))
+    }
+}
+
+/// Visit the expression as a str and parse it with `.0`.
+struct ExprVisitor<F>(F);
+
+impl<'de, T, F: Fn(&str) -> Result<T, String>> de::Visitor<'de> for ExprVisitor<F> {
+    type Value = T;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a datetime expression")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<T, E> {
+        (self.0)(v).map_err(E::custom)
+    }
+
+    fn visit_newtype_struct<D: de::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<T, D::Error> {
+        deserializer.deserialize_str(self)
+    }
+}
+
+/// Split `name(a, k=b)` into `[(None, "a"), (Some("k"), "b")]`. `name`
+/// must be one of `names`. Nested brackets in arguments are kept as-is.
+fn parse_call<'a>(
+    expr: &'a str,
+    names: &[&str],
+) -> Result<Vec<(Option<&'a str>, &'a str)>, String> {
+    let bad = || format!("expect {}(...), got {:?}", names[0], expr);
+    let expr = expr.trim();
+    let open = expr.find('(').ok_or_else(bad)?;
+    if !names.contains(&expr[..open].trim_end()) || !expr.ends_with(')') {
+        return Err(bad());
+    }
+    let inner = &expr[open + 1..expr.len() - 1];
+    let mut args = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in inner.char_indices().chain(Some((inner.len(), ','))) {
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth -= 1,
+            ',' if depth == 0 => {
+                let arg = inner[start..i].trim();
+                start = i + 1;
+                if arg.is_empty() {
+                    // Trailing comma, or `f()`.
+                    continue;
+                }
+                args.push(match arg.split_once('=') {
+                    Some((k, v)) => (Some(k.trim()), v.trim()),
+                    None => (None, arg),
+                });
+            }
+            _ => {}
+        }
+    }
+    Ok(args)
+}
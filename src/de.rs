@@ -1,4 +1,5 @@
 use crate::peek::PeekRead;
+use crate::value;
 use crate::Error;
 use crate::Result;
 use serde::de;
@@ -6,29 +7,311 @@ use serde::de::Deserializer as _;
 use serde::de::IntoDeserializer;
 use serde::de::Visitor;
 use std::borrow::Cow;
+use std::convert::TryFrom;
 use std::io;
 use std::io::Read;
+use std::marker::PhantomData;
+use std::num::ParseIntError;
 
+/// Deserialize from `reader`. Errors are wrapped in [`Error::At`] with the
+/// byte offset where they happened.
 pub fn from_reader<R: Read, T: de::DeserializeOwned>(reader: R) -> Result<T> {
-    let mut de = Deserializer::new(reader);
-    de::Deserialize::deserialize(&mut de)
+    from_reader_with_config(reader, Config::default())
 }
 
-pub fn from_slice<T: de::DeserializeOwned>(slice: &[u8]) -> Result<T> {
-    from_reader(slice)
+/// Deserialize from `slice`. Unlike `from_reader`, str without escapes can
+/// be borrowed from `slice`, like `&'a str` fields.
+pub fn from_slice<'a, T: de::Deserialize<'a>>(slice: &'a [u8]) -> Result<T> {
+    from_slice_with_config(slice, Config::default())
 }
 
-pub fn from_str<T: de::DeserializeOwned>(s: &str) -> Result<T> {
-    from_reader(s.as_bytes())
+pub fn from_str<'a, T: de::Deserialize<'a>>(s: &'a str) -> Result<T> {
+    from_slice(s.as_bytes())
 }
 
-pub struct Deserializer<R> {
+/// Like `from_reader`, but error out with `TrailingData` if anything other
+/// than spaces and comments follows the value.
+pub fn from_reader_strict<R: Read, T: de::DeserializeOwned>(reader: R) -> Result<T> {
+    from_reader_with_config(reader, Config::default().allow_trailing_data(false))
+}
+
+pub fn from_slice_strict<'a, T: de::Deserialize<'a>>(slice: &'a [u8]) -> Result<T> {
+    from_slice_with_config(slice, Config::default().allow_trailing_data(false))
+}
+
+pub fn from_str_strict<'a, T: de::Deserialize<'a>>(s: &'a str) -> Result<T> {
+    from_slice_strict(s.as_bytes())
+}
+
+/// Like `from_reader`, with options set by `config`.
+pub fn from_reader_with_config<R: Read, T: de::DeserializeOwned>(
+    reader: R,
+    config: Config,
+) -> Result<T> {
+    deserialize(Deserializer::new(reader).with_config(config))
+}
+
+pub fn from_slice_with_config<'a, T: de::Deserialize<'a>>(
+    slice: &'a [u8],
+    config: Config,
+) -> Result<T> {
+    deserialize(Deserializer::from_slice(slice).with_config(config))
+}
+
+pub fn from_str_with_config<'a, T: de::Deserialize<'a>>(s: &'a str, config: Config) -> Result<T> {
+    from_slice_with_config(s.as_bytes(), config)
+}
+
+/// Deserialize from `s` as a tuple without brackets, like `1, 'a', True`,
+/// which Python reads as `(1, 'a', True)`. A trailing comma is allowed.
+/// Unlike Python, a single item without a comma is read as a 1-tuple too.
+///
+/// ```
+/// let v: Vec<i32> = serde_pyliteral::from_str_tuple("1, 2, 3").unwrap();
+/// assert_eq!(v, [1, 2, 3]);
+/// let t: (i32, String) = serde_pyliteral::from_str_tuple("1, 'a',").unwrap();
+/// assert_eq!(t, (1, "a".to_string()));
+/// ```
+pub fn from_str_tuple<'a, T: de::Deserialize<'a>>(s: &'a str) -> Result<T> {
+    let mut de = Deserializer::from_slice(s.as_bytes());
+    de.check_bom()
+        .and_then(|_| T::deserialize(BareTupleDeserializer(&mut de)))
+        .map_err(|e| de.error_at(e))
+}
+
+fn deserialize<'de, R: Read, B: Source<'de>, T: de::Deserialize<'de>>(
+    mut de: Deserializer<R, B>,
+) -> Result<T> {
+    de.check_bom().map_err(|e| de.error_at(e))?;
+    let value = de::Deserialize::deserialize(&mut de).map_err(|e| de.error_at(e))?;
+    if !de.config.allow_trailing_data {
+        de.end().map_err(|e| de.error_at(e))?;
+    }
+    Ok(value)
+}
+
+/// Default of `Config::max_depth`, and of `ser::Config::max_serialize_depth`.
+pub(crate) const DEFAULT_MAX_DEPTH: usize = 128;
+
+/// Spellings of `True` and `False`. Even indexes are `True`. Subsets are
+/// picked by `Deserializer::bool_keywords`.
+const BOOL_KEYWORDS: &[&str] = &["True", "False", "true", "false", "1", "0"];
+
+/// Byte order mark of UTF-8. Skipped by `Config::skip_utf8_bom`.
+const UTF8_BOM: &[u8] = b"\xef\xbb\xbf";
+
+/// Byte order marks of unsupported encodings. UTF-32 LE goes first, since
+/// it starts with the mark of UTF-16 LE.
+const OTHER_BOMS: &[(&[u8], &str)] = &[
+    (b"\xff\xfe\x00\x00", "UTF-32 LE"),
+    (b"\x00\x00\xfe\xff", "UTF-32 BE"),
+    (b"\xff\xfe", "UTF-16 LE"),
+    (b"\xfe\xff", "UTF-16 BE"),
+];
+
+/// Prefix of `repr(OrderedDict(...))`.
+const ORDERED_DICT: &[u8] = b"OrderedDict(";
+
+/// Spellings of `None`. The first one is the only one accepted by
+/// `Config::python_only`.
+const NONE_KEYWORDS: &[&str] = &["None", "null"];
+
+/// Bare `inf` and `nan`, as written by `repr(float("inf"))`. Rust's `f64`
+/// parser accepts all of them.
+const INF_NAN_KEYWORDS: &[&str] = &["inf", "+inf", "-inf", "nan", "+nan", "-nan"];
+
+/// Reads Python literals from `R`. `B` is the whole input if it is a
+/// slice, so str can be borrowed from it.
+pub struct Deserializer<R, B = ()> {
     reader: PeekRead<R>,
+    input: B,
     stack: Vec<Frame>,
+    config: Config,
+}
+
+/// Options of [`Deserializer`].
+///
+/// Start with `Config::new()`, then chain setters:
+///
+/// ```
+/// use serde_pyliteral::de::Config;
+///
+/// let config = Config::new().strict_bool(true).allow_trailing_data(false);
+/// assert!(serde_pyliteral::from_str_with_config::<bool>("True", config.clone()).is_ok());
+/// assert!(serde_pyliteral::from_str_with_config::<bool>("1", config.clone()).is_err());
+/// assert!(serde_pyliteral::from_str_with_config::<bool>("True 1", config).is_err());
+/// ```
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct Config {
+    lenient_brace_escapes: bool,
+    stringify_keys: bool,
+    dataclass_repr: bool,
+    max_depth: usize,
+    strict_numbers: bool,
+    strict_bool: bool,
+    python_only: bool,
+    skip_utf8_bom: bool,
+    allow_trailing_data: bool,
+    reject_nan_keys: bool,
+    max_string_len: Option<usize>,
+    max_bytes_len: Option<usize>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            lenient_brace_escapes: false,
+            stringify_keys: false,
+            dataclass_repr: false,
+            max_depth: DEFAULT_MAX_DEPTH,
+            strict_numbers: false,
+            strict_bool: false,
+            python_only: false,
+            skip_utf8_bom: true,
+            allow_trailing_data: true,
+            reject_nan_keys: false,
+            max_string_len: None,
+            max_bytes_len: None,
+        }
+    }
+}
+
+impl Config {
+    /// Options matching `from_str`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read `\\{` and `\\}` in str as `{` and `}`, instead of erroring out
+    /// on unknown escapes. They can appear in text that went through
+    /// f-string or `str.format` escaping. `{{` and `}}` are always read
+    /// as-is.
+    pub fn lenient_brace_escapes(mut self, value: bool) -> Self {
+        self.lenient_brace_escapes = value;
+        self
+    }
+
+    /// Write non-str dict keys as Python literals, like `1` as `"1"` and
+    /// `(1, 2)` as `"(1,2)"`, when a str key is expected, like by
+    /// `HashMap<String, _>` or `serde_json::Value`. This is lossy: `{1: 1,
+    /// '1': 2}` has duplicated keys after the conversion. Targets that
+    /// accept other key types, like `HashMap<i32, _>`, are unaffected.
+    pub fn stringify_keys(mut self, value: bool) -> Self {
+        self.stringify_keys = value;
+        self
+    }
+
+    /// Read structs from `Name(field=value, ...)`, the repr of dataclasses
+    /// and namedtuples, in addition to dicts. `Name` is not checked against
+    /// the struct name, since Python reprs use the class name, which can
+    /// differ.
+    pub fn dataclass_repr(mut self, value: bool) -> Self {
+        self.dataclass_repr = value;
+        self
+    }
+
+    /// Error out with `DepthLimitExceeded` if containers are nested deeper
+    /// than `value`, instead of risking a stack overflow on malicious
+    /// input. Defaults to 128.
+    pub fn max_depth(mut self, value: usize) -> Self {
+        self.max_depth = value;
+        self
+    }
+
+    /// Reject numbers that are not valid Python 3 literals, like `007`,
+    /// `1__0`, `1_`, `+1`, `.e1` or `0x`. By default, such numbers are read
+    /// leniently. A leading `-` is allowed, like `ast.literal_eval`. `inf`
+    /// and `nan` are not affected.
+    pub fn strict_numbers(mut self, value: bool) -> Self {
+        self.strict_numbers = value;
+        self
+    }
+
+    /// Reject `1` and `0` as bool with `TypeMismatch`, since an int where a
+    /// bool is expected is likely a schema error. `True`, `False`, `true` and
+    /// `false` are still accepted. By default, `1` and `0` are read as bool.
+    pub fn strict_bool(mut self, value: bool) -> Self {
+        self.strict_bool = value;
+        self
+    }
+
+    /// Reject the JSON spellings `null`, `true` and `false`, accepting only
+    /// `None`, `True` and `False`, to check that the input is a Python
+    /// literal rather than JSON. The serializer always writes the Python
+    /// spellings, so its output is accepted. Combine with `strict_bool` to
+    /// also reject `1` and `0` as bool.
+    pub fn python_only(mut self, value: bool) -> Self {
+        self.python_only = value;
+        self
+    }
+
+    /// Skip the UTF-8 byte order mark at the start of the input, which
+    /// some editors write to files. Defaults to `true`. Input starting with
+    /// the byte order mark of UTF-16 or UTF-32 is rejected with
+    /// `UnsupportedEncoding` regardless. Only affects the `from_*` functions
+    /// and `Deserializer::into_iter`.
+    pub fn skip_utf8_bom(mut self, value: bool) -> Self {
+        self.skip_utf8_bom = value;
+        self
+    }
+
+    /// Ignore what follows the root value, like `from_str`. If `false`,
+    /// error out with `TrailingData` if anything other than spaces and
+    /// comments follows, like `from_str_strict`. Only affects the
+    /// `from_*_with_config` functions. Defaults to `true`.
+    pub fn allow_trailing_data(mut self, value: bool) -> Self {
+        self.allow_trailing_data = value;
+        self
+    }
+
+    /// Error out with `NaNKey` on dict keys that are `nan`. Since `nan` is
+    /// not equal to itself, such keys cannot be looked up after being
+    /// inserted into a map, and multiple `nan` keys do not replace each
+    /// other. `nan` nested in keys, like `(nan, 1)`, is not checked.
+    pub fn reject_nan_keys(mut self, value: bool) -> Self {
+        self.reject_nan_keys = value;
+        self
+    }
+
+    /// Error out with `LengthLimitExceeded` if a str is longer than `value`
+    /// bytes in UTF-8, checked while reading the literal, so an enormous or
+    /// unterminated literal does not allocate unboundedly. Adjacent
+    /// literals count as one str. Defaults to no limit.
+    pub fn max_string_len(mut self, value: Option<usize>) -> Self {
+        self.max_string_len = value;
+        self
+    }
+
+    /// Like `max_string_len`, but for bytes, including `bytearray(...)`
+    /// and `base64.b64decode(...)`.
+    pub fn max_bytes_len(mut self, value: Option<usize>) -> Self {
+        self.max_bytes_len = value;
+        self
+    }
+}
+
+/// Input that str can be borrowed from, for [`Deserializer`].
+pub trait Source<'de> {
+    /// The whole input, or `None` if nothing can be borrowed.
+    fn slice(&self) -> Option<&'de [u8]>;
+}
+
+impl<'de> Source<'de> for () {
+    fn slice(&self) -> Option<&'de [u8]> {
+        None
+    }
+}
+
+impl<'de> Source<'de> for &'de [u8] {
+    fn slice(&self) -> Option<&'de [u8]> {
+        Some(self)
+    }
 }
 
 struct Frame {
-    right_bracket: u8,
+    /// `None` for a frame that ends at EOF, like the tuple of `from_str_tuple`.
+    right_bracket: Option<u8>,
     count: usize,
     size_hint: Option<usize>,
 }
@@ -37,13 +320,158 @@ impl<R: Read> Deserializer<R> {
     pub fn new(reader: R) -> Self {
         Self {
             reader: PeekRead::from_reader(reader),
+            input: (),
+            stack: Vec::new(),
+            config: Config::default(),
+        }
+    }
+}
+
+impl<'a> Deserializer<&'a [u8], &'a [u8]> {
+    /// Read from `slice`. str without escapes are borrowed from `slice`
+    /// when the visitor accepts `&'a str`.
+    pub fn from_slice(slice: &'a [u8]) -> Self {
+        Self {
+            reader: PeekRead::from_reader(slice),
+            input: slice,
             stack: Vec::new(),
+            config: Config::default(),
+        }
+    }
+}
+
+impl<R: Read, B> Deserializer<R, B> {
+    pub fn with_config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// See [`Config::lenient_brace_escapes`].
+    pub fn lenient_brace_escapes(mut self, value: bool) -> Self {
+        self.config.lenient_brace_escapes = value;
+        self
+    }
+
+    /// See [`Config::stringify_keys`].
+    pub fn stringify_keys(mut self, value: bool) -> Self {
+        self.config.stringify_keys = value;
+        self
+    }
+
+    /// See [`Config::dataclass_repr`].
+    pub fn dataclass_repr(mut self, value: bool) -> Self {
+        self.config.dataclass_repr = value;
+        self
+    }
+
+    /// See [`Config::max_depth`].
+    pub fn max_depth(mut self, value: usize) -> Self {
+        self.config.max_depth = value;
+        self
+    }
+
+    /// See [`Config::strict_numbers`].
+    pub fn strict_numbers(mut self, value: bool) -> Self {
+        self.config.strict_numbers = value;
+        self
+    }
+
+    /// Number of bytes consumed so far.
+    pub fn offset(&self) -> usize {
+        self.reader.offset()
+    }
+
+    /// Detect the kind of the next value without consuming it, to decide
+    /// which type to deserialize it into. Spaces and comments before the
+    /// value are skipped.
+    ///
+    /// ```
+    /// use serde::Deserialize;
+    /// use serde_pyliteral::de::{Deserializer, ValueKind};
+    ///
+    /// let mut de = Deserializer::from_slice(b"[1] {2: 3}");
+    /// assert_eq!(de.peek_kind().unwrap(), ValueKind::List);
+    /// let _ = Vec::<i32>::deserialize(&mut de).unwrap();
+    /// assert_eq!(de.peek_kind().unwrap(), ValueKind::Map);
+    /// ```
+    pub fn peek_kind(&mut self) -> Result<ValueKind> {
+        let kind = match self.peek_type()? {
+            PeekType::Eof => ValueKind::Eof,
+            PeekType::List => ValueKind::List,
+            PeekType::Map => match self.peek_scan(scan_braced_type, PeekType::Map)? {
+                PeekType::Set => ValueKind::Set,
+                _ => ValueKind::Map,
+            },
+            PeekType::Tuple => ValueKind::Tuple,
+            PeekType::Set => ValueKind::Set,
+            PeekType::Str => ValueKind::Str,
+            PeekType::Bytes => ValueKind::Bytes,
+            PeekType::Bool => ValueKind::Bool,
+            PeekType::SignedInt => ValueKind::SignedInt,
+            PeekType::UnsignedInt => ValueKind::UnsignedInt,
+            PeekType::Float => ValueKind::Float,
+            PeekType::Complex => ValueKind::Complex,
+            PeekType::None => ValueKind::None,
+            PeekType::Ellipsis => ValueKind::Ellipsis,
+            PeekType::Unknown(_) => ValueKind::Unknown,
+        };
+        Ok(kind)
+    }
+
+    /// Read values separated by spaces or comments until EOF, like a log
+    /// of one literal per line. Errors are wrapped in [`Error::At`].
+    /// Iteration stops after the first error.
+    ///
+    /// Like in Python, adjacent str or bytes literals are concatenated,
+    /// even across lines. `'a'\n'b'` is read as a single `'ab'`.
+    ///
+    /// ```
+    /// use serde_pyliteral::de::Deserializer;
+    ///
+    /// let input = "1\n2 # comment\n3";
+    /// let values: Vec<i32> = Deserializer::new(input.as_bytes())
+    ///     .into_iter()
+    ///     .collect::<Result<_, _>>()
+    ///     .unwrap();
+    /// assert_eq!(values, [1, 2, 3]);
+    /// ```
+    #[allow(clippy::should_implement_trait)]
+    pub fn into_iter<T: de::DeserializeOwned>(self) -> StreamDeserializer<R, T, B> {
+        StreamDeserializer {
+            de: self,
+            failed: false,
+            output: PhantomData,
+        }
+    }
+
+    /// Check that only spaces and comments are left. Call this after
+    /// deserializing the root value.
+    pub fn end(&mut self) -> Result<()> {
+        match self.peek_byte()? {
+            Some(_) => Err(Error::TrailingData),
+            None => Ok(()),
+        }
+    }
+
+    /// Attach the current offset to `error`.
+    fn error_at(&self, error: Error) -> Error {
+        match error {
+            Error::At { .. } => error,
+            _ => {
+                let (line, column) = self.reader.line_column();
+                Error::At {
+                    offset: self.offset(),
+                    line,
+                    column,
+                    error: Box::new(error),
+                }
+            }
         }
     }
 }
 
 // Delegate to reader.
-impl<R: Read> Deserializer<R> {
+impl<R: Read, B> Deserializer<R, B> {
     fn peek(&mut self, out: &mut Vec<u8>) -> io::Result<()> {
         self.reader.peek(out)
     }
@@ -58,16 +486,65 @@ impl<R: Read> Deserializer<R> {
     fn skip(&mut self, n: usize) -> io::Result<()> {
         self.reader.skip(n)
     }
+
+    fn peek_slice(&mut self, n: usize) -> io::Result<&[u8]> {
+        self.reader.peek_slice(n)
+    }
 }
 
-impl<R: Read> Read for Deserializer<R> {
+impl<R: Read, B> Read for Deserializer<R, B> {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
         self.reader.read(buf)
     }
 }
 
 // Helper methods.
-impl<R: Read> Deserializer<R> {
+impl<R: Read, B> Deserializer<R, B> {
+    /// Spellings of bool accepted by `strict_bool` and `python_only`.
+    fn bool_keywords(&self) -> &'static [&'static str] {
+        match (self.config.strict_bool, self.config.python_only) {
+            (false, false) => BOOL_KEYWORDS,
+            (true, false) => &BOOL_KEYWORDS[..4],
+            (false, true) => &["True", "False", "1", "0"],
+            (true, true) => &BOOL_KEYWORDS[..2],
+        }
+    }
+
+    /// Skip the UTF-8 byte order mark, and error out on byte order marks
+    /// of other encodings. Does nothing after the start of the input.
+    fn check_bom(&mut self) -> Result<()> {
+        if self.offset() != 0 {
+            return Ok(());
+        }
+        let mut head = vec![0; 4];
+        self.peek(&mut head)?;
+        if head.starts_with(UTF8_BOM) {
+            if self.config.skip_utf8_bom {
+                self.skip(UTF8_BOM.len())?;
+            }
+        } else if let Some((_, name)) = OTHER_BOMS.iter().find(|(bom, _)| head.starts_with(bom)) {
+            return Err(Error::UnsupportedEncoding(name));
+        }
+        Ok(())
+    }
+
+    /// Spellings of `None` accepted by `python_only`.
+    fn none_keywords(&self) -> &'static [&'static str] {
+        if self.config.python_only {
+            &NONE_KEYWORDS[..1]
+        } else {
+            NONE_KEYWORDS
+        }
+    }
+
+    /// Error out if the next dict key is `nan`, with `reject_nan_keys`.
+    fn check_nan_key(&mut self) -> Result<()> {
+        if self.config.reject_nan_keys && self.peek_keyword(&INF_NAN_KEYWORDS[3..])?.is_some() {
+            return Err(Error::NaNKey);
+        }
+        Ok(())
+    }
+
     fn peek_byte(&mut self) -> crate::Result<Option<u8>> {
         self.skip_spaces_and_comments()?;
         let mut v = vec![0];
@@ -76,19 +553,56 @@ impl<R: Read> Deserializer<R> {
     }
 
     fn read_number_string(&mut self) -> crate::Result<String> {
-        self.skip_spaces_and_comments()?;
+        if let Some(i) = self.match_keyword(INF_NAN_KEYWORDS)? {
+            return Ok(INF_NAN_KEYWORDS[i].to_string());
+        }
+        if self.config.strict_numbers {
+            // Keep the original text, including `_`, for validation.
+            self.skip_spaces_and_comments()?;
+            let raw = self.read_while(|b, raw: &mut String| {
+                let accept = match b {
+                    b'0'..=b'9' | b'a'..=b'f' | b'A'..=b'F' => true,
+                    b'x' | b'X' | b'o' | b'O' | b'_' | b'.' => true,
+                    b'+' | b'-' => {
+                        let hex = raw.trim_start_matches('-').get(..2);
+                        raw.is_empty()
+                            || (raw.ends_with(&['e', 'E'][..]) && !matches!(hex, Some("0x" | "0X")))
+                    }
+                    _ => false,
+                };
+                if accept {
+                    raw.push(b as char);
+                }
+                Ok::<_, Error>(accept)
+            })?;
+            if !raw.is_empty() && !is_python_number(&raw) {
+                return Err(Error::ParseNumber(raw));
+            }
+            let normalized = raw.replace('_', "").to_ascii_lowercase();
+            return Ok(normalized);
+        }
         self.read_while(|b, s: &mut String| {
-            if (b == b'+' || b == b'-') && (s.is_empty() || s.ends_with('e')) {
-                s.push(b as char);
-                Ok(true)
-            } else if b >= b'0' && b <= b'9' {
-                s.push(b as char);
-                Ok(true)
-            } else if b == b'e' && !s.contains('e') {
-                s.push(b as char);
-                Ok(true)
-            } else if b == b'.' && !s.contains('.') && !s.contains('e') {
-                s.push(b as char);
+            let unsigned = s.trim_start_matches(&['+', '-'][..]);
+            let has_radix_prefix = matches!(
+                unsigned.as_bytes().get(1),
+                Some(b'x' | b'X' | b'o' | b'O' | b'b' | b'B')
+            );
+            if has_radix_prefix {
+                // Digits are validated later by `from_str_radix`.
+                if b.is_ascii_hexdigit() {
+                    s.push(b as char);
+                    Ok(true)
+                } else {
+                    Ok(b == b'_')
+                }
+            } else if (unsigned == "0" && matches!(b, b'x' | b'X' | b'o' | b'O' | b'b' | b'B'))
+                || ((b == b'+' || b == b'-') && (s.is_empty() || s.ends_with('e')))
+                || b.is_ascii_digit()
+                || ((b == b'e' || b == b'E') && !s.contains('e'))
+                || (b == b'.' && !s.contains('.') && !s.contains('e'))
+            {
+                // Normalize "E" to "e".
+                s.push(b.to_ascii_lowercase() as char);
                 Ok(true)
             } else if b == b'_' {
                 Ok(true)
@@ -99,18 +613,136 @@ impl<R: Read> Deserializer<R> {
     }
 
     fn read_string(&mut self) -> crate::Result<String> {
+        // Parenthesized, like `('a '\n 'b')` written by pprint. Count the
+        // parentheses instead of recursing, so deep nesting is harmless.
+        let mut depth = 0;
+        while self.peek_byte()? == Some(b'(') {
+            self.skip(1)?;
+            depth += 1;
+        }
+        let mut out = self.read_string_literal()?;
+        // Adjacent literals are concatenated, like "a" 'b'.
+        while self.peek_str_prefix()?.is_some() {
+            out.push_str(&self.read_string_literal()?);
+            check_len(out.len(), self.config.max_string_len, "str")?;
+        }
+        self.reject_adjacent_prefix()?;
+        for _ in 0..depth {
+            self.expect_byte(b')', "str")?;
+        }
+        Ok(out)
+    }
+
+    /// Like `read_string`, but borrow from the input if the str is a single
+    /// literal without escapes.
+    fn read_str<'de>(&mut self) -> crate::Result<Cow<'de, str>>
+    where
+        B: Source<'de>,
+    {
+        let input = match self.input.slice() {
+            Some(input) => input,
+            None => return self.read_string().map(Cow::Owned),
+        };
+        let prefix = match self.peek_str_prefix()? {
+            Some(len) => len,
+            None => return self.read_string().map(Cow::Owned),
+        };
+        let quote = input[self.offset() + prefix];
+        let rest = &input[self.offset() + prefix + 1..];
+        let len = rest
+            .iter()
+            .position(|&b| b == quote || b == b'\\' || b == b'\n' || b == b'\r');
+        let borrowed = match len {
+            // `''` followed by another quote starts a triple-quoted str.
+            Some(0) if rest.get(1) == Some(&quote) => None,
+            Some(len) if rest[len] == quote => std::str::from_utf8(&rest[..len]).ok(),
+            _ => None,
+        };
+        let borrowed = match borrowed {
+            Some(s) => s,
+            None => return self.read_string().map(Cow::Owned),
+        };
+        check_len(borrowed.len(), self.config.max_string_len, "str")?;
+        self.skip(prefix + borrowed.len() + 2)?;
+        if self.peek_str_prefix()?.is_some() {
+            // Adjacent literals are concatenated.
+            let mut out = borrowed.to_string();
+            out.push_str(&self.read_string()?);
+            Ok(Cow::Owned(out))
+        } else {
+            self.reject_adjacent_prefix()?;
+            Ok(Cow::Borrowed(borrowed))
+        }
+    }
+
+    /// Raise an error if the next value is a literal with a prefix that
+    /// cannot be concatenated to the str or bytes just read, like `r` in
+    /// `'a' r'b'`. Python would concatenate it, so stopping before it would
+    /// silently drop part of the value.
+    fn reject_adjacent_prefix(&mut self) -> Result<()> {
         self.skip_spaces_and_comments()?;
+        let mut v = vec![0u8; 3];
+        self.peek(&mut v)?;
+        let len = v
+            .iter()
+            .take(2)
+            .take_while(|b| b.is_ascii_alphabetic())
+            .count();
+        match v.get(len) {
+            Some(b'"') | Some(b'\'') if len > 0 => {
+                let prefix = String::from_utf8_lossy(&v[..len]);
+                let message = format!("adjacent literal with unsupported prefix {:?}", prefix);
+                Err(Error::ParseString(message.into()))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn read_string_literal(&mut self) -> crate::Result<String> {
+        // Python 3 ignores the `u` prefix, kept for Python 2 compatibility.
+        if self.peek_str_prefix()? == Some(1) {
+            self.skip(1)?;
+        }
 
         struct State {
             parsing: ParsingState,
             out: Vec<u8>,
             quote: u8,
+            // Whether the string uses triple quotes.
+            triple: bool,
+            // Unescaped quotes seen so far in a triple-quoted string.
+            pending_quotes: usize,
+            // A `\uXXXX` high surrogate waiting for its low surrogate.
+            high_surrogate: Option<u32>,
         }
         enum ParsingState {
             None,
+            Opening {
+                count: usize,
+            },
             Parsing,
             ParsingSlash,
-            ParsingUnicode4 { value: u32, count: usize },
+            // `\` followed by `\r`, which might be followed by `\n`.
+            ParsingSlashCr,
+            ParsingOctal {
+                value: u32,
+                count: usize,
+            },
+            // \xhh, \uhhhh or \Uhhhhhhhh.
+            ParsingHex {
+                value: u32,
+                count: usize,
+                len: usize,
+            },
+            // `\` and `u` of the low surrogate after a high surrogate.
+            ParsingLowSurrogate {
+                high: u32,
+                count: usize,
+            },
+            ParsingNameStart,
+            ParsingName {
+                name: Vec<u8>,
+            },
             Closed,
         }
         impl Default for State {
@@ -119,47 +751,133 @@ impl<R: Read> Deserializer<R> {
                     parsing: ParsingState::None,
                     out: Vec::new(),
                     quote: 0,
+                    triple: false,
+                    pending_quotes: 0,
+                    high_surrogate: None,
+                }
+            }
+        }
+        impl State {
+            fn parse_unescaped(&mut self, b: u8) -> crate::Result<bool> {
+                if self.triple {
+                    if b == self.quote {
+                        self.pending_quotes += 1;
+                        if self.pending_quotes == 3 {
+                            self.parsing = ParsingState::Closed;
+                        }
+                        return Ok(true);
+                    }
+                    // Less than 3 quotes do not close the string.
+                    for _ in 0..self.pending_quotes {
+                        self.out.push(self.quote);
+                    }
+                    self.pending_quotes = 0;
+                }
+                match b {
+                    b'\\' => self.parsing = ParsingState::ParsingSlash,
+                    b if b == self.quote => self.parsing = ParsingState::Closed,
+                    _ => self.out.push(b),
                 }
+                Ok(true)
+            }
+
+            fn push_char(&mut self, value: u32) -> crate::Result<()> {
+                let value = match self.high_surrogate.take() {
+                    Some(high) if (0xdc00..0xe000).contains(&value) => {
+                        0x10000 + ((high - 0xd800) << 10) + (value - 0xdc00)
+                    }
+                    Some(high) => return Err(unpaired_surrogate(high)),
+                    None if (0xd800..0xe000).contains(&value) => {
+                        return Err(unpaired_surrogate(value))
+                    }
+                    None => value,
+                };
+                let ch = match char::from_u32(value) {
+                    None => {
+                        return Err(Error::ParseString(
+                            format!("not utf8 char: {}", value).into(),
+                        ))
+                    }
+                    Some(ch) => ch,
+                };
+                self.out.extend_from_slice(ch.to_string().as_bytes());
+                Ok(())
             }
         }
 
-        let state = self.read_while(|b, s: &mut State| match s.parsing {
+        let lenient_brace_escapes = self.config.lenient_brace_escapes;
+        let parse = |b: u8, s: &mut State| match s.parsing {
             ParsingState::None => {
                 if b == b'"' || b == b'\'' {
                     s.quote = b;
-                    s.parsing = ParsingState::Parsing;
+                    s.parsing = ParsingState::Opening { count: 1 };
                     Ok(true)
                 } else {
                     Ok(false)
                 }
             }
-            ParsingState::Parsing => match b {
-                b'\\' => {
-                    s.parsing = ParsingState::ParsingSlash;
+            ParsingState::Opening { ref mut count } => {
+                if b == s.quote {
+                    *count += 1;
+                    if *count == 3 {
+                        s.triple = true;
+                        s.parsing = ParsingState::Parsing;
+                    }
                     Ok(true)
-                }
-                b if b == s.quote => {
+                } else if *count == 2 {
+                    // Empty string. `b` is not part of it.
                     s.parsing = ParsingState::Closed;
-                    Ok(true)
-                }
-                _ => {
-                    s.out.push(b);
-                    Ok(true)
+                    Ok(false)
+                } else {
+                    s.parsing = ParsingState::Parsing;
+                    s.parse_unescaped(b)
                 }
-            },
+            }
+            ParsingState::Parsing => s.parse_unescaped(b),
             ParsingState::ParsingSlash => {
                 let escape = match b {
-                    b'0' => 0,
                     b'\\' => b'\\',
                     b'"' => b'"',
                     b'\'' => b'\'',
+                    b'a' => 0x07,
+                    b'b' => 0x08,
+                    b'f' => 0x0c,
                     b'n' => b'\n',
                     b'r' => b'\r',
                     b't' => b'\t',
-                    b'u' => {
-                        s.parsing = ParsingState::ParsingUnicode4 { count: 0, value: 0 };
+                    b'v' => 0x0b,
+                    b'0'..=b'7' => {
+                        let value = (b - b'0') as u32;
+                        s.parsing = ParsingState::ParsingOctal { count: 1, value };
+                        return Ok(true);
+                    }
+                    b'x' | b'u' | b'U' => {
+                        let len = match b {
+                            b'x' => 2,
+                            b'u' => 4,
+                            _ => 8,
+                        };
+                        s.parsing = ParsingState::ParsingHex {
+                            count: 0,
+                            value: 0,
+                            len,
+                        };
+                        return Ok(true);
+                    }
+                    b'N' => {
+                        s.parsing = ParsingState::ParsingNameStart;
+                        return Ok(true);
+                    }
+                    // Line continuation.
+                    b'\n' => {
+                        s.parsing = ParsingState::Parsing;
+                        return Ok(true);
+                    }
+                    b'\r' => {
+                        s.parsing = ParsingState::ParsingSlashCr;
                         return Ok(true);
                     }
+                    b'{' | b'}' if lenient_brace_escapes => b,
                     _ => {
                         return Err(Error::ParseString(
                             format!("unknown escape: \\{}", b as char).into(),
@@ -170,112 +888,310 @@ impl<R: Read> Deserializer<R> {
                 s.parsing = ParsingState::Parsing;
                 Ok(true)
             }
-            ParsingState::ParsingUnicode4 {
+            ParsingState::ParsingSlashCr => {
+                s.parsing = ParsingState::Parsing;
+                if b == b'\n' {
+                    Ok(true)
+                } else {
+                    s.parse_unescaped(b)
+                }
+            }
+            ParsingState::ParsingOctal {
+                ref mut count,
+                ref mut value,
+            } => match oct_to_u3(b) {
+                Some(v) => {
+                    *value = (*value << 3) | (v as u32);
+                    *count += 1;
+                    if *count == 3 {
+                        let value = *value;
+                        s.push_char(value)?;
+                        s.parsing = ParsingState::Parsing;
+                    }
+                    Ok(true)
+                }
+                None => {
+                    // Less than 3 octal digits. `b` is not part of the escape.
+                    let value = *value;
+                    s.push_char(value)?;
+                    s.parsing = ParsingState::Parsing;
+                    s.parse_unescaped(b)
+                }
+            },
+            ParsingState::ParsingHex {
                 ref mut count,
                 ref mut value,
+                len,
             } => {
                 let v = hex_to_u4(b).ok_or_else(|| {
                     Error::ParseString(format!("unknown hex: \\{}", b as char).into())
                 })?;
-                *value = ((*value as u32) << 4) | (v as u32);
+                *value = (*value << 4) | (v as u32);
                 *count += 1;
-                if *count == 4 {
-                    let ch = match char::from_u32(*value) {
-                        None => {
-                            return Err(Error::ParseString(
-                                format!("not utf8 char: {}", *value).into(),
-                            ))
+                if *count == len {
+                    let value = *value;
+                    if len == 4 && (0xd800..0xdc00).contains(&value) && s.high_surrogate.is_none() {
+                        s.parsing = ParsingState::ParsingLowSurrogate {
+                            high: value,
+                            count: 0,
+                        };
+                    } else {
+                        s.push_char(value)?;
+                        s.parsing = ParsingState::Parsing;
+                    }
+                }
+                Ok(true)
+            }
+            ParsingState::ParsingLowSurrogate {
+                high,
+                ref mut count,
+            } => {
+                match (*count, b) {
+                    (0, b'\\') => *count += 1,
+                    (1, b'u') => {
+                        s.high_surrogate = Some(high);
+                        s.parsing = ParsingState::ParsingHex {
+                            count: 0,
+                            value: 0,
+                            len: 4,
                         }
-                        Some(ch) => ch,
-                    };
-                    s.out.extend_from_slice(ch.to_string().as_bytes());
+                    }
+                    _ => return Err(unpaired_surrogate(high)),
+                }
+                Ok(true)
+            }
+            ParsingState::ParsingNameStart => {
+                if b != b'{' {
+                    return Err(Error::ParseString("missing '{' after \\N".into()));
+                }
+                s.parsing = ParsingState::ParsingName { name: Vec::new() };
+                Ok(true)
+            }
+            ParsingState::ParsingName { ref mut name } => {
+                if b == b'}' {
+                    let name = String::from_utf8_lossy(name).into_owned();
+                    let ch = unicode_name_to_char(&name)?;
+                    s.push_char(ch as u32)?;
                     s.parsing = ParsingState::Parsing;
+                } else if b == s.quote || b == b'\n' {
+                    return Err(Error::ParseString("unterminated \\N{...}".into()));
+                } else {
+                    name.push(b);
                 }
                 Ok(true)
             }
             ParsingState::Closed => Ok(false),
+        };
+        let max_len = self.config.max_string_len;
+        let state = self.read_while(|b, s: &mut State| -> crate::Result<bool> {
+            let more = parse(b, s)?;
+            check_len(s.out.len(), max_len, "str")?;
+            Ok(more)
         })?;
         match state.parsing {
-            ParsingState::Closed => {
+            ParsingState::Closed | ParsingState::Opening { count: 2 } => {
                 let out = String::from_utf8(state.out)
                     .map_err(|e| Error::ParseString(format!("not utf8: {}", e).into()))?;
                 Ok(out)
             }
             ParsingState::None => self.type_mismatch("str"),
-            _ => Err(Error::ParseString("incomplete str".into())),
+            _ => Err(Error::IncompleteStr),
         }
     }
 
     fn read_bytes(&mut self) -> crate::Result<Vec<u8>> {
         self.skip_spaces_and_comments()?;
-
-        struct State {
-            parsing: ParsingState,
-            out: Vec<u8>,
-            quote: u8,
+        let mut buf = vec![0; 10];
+        self.peek(&mut buf)?;
+        if buf == b"bytearray(" {
+            // bytearray(b'...') or bytearray()
+            self.skip(buf.len())?;
+            let out = match self.peek_byte()? {
+                Some(b')') => Vec::new(),
+                _ => self.nested(|de| de.read_bytes())?,
+            };
+            self.expect_byte(b')', "bytearray")?;
+            return Ok(out);
         }
-        enum ParsingState {
-            None,
-            BPrefix,
-            Parsing,
-            ParsingSlash,
-            ParsingHex { value: u8, count: usize },
-            Closed,
+        if self.peek_byte()? == Some(b'(') {
+            // Parenthesized, like `(b'a '\n b'b')` written by pprint.
+            self.skip(1)?;
+            let out = self.nested(|de| de.read_bytes())?;
+            self.expect_byte(b')', "bytes")?;
+            return Ok(out);
         }
-        impl Default for State {
-            fn default() -> Self {
-                State {
-                    parsing: ParsingState::None,
+        if let Some(out) = self.maybe_read_base64()? {
+            check_len(out.len(), self.config.max_bytes_len, "bytes")?;
+            return Ok(out);
+        }
+        let mut out = self.read_bytes_literal()?;
+        // Adjacent literals are concatenated, like b"a" b'b'.
+        loop {
+            self.skip_spaces_and_comments()?;
+            let mut buf = vec![0; 3];
+            self.peek(&mut buf)?;
+            match bytes_prefix_len(&buf) {
+                Some(_) => out.extend(self.read_bytes_literal()?),
+                None => break,
+            }
+            check_len(out.len(), self.config.max_bytes_len, "bytes")?;
+        }
+        self.reject_adjacent_prefix()?;
+        Ok(out)
+    }
+
+    /// Read `base64.b64decode("...")` if it is the next token.
+    #[cfg(feature = "base64")]
+    fn maybe_read_base64(&mut self) -> crate::Result<Option<Vec<u8>>> {
+        use base64::Engine;
+        const PREFIX: &[u8] = b"base64.b64decode(";
+        self.skip_spaces_and_comments()?;
+        let mut buf = vec![0; PREFIX.len()];
+        self.peek(&mut buf)?;
+        if buf != PREFIX {
+            return Ok(None);
+        }
+        self.skip(PREFIX.len())?;
+        let encoded = self.read_string()?;
+        if self.peek_byte()? != Some(b')') {
+            return self.type_mismatch(")");
+        }
+        self.skip(1)?;
+        let decoded = base64::engine::general_purpose::STANDARD
+            .decode(encoded)
+            .map_err(|e| Error::ParseBytes(format!("invalid base64: {}", e).into()))?;
+        Ok(Some(decoded))
+    }
+
+    #[cfg(not(feature = "base64"))]
+    fn maybe_read_base64(&mut self) -> crate::Result<Option<Vec<u8>>> {
+        Ok(None)
+    }
+
+    fn read_bytes_literal(&mut self) -> crate::Result<Vec<u8>> {
+        self.skip_spaces_and_comments()?;
+
+        struct State {
+            parsing: ParsingState,
+            out: Vec<u8>,
+            quote: u8,
+            // Whether the literal is raw, like `rb"\d"`.
+            raw: bool,
+        }
+        enum ParsingState {
+            None,
+            // `b`, `r`, or both in either order, case-insensitive.
+            Prefix { bytes: bool, raw: bool },
+            Parsing,
+            ParsingSlash,
+            // `\` in a raw literal. The next byte is kept as-is.
+            ParsingRawSlash,
+            // `\` followed by `\r`, which might be followed by `\n`.
+            ParsingSlashCr,
+            ParsingHex { value: u8, count: usize },
+            ParsingOctal { value: u16, count: usize },
+            Closed,
+        }
+        impl Default for State {
+            fn default() -> Self {
+                State {
+                    parsing: ParsingState::None,
                     out: Vec::new(),
                     quote: 0,
+                    raw: false,
                 }
             }
         }
-        let state = self.read_while(|b, s: &mut State| match s.parsing {
-            ParsingState::None => {
-                if b == b'b' {
-                    s.parsing = ParsingState::BPrefix;
-                    Ok(true)
-                } else {
-                    Ok(false)
+        impl State {
+            fn parse_unescaped(&mut self, b: u8) -> crate::Result<bool> {
+                match b {
+                    b'\\' if self.raw => {
+                        self.out.push(b);
+                        self.parsing = ParsingState::ParsingRawSlash;
+                    }
+                    b'\\' => self.parsing = ParsingState::ParsingSlash,
+                    b if b == self.quote => self.parsing = ParsingState::Closed,
+                    _ => self.out.push(b),
                 }
+                Ok(true)
             }
-            ParsingState::BPrefix => {
-                if b == b'"' || b == b'\'' {
-                    s.quote = b;
-                    s.parsing = ParsingState::Parsing;
-                    Ok(true)
-                } else {
-                    Ok(false)
+
+            fn push_octal(&mut self, value: u16) -> crate::Result<()> {
+                if value > 0o377 {
+                    return Err(Error::ParseBytes(
+                        format!("octal escape out of range: \\{:o}", value).into(),
+                    ));
                 }
+                self.out.push(value as u8);
+                Ok(())
+            }
+        }
+        let parse = |b: u8, s: &mut State| match s.parsing {
+            ParsingState::None => {
+                let (bytes, raw) = match b {
+                    b'b' | b'B' => (true, false),
+                    b'r' | b'R' => (false, true),
+                    _ => return Ok(false),
+                };
+                s.parsing = ParsingState::Prefix { bytes, raw };
+                Ok(true)
             }
-            ParsingState::Parsing => match b {
-                b'\\' => {
-                    s.parsing = ParsingState::ParsingSlash;
+            ParsingState::Prefix {
+                ref mut bytes,
+                ref mut raw,
+            } => match b {
+                b'b' | b'B' if !*bytes => {
+                    *bytes = true;
                     Ok(true)
                 }
-                b if b == s.quote => {
-                    s.parsing = ParsingState::Closed;
+                b'r' | b'R' if !*raw => {
+                    *raw = true;
                     Ok(true)
                 }
-                _ => {
-                    s.out.push(b);
+                b'"' | b'\'' if *bytes => {
+                    s.quote = b;
+                    s.raw = *raw;
+                    s.parsing = ParsingState::Parsing;
                     Ok(true)
                 }
+                _ => Ok(false),
             },
+            ParsingState::Parsing => s.parse_unescaped(b),
+            ParsingState::ParsingRawSlash => {
+                s.out.push(b);
+                s.parsing = ParsingState::Parsing;
+                Ok(true)
+            }
             ParsingState::ParsingSlash => {
                 let escape = match b {
-                    b'0' => 0,
                     b'\\' => b'\\',
                     b'"' => b'"',
                     b'\'' => b'\'',
+                    b'a' => 0x07,
+                    b'b' => 0x08,
+                    b'f' => 0x0c,
                     b'n' => b'\n',
                     b'r' => b'\r',
                     b't' => b'\t',
+                    b'v' => 0x0b,
+                    b'0'..=b'7' => {
+                        let value = (b - b'0') as u16;
+                        s.parsing = ParsingState::ParsingOctal { count: 1, value };
+                        return Ok(true);
+                    }
                     b'x' => {
                         s.parsing = ParsingState::ParsingHex { count: 0, value: 0 };
                         return Ok(true);
                     }
+                    // Line continuation.
+                    b'\n' => {
+                        s.parsing = ParsingState::Parsing;
+                        return Ok(true);
+                    }
+                    b'\r' => {
+                        s.parsing = ParsingState::ParsingSlashCr;
+                        return Ok(true);
+                    }
                     _ => {
                         return Err(Error::ParseBytes(
                             format!("unknown escape: \\{}", b as char).into(),
@@ -286,6 +1202,14 @@ impl<R: Read> Deserializer<R> {
                 s.parsing = ParsingState::Parsing;
                 Ok(true)
             }
+            ParsingState::ParsingSlashCr => {
+                s.parsing = ParsingState::Parsing;
+                if b == b'\n' {
+                    Ok(true)
+                } else {
+                    s.parse_unescaped(b)
+                }
+            }
             ParsingState::ParsingHex {
                 ref mut count,
                 ref mut value,
@@ -301,25 +1225,123 @@ impl<R: Read> Deserializer<R> {
                 }
                 Ok(true)
             }
+            ParsingState::ParsingOctal {
+                ref mut count,
+                ref mut value,
+            } => match oct_to_u3(b) {
+                Some(v) => {
+                    *value = (*value << 3) | (v as u16);
+                    *count += 1;
+                    if *count == 3 {
+                        let value = *value;
+                        s.push_octal(value)?;
+                        s.parsing = ParsingState::Parsing;
+                    }
+                    Ok(true)
+                }
+                None => {
+                    // Less than 3 octal digits. `b` is not part of the escape.
+                    let value = *value;
+                    s.push_octal(value)?;
+                    s.parsing = ParsingState::Parsing;
+                    s.parse_unescaped(b)
+                }
+            },
             ParsingState::Closed => Ok(false),
+        };
+        let max_len = self.config.max_bytes_len;
+        let state = self.read_while(|b, s: &mut State| -> crate::Result<bool> {
+            let more = parse(b, s)?;
+            check_len(s.out.len(), max_len, "bytes")?;
+            Ok(more)
         })?;
         match state.parsing {
             ParsingState::Closed => Ok(state.out),
-            ParsingState::None => self.type_mismatch("bytes"),
-            _ => Err(Error::ParseString("incomplete str".into())),
+            ParsingState::None | ParsingState::Prefix { .. } => self.type_mismatch("bytes"),
+            _ => Err(Error::IncompleteStr),
+        }
+    }
+
+    /// Read a call expression, like `datetime.timedelta(seconds=1)`, as a
+    /// str. Brackets in arguments must be balanced. Quotes are not special.
+    #[cfg(feature = "datetime")]
+    fn read_call_expr(&mut self) -> Result<String> {
+        if !self.peek_byte()?.is_some_and(|b| b.is_ascii_alphabetic()) {
+            return self.type_mismatch("call expression");
+        }
+        #[derive(Default)]
+        struct State {
+            out: Vec<u8>,
+            depth: usize,
+            closed: bool,
+        }
+        let state = self.read_while(|b, s: &mut State| {
+            let accept = match b {
+                _ if s.closed => false,
+                b'(' | b'[' | b'{' => {
+                    s.depth += 1;
+                    true
+                }
+                b')' | b']' | b'}' if s.depth > 0 => {
+                    s.depth -= 1;
+                    s.closed = s.depth == 0;
+                    true
+                }
+                _ if s.depth > 0 => true,
+                _ => b.is_ascii_alphanumeric() || b == b'_' || b == b'.',
+            };
+            if accept {
+                s.out.push(b);
+            }
+            Ok::<_, io::Error>(accept)
+        })?;
+        let out = String::from_utf8_lossy(&state.out).into_owned();
+        if !state.closed {
+            return Err(Error::Generic(format!(
+                "incomplete call expression: {}",
+                out
+            )));
         }
+        Ok(out)
     }
 
+    /// Read `()`, or `...`, which is also read as unit.
     fn read_unit(&mut self) -> crate::Result<()> {
-        self.skip_spaces_and_comments()?;
-        let mut buf = vec![0; 2];
+        match self.match_keyword(&["()", "..."])? {
+            Some(_) => Ok(()),
+            None => self.type_mismatch("()"),
+        }
+    }
+
+    /// Check if the next token is one of `candidates`, followed by a word
+    /// boundary. For example, `None` matches `None,` but not `Nonesuch`,
+    /// and `1` matches `1]` but not `10` or `1.5`. Return the index of the
+    /// first matched candidate. Nothing is consumed.
+    fn peek_keyword(&mut self, candidates: &[&str]) -> Result<Option<usize>> {
+        let max_len = candidates.iter().map(|c| c.len()).max().unwrap_or_default();
+        // One more byte for the boundary check.
+        let mut buf = vec![0; max_len + 1];
         self.peek(&mut buf)?;
-        if buf != b"()" {
-            return self.type_mismatch("()");
-        } else {
-            self.skip(2)?;
+        for (i, candidate) in candidates.iter().enumerate() {
+            let candidate = candidate.as_bytes();
+            if buf.get(..candidate.len()) == Some(candidate)
+                && !buf.get(candidate.len()).is_some_and(|&b| is_word_byte(b))
+            {
+                return Ok(Some(i));
+            }
         }
-        Ok(())
+        Ok(None)
+    }
+
+    /// Skip spaces, then consume a keyword matched by `peek_keyword`.
+    /// Return the index of the matched candidate.
+    pub(crate) fn match_keyword(&mut self, candidates: &[&str]) -> Result<Option<usize>> {
+        self.skip_spaces_and_comments()?;
+        let matched = self.peek_keyword(candidates)?;
+        if let Some(i) = matched {
+            self.skip(candidates[i].len())?;
+        }
+        Ok(matched)
     }
 
     fn skip_spaces_and_comments(&mut self) -> io::Result<()> {
@@ -329,6 +1351,8 @@ impl<R: Read> Deserializer<R> {
                     *in_comment = true;
                     true
                 }
+                // Space, \t, \n, \x0c and \r. Like Python's tokenizer, \x0b is
+                // not whitespace.
                 (_, false) => (b as char).is_ascii_whitespace(),
                 (b'\n', true) => {
                     *in_comment = false;
@@ -347,12 +1371,26 @@ impl<R: Read> Deserializer<R> {
             0 => PeekType::Eof,
             b'[' => PeekType::List,
             b'{' => PeekType::Map,
-            b'(' => PeekType::Tuple,
+            b'(' if self.peek_is_complex()? => PeekType::Complex,
+            b'(' => self.peek_scan(scan_parenthesized_type, PeekType::Tuple)?,
+            b'c' if self.peek_is_complex()? => PeekType::Complex,
             b'\'' | b'"' => PeekType::Str,
+            b'u' | b'U' if self.peek_str_prefix()?.is_some() => PeekType::Str,
             b'b' => PeekType::Bytes,
-            b'T' | b'F' | b't' | b'f' => PeekType::Bool,
-            b'0'..=b'9' | b'+' | b'-' => {
-                if self.peek_is_float_or_int()? {
+            b'B' | b'r' | b'R' if self.peek_bytes_prefix()? => PeekType::Bytes,
+            b'f' | b's' if self.peek_set_constructor()? => PeekType::Set,
+            b'T' | b'F' | b't' | b'f' if self.peek_keyword(self.bool_keywords())?.is_some() => {
+                PeekType::Bool
+            }
+            b'i' | b'n' if self.peek_keyword(INF_NAN_KEYWORDS)?.is_some() => PeekType::Float,
+            // Not a float like `.5`.
+            b'.' if self.peek_keyword(&["..."])?.is_some() => PeekType::Ellipsis,
+            b'0'..=b'9' | b'+' | b'-' | b'.' => {
+                if self.peek_is_complex()? {
+                    PeekType::Complex
+                } else if self.peek_keyword(INF_NAN_KEYWORDS)?.is_some()
+                    || self.peek_is_float_or_int()?
+                {
                     PeekType::Float
                 } else if b == b'-' {
                     PeekType::SignedInt
@@ -360,7 +1398,8 @@ impl<R: Read> Deserializer<R> {
                     PeekType::UnsignedInt
                 }
             }
-            b'N' => PeekType::None,
+            b'O' if self.peek_ordered_dict()? => PeekType::Map,
+            b'N' | b'n' if self.peek_keyword(self.none_keywords())?.is_some() => PeekType::None,
             _ => {
                 let mut v = vec![b' '; 10];
                 self.peek(&mut v)?;
@@ -370,6 +1409,119 @@ impl<R: Read> Deserializer<R> {
         Ok(peek_type)
     }
 
+    /// Length of the prefix of the next value if it is a str literal, like
+    /// 1 for `u"..."`.
+    fn peek_str_prefix(&mut self) -> Result<Option<usize>> {
+        self.skip_spaces_and_comments()?;
+        let mut v = vec![0u8; 2];
+        self.peek(&mut v)?;
+        Ok(str_prefix_len(&v))
+    }
+
+    /// Check if the next value is a bytes literal with a prefix like `rb`.
+    fn peek_bytes_prefix(&mut self) -> Result<bool> {
+        let mut v = vec![0u8; 3];
+        self.peek(&mut v)?;
+        Ok(bytes_prefix_len(&v).is_some())
+    }
+
+    /// Check if the next value is `set()`, `frozenset()` or `frozenset(...)`.
+    fn peek_set_constructor(&mut self) -> Result<bool> {
+        let mut v = vec![0u8; 10];
+        self.peek(&mut v)?;
+        Ok(v.starts_with(b"set()") || v.starts_with(b"frozenset("))
+    }
+
+    /// Check if the next value is a call like `Name(`, excluding
+    /// `OrderedDict(`. Return the length of `Name`.
+    fn peek_call_name(&mut self) -> Result<Option<usize>> {
+        self.skip_spaces_and_comments()?;
+        let mut v = vec![0u8; 256];
+        self.peek(&mut v)?;
+        if v.starts_with(ORDERED_DICT)
+            || !v
+                .first()
+                .is_some_and(|b| b.is_ascii_alphabetic() || *b == b'_')
+        {
+            return Ok(None);
+        }
+        let len = v.iter().take_while(|&&b| is_word_byte(b)).count();
+        match v[len..].iter().find(|b| !b.is_ascii_whitespace()) {
+            Some(b'(') => Ok(Some(len)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Read an enum member access, like `Color.RED`, and return the member
+    /// name. The class name, which can be qualified like `mod.Color`, is
+    /// not checked. Return `None` if the next value is not an attribute
+    /// access.
+    fn read_enum_member(&mut self) -> Result<Option<String>> {
+        let mut v = vec![0u8; 256];
+        self.peek(&mut v)?;
+        if !v
+            .first()
+            .is_some_and(|b| b.is_ascii_alphabetic() || *b == b'_')
+        {
+            return Ok(None);
+        }
+        let len = v.iter().take_while(|&&b| is_word_byte(b)).count();
+        let word = String::from_utf8_lossy(&v[..len]);
+        let member = match word.rsplit_once('.') {
+            Some((class, member)) if !class.ends_with('.') && !member.is_empty() => {
+                member.to_string()
+            }
+            _ => return Ok(None),
+        };
+        self.skip(len)?;
+        Ok(Some(member))
+    }
+
+    /// Read a keyword argument name, like `x` in `x=1`, and the `=`.
+    fn read_keyword_argument(&mut self) -> Result<String> {
+        self.skip_spaces_and_comments()?;
+        let name = self.read_while(|b, s: &mut String| {
+            let accept = b.is_ascii_alphanumeric() || b == b'_';
+            if accept {
+                s.push(b as char);
+            }
+            Ok::<_, Error>(accept)
+        })?;
+        if name.is_empty() {
+            return self.type_mismatch("keyword argument");
+        }
+        self.expect_byte(b'=', "keyword argument")?;
+        Ok(name)
+    }
+
+    /// Check if the next value is `OrderedDict(...)`.
+    fn peek_ordered_dict(&mut self) -> Result<bool> {
+        let mut v = vec![0u8; ORDERED_DICT.len()];
+        self.peek(&mut v)?;
+        Ok(v == ORDERED_DICT)
+    }
+
+    /// Peek more and more bytes until `scan` can decide the type, like
+    /// telling `('a' 'b')`, which is a str, from a tuple.
+    fn peek_scan(
+        &mut self,
+        scan: fn(&[u8]) -> Option<PeekType>,
+        fallback: PeekType,
+    ) -> Result<PeekType> {
+        let mut len = 256;
+        loop {
+            let mut v = vec![0u8; len];
+            self.peek(&mut v)?;
+            let eof = v.len() < len;
+            match scan(&v) {
+                Some(t) => return Ok(t),
+                // Give up on overly long values.
+                None if eof || len >= (1 << 20) => return Ok(fallback),
+                None => len *= 4,
+            }
+        }
+    }
+
     /// Check if a number is float or int.
     /// Return `true` for float, `false` for int.
     fn peek_is_float_or_int(&mut self) -> Result<bool> {
@@ -378,7 +1530,7 @@ impl<R: Read> Deserializer<R> {
         self.peek(&mut v)?;
         for b in v {
             match b {
-                b'e' | b'.' => return Ok(true),
+                b'e' | b'E' | b'.' => return Ok(true),
                 b'0'..=b'9' | b'_' | b'+' | b'-' => continue,
                 _ => return Ok(false),
             }
@@ -386,10 +1538,258 @@ impl<R: Read> Deserializer<R> {
         Ok(false)
     }
 
-    /// Raise a TypeMismatch error.
+    /// Check if the next value is a complex number, like `1+2j`, `(1+2j)`
+    /// or `complex(1, 2)`.
+    ///
+    /// Unlike `peek_type`, this does not scan a `(...)` tuple, and only peeks
+    /// for values that can start a complex number.
+    fn peek_complex(&mut self) -> Result<bool> {
+        match self.peek_byte()? {
+            Some(b'(') | Some(b'c') | Some(b'0'..=b'9') | Some(b'+') | Some(b'-') | Some(b'.') => {
+                self.peek_is_complex()
+            }
+            _ => Ok(false),
+        }
+    }
+
+    fn peek_is_complex(&mut self) -> Result<bool> {
+        // 128-char is enough to hold "(<f64>+<f64>j)".
+        let mut v = vec![0u8; 128];
+        self.peek(&mut v)?;
+        if v.starts_with(b"complex(") {
+            return Ok(true);
+        }
+        let mut rest = &v[..];
+        let parenthesized = rest.first() == Some(&b'(');
+        if parenthesized {
+            rest = &rest[1..];
+            while rest.first().is_some_and(|b| b.is_ascii_whitespace()) {
+                rest = &rest[1..];
+            }
+        }
+        let mut has_digit = false;
+        for (i, &b) in rest.iter().enumerate() {
+            match b {
+                // `(1+2j, 3)` is a tuple.
+                b'j' | b'J' if parenthesized => {
+                    let next = rest[i + 1..].iter().find(|b| !b.is_ascii_whitespace());
+                    return Ok(has_digit && next == Some(&b')'));
+                }
+                b'j' | b'J' => return Ok(has_digit),
+                b'0'..=b'9' => has_digit = true,
+                b'e' | b'E' | b'.' | b'_' | b'+' | b'-' => continue,
+                _ => return Ok(false),
+            }
+        }
+        Ok(false)
+    }
+
+    /// Read a complex number. Return `(real, imag)`.
+    fn read_complex(&mut self) -> Result<(f64, f64)> {
+        self.skip_spaces_and_comments()?;
+        let mut buf = vec![0; 8];
+        self.peek(&mut buf)?;
+        if buf == b"complex(" {
+            // complex(real, imag), complex(real), or complex().
+            self.skip(buf.len())?;
+            let mut parts = [0.0; 2];
+            for (i, part) in parts.iter_mut().enumerate() {
+                if self.peek_byte()? == Some(b')') {
+                    break;
+                }
+                if i > 0 {
+                    self.expect_byte(b',', "complex")?;
+                    if self.peek_byte()? == Some(b')') {
+                        break;
+                    }
+                }
+                *part = self.read_f64()?;
+            }
+            if self.peek_byte()? == Some(b',') {
+                self.skip(1)?;
+            }
+            self.expect_byte(b')', "complex")?;
+            return Ok((parts[0], parts[1]));
+        }
+
+        // 1+2j, (1+2j), 2j.
+        let parenthesized = self.peek_byte()? == Some(b'(');
+        if parenthesized {
+            self.skip(1)?;
+        }
+        let first = self.read_f64()?;
+        let value = match self.peek_byte()? {
+            Some(b'j') | Some(b'J') => {
+                self.skip(1)?;
+                (0.0, first)
+            }
+            Some(sign @ b'+') | Some(sign @ b'-') => {
+                // Read the sign separately, since `+2` alone is not a valid
+                // number for `strict_numbers`.
+                self.skip(1)?;
+                let imag = self.read_f64()?;
+                let imag = if sign == b'-' { -imag } else { imag };
+                match self.peek_byte()? {
+                    Some(b'j') | Some(b'J') => self.skip(1)?,
+                    _ => return self.type_mismatch("complex"),
+                }
+                (first, imag)
+            }
+            _ => (first, 0.0),
+        };
+        if parenthesized {
+            self.expect_byte(b')', "complex")?;
+        }
+        Ok(value)
+    }
+
+    fn read_f64(&mut self) -> Result<f64> {
+        let s = self.read_number_string()?;
+        if s.is_empty() {
+            return self.type_mismatch("number");
+        }
+        Ok(s.parse::<f64>()?)
+    }
+
+    /// Skip the next byte if it is `expected`. Otherwise raise a TypeMismatch error.
+    fn expect_byte(&mut self, expected: u8, name: &'static str) -> Result<()> {
+        if self.peek_byte()? == Some(expected) {
+            self.skip(1)?;
+            Ok(())
+        } else {
+            self.type_mismatch(name)
+        }
+    }
+
+    /// Visit a complex number as a `[real, imag]` sequence.
+    ///
+    /// `peek_type` reports `Complex` for a number followed by `j`, so
+    /// `deserialize_any`, `deserialize_seq`, `deserialize_tuple` and
+    /// `deserialize_struct` read it as a 2-item sequence.
+    fn visit_complex<'de, V: Visitor<'de>>(&mut self, visitor: V) -> Result<V::Value> {
+        let (real, imag) = self.read_complex()?;
+        let seq = de::value::SeqDeserializer::<_, Error>::new(vec![real, imag].into_iter());
+        visitor.visit_seq(seq)
+    }
+
+    /// Read an int. Decimal ints are parsed without allocating a `String`.
+    /// Other forms, like `0xff`, fall back to `read_number_string`.
+    fn read_int<T>(&mut self) -> Result<T>
+    where
+        T: FromStrRadix + TryFrom<i128> + TryFrom<u128>,
+    {
+        self.skip_spaces_and_comments()?;
+        let fast = if self.config.strict_numbers {
+            None
+        } else {
+            // 64 bytes fit i128::MIN with "_" separators.
+            let buf = self.peek_slice(64)?;
+            let mut negative = false;
+            let mut magnitude: Option<u128> = Some(0);
+            let mut digits = 0;
+            let mut len = 0;
+            for (i, &b) in buf.iter().enumerate() {
+                match b {
+                    b'+' | b'-' if i == 0 => negative = b == b'-',
+                    b'0'..=b'9' => {
+                        let digit = u128::from(b - b'0');
+                        magnitude = magnitude
+                            .and_then(|m| m.checked_mul(10))
+                            .and_then(|m| m.checked_add(digit));
+                        digits += 1;
+                    }
+                    b'_' if digits > 0 => {}
+                    _ => break,
+                }
+                len = i + 1;
+            }
+            // "0x", "1.0", "1e3", "1j", "1_" and long input take the slow path.
+            let complete = match buf.get(len) {
+                Some(&b) => !(b.is_ascii_alphanumeric() || b == b'.' || b == b'_'),
+                None => buf.len() < 64,
+            };
+            let value = match magnitude {
+                Some(m) if digits > 0 && complete => {
+                    if !negative {
+                        T::try_from(m).ok()
+                    } else if m <= i128::MAX as u128 + 1 {
+                        T::try_from((m as i128).wrapping_neg()).ok()
+                    } else {
+                        None
+                    }
+                }
+                _ => None,
+            };
+            value.map(|v| (v, len))
+        };
+        if let Some((value, len)) = fast {
+            self.skip(len)?;
+            return Ok(value);
+        }
+        // Slow path. Also reports errors, like overflow.
+        let s = self.read_number_string()?;
+        if s.is_empty() {
+            return self.type_mismatch("number");
+        }
+        parse_int::<T>(&s).map_err(|e| match e {
+            Error::ParseInt(source) => Error::ParseIntAs {
+                text: s,
+                target: std::any::type_name::<T>(),
+                source,
+            },
+            e => e,
+        })
+    }
+
+    /// Visit an int using the smallest of 64-bit and 128-bit types.
+    fn visit_any_int<'de, V: Visitor<'de>>(&mut self, visitor: V) -> Result<V::Value> {
+        let s = self.read_number_string()?;
+        if s.is_empty() {
+            return self.type_mismatch("int");
+        }
+        if s.starts_with('-') {
+            match parse_int::<i64>(&s) {
+                Ok(v) => visitor.visit_i64(v),
+                Err(_) => visitor.visit_i128(parse_int::<i128>(&s)?),
+            }
+        } else {
+            match parse_int::<u64>(&s) {
+                Ok(v) => visitor.visit_u64(v),
+                Err(_) => visitor.visit_u128(parse_int::<u128>(&s)?),
+            }
+        }
+    }
+
+    /// Raise a TypeMismatch error, or UnexpectedEnd or UnknownType if the
+    /// next value is missing or of an unknown type.
     fn type_mismatch<T>(&mut self, expected: &'static str) -> Result<T> {
-        let got = self.peek_type()?;
-        Err(Error::TypeMismatch(expected, got.to_cow_str()))
+        match self.peek_type()? {
+            PeekType::Eof => Err(Error::UnexpectedEnd(expected)),
+            PeekType::Unknown(s) => match self.unsupported_expression()? {
+                Some(e) => Err(e),
+                None => Err(Error::UnknownType(expected, s)),
+            },
+            got => Err(Error::TypeMismatch(expected, got.to_cow_str())),
+        }
+    }
+
+    /// Explain why the next value, of an unknown type, cannot be read, if
+    /// it is a Python expression that is not a literal, like an f-string or
+    /// a call.
+    fn unsupported_expression(&mut self) -> Result<Option<Error>> {
+        let mut v = vec![0u8; 3];
+        self.peek(&mut v)?;
+        if is_fstring_prefix(&v) {
+            return Ok(Some(Error::UnsupportedExpression("f-string".into())));
+        }
+        if let Some(len) = self.peek_call_name()? {
+            let mut v = vec![0u8; len];
+            self.peek(&mut v)?;
+            let name = String::from_utf8_lossy(&v);
+            let call = format!("call to {}()", name);
+            return Ok(Some(Error::UnsupportedExpression(call.into())));
+        }
+        Ok(None)
     }
 
     /// Push a frame if bracket matches. Return true if a frame is pushed.
@@ -402,28 +1802,54 @@ impl<R: Read> Deserializer<R> {
         let b = self.peek_byte()?;
         if b == Some(left_bracket) {
             self.skip(1)?;
-            self.stack.push(Frame {
-                right_bracket,
+            self.push_frame(Frame {
+                right_bracket: Some(right_bracket),
                 count: 0,
                 size_hint,
-            });
+            })?;
             Ok(true)
         } else {
             Ok(false)
         }
     }
 
+    /// Call `f` to read a value wrapped in a call like `frozenset(...)`,
+    /// counting the call towards `max_depth`. The caller reads the `)`.
+    fn nested<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        self.push_frame(Frame {
+            right_bracket: Some(b')'),
+            count: 0,
+            size_hint: None,
+        })?;
+        let result = f(self);
+        self.stack.pop();
+        result
+    }
+
+    fn push_frame(&mut self, frame: Frame) -> Result<()> {
+        if self.stack.len() >= self.config.max_depth {
+            return Err(Error::DepthLimitExceeded(self.config.max_depth));
+        }
+        self.stack.push(frame);
+        Ok(())
+    }
+
     /// Pop a frame if bracket matches. Return true if the right
     /// bracket matches.
     fn maybe_pop_bracket(&mut self) -> crate::Result<bool> {
         if let Some(frame) = self.stack.last() {
             let right_bracket = frame.right_bracket;
-            if let Some(b) = self.peek_byte()? {
-                if b == right_bracket {
+            match self.peek_byte()? {
+                Some(b) if Some(b) == right_bracket => {
                     self.stack.pop();
                     self.skip(1)?;
                     return Ok(true);
                 }
+                None if right_bracket.is_none() => {
+                    self.stack.pop();
+                    return Ok(true);
+                }
+                _ => {}
             }
         }
         Ok(false)
@@ -463,11 +1889,16 @@ impl<R: Read> Deserializer<R> {
     }
 
     /// Force read till the end of a container.
-    fn force_end_container(&mut self) -> crate::Result<()> {
+    fn force_end_container<'de>(&mut self) -> crate::Result<()>
+    where
+        B: Source<'de>,
+    {
         while !self.maybe_pop_bracket()? {
             let b = self.peek_byte()?.unwrap_or(b' ');
             if b == b':' || b == b',' {
                 self.skip(1)?;
+                // The right bracket might follow a trailing comma.
+                continue;
             }
             self.deserialize_ignored_any(de::IgnoredAny)?;
         }
@@ -486,40 +1917,77 @@ impl<R: Read> Deserializer<R> {
     }
 
     fn debug(&mut self, label: &'static str) {
-        if cfg!(test) && cfg!(debug_assertions) {
-            if std::env::var_os("DEBUG").is_some() {
-                let brackets = self
-                    .stack
-                    .iter()
-                    .map(|f| f.right_bracket)
-                    .collect::<Vec<u8>>();
-                let mut buf = vec![b' '; 10];
-                self.peek(&mut buf).unwrap();
-                eprintln!(
-                    "{:22} STACK: '{}' PEEK: '{}'",
-                    label,
-                    String::from_utf8(brackets).unwrap(),
-                    String::from_utf8_lossy(&buf),
-                );
-            }
+        if cfg!(test) && cfg!(debug_assertions) && std::env::var_os("DEBUG").is_some() {
+            let brackets = self
+                .stack
+                .iter()
+                .map(|f| f.right_bracket.unwrap_or(b'$'))
+                .collect::<Vec<u8>>();
+            let mut buf = vec![b' '; 10];
+            self.peek(&mut buf).unwrap();
+            eprintln!(
+                "{:22} STACK: '{}' PEEK: '{}'",
+                label,
+                String::from_utf8(brackets).unwrap(),
+                String::from_utf8_lossy(&buf),
+            );
         }
         let _ = label;
     }
 }
 
+/// Kind of the next value, returned by [`Deserializer::peek_kind`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ValueKind {
+    /// Nothing but spaces and comments is left.
+    Eof,
+    /// `[1, 2]`
+    List,
+    /// `{1: 2}`, or `OrderedDict(...)`.
+    Map,
+    /// `(1, 2)`
+    Tuple,
+    /// `{1, 2}`, `set()` or `frozenset(...)`.
+    Set,
+    /// `'a'`, or adjacent literals like `'a' 'b'`.
+    Str,
+    /// `b'a'`
+    Bytes,
+    /// `True` or `False`
+    Bool,
+    /// An int starting with `-`, like `-1`.
+    SignedInt,
+    /// An int not starting with `-`, like `1` or `0xff`.
+    UnsignedInt,
+    /// `1.5`, `1e3`, `inf` or `nan`.
+    Float,
+    /// `1+2j` or `complex(1, 2)`.
+    Complex,
+    /// `None`
+    None,
+    /// `...`
+    Ellipsis,
+    /// Not a value this crate can read.
+    Unknown,
+}
+
 #[derive(Debug)]
 enum PeekType {
     Eof,
     List,
     Map,
     Tuple,
+    Set,
     Str,
     Bytes,
     Bool,
     SignedInt,
     UnsignedInt,
     Float,
+    Complex,
     None,
+    Ellipsis,
     Unknown(String),
 }
 
@@ -531,12 +1999,15 @@ impl PeekType {
             List => "list",
             Map => "map",
             Tuple => "tuple",
+            Set => "set",
             Str => "str",
             Bytes => "bytes",
             Bool => "bool",
             SignedInt | UnsignedInt => "int",
             Float => "float",
+            Complex => "complex",
             None => "None",
+            Ellipsis => "Ellipsis",
             Unknown(s) => {
                 return format!("unknown type ({:?})", s).into();
             }
@@ -545,62 +2016,59 @@ impl PeekType {
     }
 }
 
-impl<'de, 'a, R: Read> de::Deserializer<'de> for &'a mut Deserializer<R> {
+impl<'de, R: Read, B: Source<'de>> de::Deserializer<'de> for &mut Deserializer<R, B> {
     type Error = Error;
 
     fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
         self.debug("deserialize_any");
         use PeekType::*;
         match self.peek_type()? {
-            List | Tuple => self.deserialize_seq(visitor),
+            // `()` is how unit is serialized. Reporting it as unit lets
+            // buffered content, like in untagged enums, read it back.
+            // `serde_json::Value` reads it as `null`, while
+            // `pyliteral_string_to_json_value` maps it to `[]`.
+            Tuple if self.peek_keyword(&["()"])?.is_some() => self.deserialize_unit(visitor),
+            List | Tuple | Set => self.deserialize_seq(visitor),
             Map => self.deserialize_map(visitor),
             Str => self.deserialize_str(visitor),
             Bytes => self.deserialize_bytes(visitor),
             Bool => self.deserialize_bool(visitor),
-            UnsignedInt => self.deserialize_u64(visitor),
-            SignedInt => self.deserialize_i64(visitor),
+            UnsignedInt | SignedInt => self.visit_any_int(visitor),
             Float => self.deserialize_f64(visitor),
+            Complex => self.visit_complex(visitor),
             None => self.deserialize_option(visitor),
+            Ellipsis => self.deserialize_unit(visitor),
             Eof => Err(Error::ParseAny(String::new())),
-            Unknown(s) => Err(Error::ParseAny(s)),
+            Unknown(s) => match self.unsupported_expression()? {
+                Some(e) => Err(e),
+                Option::None => Err(Error::ParseAny(s)),
+            },
         }
     }
 
     fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
         self.debug("deserialize_bool");
-        self.skip_spaces_and_comments()?;
-        let mut buf = vec![0; 5];
-        let v: V::Value;
-        self.peek(&mut buf)?;
-        if let Some(b"True") | Some(b"true") = buf.get(..4) {
-            v = visitor.visit_bool::<Error>(true)?;
-            self.skip(4)?;
-        } else if let Some(b"False") | Some(b"false") = buf.get(..5) {
-            v = visitor.visit_bool::<Error>(false)?;
-            self.skip(5)?;
-        } else if buf.get(0) == Some(&b'1') {
-            v = visitor.visit_bool::<Error>(true)?;
-            self.skip(1)?;
-        } else if buf.get(0) == Some(&b'0') {
-            v = visitor.visit_bool::<Error>(false)?;
-            self.skip(1)?;
-        } else {
-            return self.type_mismatch("bool");
+        match self.match_keyword(self.bool_keywords())? {
+            Some(i) => visitor.visit_bool(i % 2 == 0),
+            None => self.type_mismatch("bool"),
         }
-        Ok(v)
     }
 
     /* [[[cog
     import cog
-    for t in "i8 i16 i32 i64 u8 u16 u32 u64 f32 f64".split():
-        cog.out(f"""
-    fn deserialize_{t}<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {{
-        self.debug("deserialize_{t}");
-        let s = self.read_number_string()?;
+    for t in "i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64".split():
+        if t.startswith("f"):
+            read = f"""let s = self.read_number_string()?;
         if s.is_empty() {{
             return self.type_mismatch("number");
         }}
-        let i = s.parse::<{t}>()?;
+        let i = s.parse::<{t}>()?;"""
+        else:
+            read = f"let i = self.read_int::<{t}>()?;"
+        cog.out(f"""
+    fn deserialize_{t}<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {{
+        self.debug("deserialize_{t}");
+        {read}
         visitor.visit_{t}(i)
     }}
     """)
@@ -608,84 +2076,64 @@ impl<'de, 'a, R: Read> de::Deserializer<'de> for &'a mut Deserializer<R> {
 
     fn deserialize_i8<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
         self.debug("deserialize_i8");
-        let s = self.read_number_string()?;
-        if s.is_empty() {
-            return self.type_mismatch("number");
-        }
-        let i = s.parse::<i8>()?;
+        let i = self.read_int::<i8>()?;
         visitor.visit_i8(i)
     }
 
     fn deserialize_i16<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
         self.debug("deserialize_i16");
-        let s = self.read_number_string()?;
-        if s.is_empty() {
-            return self.type_mismatch("number");
-        }
-        let i = s.parse::<i16>()?;
+        let i = self.read_int::<i16>()?;
         visitor.visit_i16(i)
     }
 
     fn deserialize_i32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
         self.debug("deserialize_i32");
-        let s = self.read_number_string()?;
-        if s.is_empty() {
-            return self.type_mismatch("number");
-        }
-        let i = s.parse::<i32>()?;
+        let i = self.read_int::<i32>()?;
         visitor.visit_i32(i)
     }
 
     fn deserialize_i64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
         self.debug("deserialize_i64");
-        let s = self.read_number_string()?;
-        if s.is_empty() {
-            return self.type_mismatch("number");
-        }
-        let i = s.parse::<i64>()?;
+        let i = self.read_int::<i64>()?;
         visitor.visit_i64(i)
     }
 
+    fn deserialize_i128<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.debug("deserialize_i128");
+        let i = self.read_int::<i128>()?;
+        visitor.visit_i128(i)
+    }
+
     fn deserialize_u8<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
         self.debug("deserialize_u8");
-        let s = self.read_number_string()?;
-        if s.is_empty() {
-            return self.type_mismatch("number");
-        }
-        let i = s.parse::<u8>()?;
+        let i = self.read_int::<u8>()?;
         visitor.visit_u8(i)
     }
 
     fn deserialize_u16<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
         self.debug("deserialize_u16");
-        let s = self.read_number_string()?;
-        if s.is_empty() {
-            return self.type_mismatch("number");
-        }
-        let i = s.parse::<u16>()?;
+        let i = self.read_int::<u16>()?;
         visitor.visit_u16(i)
     }
 
     fn deserialize_u32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
         self.debug("deserialize_u32");
-        let s = self.read_number_string()?;
-        if s.is_empty() {
-            return self.type_mismatch("number");
-        }
-        let i = s.parse::<u32>()?;
+        let i = self.read_int::<u32>()?;
         visitor.visit_u32(i)
     }
 
     fn deserialize_u64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
         self.debug("deserialize_u64");
-        let s = self.read_number_string()?;
-        if s.is_empty() {
-            return self.type_mismatch("number");
-        }
-        let i = s.parse::<u64>()?;
+        let i = self.read_int::<u64>()?;
         visitor.visit_u64(i)
     }
 
+    fn deserialize_u128<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.debug("deserialize_u128");
+        let i = self.read_int::<u128>()?;
+        visitor.visit_u128(i)
+    }
+
     fn deserialize_f32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
         self.debug("deserialize_f32");
         let s = self.read_number_string()?;
@@ -710,17 +2158,23 @@ impl<'de, 'a, R: Read> de::Deserializer<'de> for &'a mut Deserializer<R> {
     fn deserialize_char<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
         self.debug("deserialize_char");
         let s = self.read_string()?;
-        let chars: Vec<char> = s.chars().take(2).collect();
-        if chars.len() != 1 {
-            Err(Error::TypeMismatch("char", "str".into()))
-        } else {
-            visitor.visit_char(chars[0])
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(ch), None) => visitor.visit_char(ch),
+            (None, _) => Err(Error::TypeMismatch("char", "empty str".into())),
+            (Some(_), Some(_)) => Err(Error::TypeMismatch(
+                "char",
+                format!("str of {} chars", s.chars().count()).into(),
+            )),
         }
     }
 
     fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
         self.debug("deserialize_str");
-        self.deserialize_string(visitor)
+        match self.read_str()? {
+            Cow::Borrowed(s) => visitor.visit_borrowed_str(s),
+            Cow::Owned(s) => visitor.visit_string(s),
+        }
     }
 
     fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
@@ -742,14 +2196,12 @@ impl<'de, 'a, R: Read> de::Deserializer<'de> for &'a mut Deserializer<R> {
 
     fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
         self.debug("deserialize_option");
-        self.skip_spaces_and_comments()?;
-        let mut buf = vec![0; 4];
-        self.peek(&mut buf)?;
-        if buf == b"None" || buf == b"null" {
-            self.skip(4)?;
-            visitor.visit_none()
-        } else {
-            visitor.visit_some(self)
+        match self.match_keyword(self.none_keywords())? {
+            Some(_) => visitor.visit_none(),
+            None if self.config.python_only && self.peek_keyword(&["null"])?.is_some() => {
+                Err(Error::TypeMismatch("None", "null".into()))
+            }
+            None => visitor.visit_some(self),
         }
     }
 
@@ -761,26 +2213,74 @@ impl<'de, 'a, R: Read> de::Deserializer<'de> for &'a mut Deserializer<R> {
 
     fn deserialize_unit_struct<V: Visitor<'de>>(
         self,
-        _name: &'static str,
+        name: &'static str,
         visitor: V,
     ) -> Result<V::Value> {
         self.debug("deserialize_unit_struct");
+        if name == crate::ellipsis::ELLIPSIS_TOKEN {
+            return match self.match_keyword(&["..."])? {
+                Some(_) => visitor.visit_unit(),
+                None => self.type_mismatch("Ellipsis"),
+            };
+        }
         self.deserialize_unit(visitor)
     }
 
     fn deserialize_newtype_struct<V: Visitor<'de>>(
         self,
-        _name: &'static str,
+        name: &'static str,
         visitor: V,
     ) -> Result<V::Value> {
         self.debug("deserialize_newtype_struct");
+        if name == value::VALUE_TOKEN {
+            let variant = match self.peek_type()? {
+                PeekType::Tuple => Some(value::TUPLE_TOKEN),
+                PeekType::Set => Some(value::SET_TOKEN),
+                PeekType::Complex => Some(value::COMPLEX_TOKEN),
+                PeekType::Ellipsis => Some(crate::ellipsis::ELLIPSIS_TOKEN),
+                PeekType::Map => match self.peek_scan(scan_braced_type, PeekType::Map)? {
+                    PeekType::Set => Some(value::SET_TOKEN),
+                    _ => None,
+                },
+                _ => None,
+            };
+            if let Some(variant) = variant {
+                return visitor.visit_enum(VariantAccess(variant, self));
+            }
+        }
+        #[cfg(feature = "datetime")]
+        if name == crate::datetime::EXPR_TOKEN {
+            let expr = self.read_call_expr()?;
+            return visitor.visit_string(expr);
+        }
         visitor.visit_newtype_struct(self)
     }
 
     fn deserialize_seq<V: Visitor<'de>>(mut self, visitor: V) -> Result<V::Value> {
         self.debug("deserialize_seq");
+        if self.peek_complex()? {
+            return self.visit_complex(visitor);
+        }
+        self.skip_spaces_and_comments()?;
+        let mut buf = vec![0; 11];
+        self.peek(&mut buf)?;
+        if buf.starts_with(b"set()") {
+            // Empty set. Read "()" as an empty seq.
+            self.skip(3)?;
+        } else if buf.starts_with(b"frozenset()") {
+            self.skip(9)?;
+        } else if buf.starts_with(b"frozenset(") {
+            // frozenset({...})
+            self.skip(10)?;
+            let v = self.nested(|de| de::Deserializer::deserialize_seq(de, visitor))?;
+            self.expect_byte(b')', "frozenset")?;
+            return Ok(v);
+        }
+        // `{...}` is a set. A dict is rejected by `maybe_read_comma` at
+        // the first `:`.
         if self.maybe_push_bracket(b'[', b']', None)?
             || self.maybe_push_bracket(b'(', b')', None)?
+            || self.maybe_push_bracket(b'{', b'}', None)?
         {
             visitor.visit_seq(&mut self)
         } else {
@@ -790,6 +2290,22 @@ impl<'de, 'a, R: Read> de::Deserializer<'de> for &'a mut Deserializer<R> {
 
     fn deserialize_tuple<V: Visitor<'de>>(mut self, len: usize, visitor: V) -> Result<V::Value> {
         self.debug("deserialize_tuple");
+        if self.peek_complex()? {
+            return self.visit_complex(visitor);
+        }
+        let b = self.peek_byte()?;
+        if len == 2 && b != Some(b'(') && b != Some(b'[') {
+            // A real number is promoted to complex for a 2-tuple like (f64, f64).
+            let got = self.peek_type()?;
+            if let PeekType::UnsignedInt | PeekType::SignedInt | PeekType::Float = got {
+                let got = got.to_cow_str();
+                let real = self.read_f64()?;
+                let parts = vec![real, 0.0].into_iter();
+                let parts = parts.map(|v| PromotedFloat(v, got.clone()));
+                let seq = de::value::SeqDeserializer::<_, Error>::new(parts);
+                return visitor.visit_seq(seq);
+            }
+        }
         if self.maybe_push_bracket(b'(', b')', Some(len))?
             || self.maybe_push_bracket(b'[', b']', Some(len))?
         {
@@ -811,6 +2327,30 @@ impl<'de, 'a, R: Read> de::Deserializer<'de> for &'a mut Deserializer<R> {
 
     fn deserialize_map<V: Visitor<'de>>(mut self, visitor: V) -> Result<V::Value> {
         self.debug("deserialize_map");
+        if self.peek_ordered_dict()? {
+            self.skip(ORDERED_DICT.len())?;
+            if self.peek_byte()? == Some(b'{') {
+                // OrderedDict({...})
+                let v = self.nested(|de| de::Deserializer::deserialize_map(de, visitor))?;
+                self.expect_byte(b')', "OrderedDict")?;
+                return Ok(v);
+            }
+            if self.maybe_push_bracket(b'[', b']', None)?
+                || self.maybe_push_bracket(b'(', b')', None)?
+            {
+                // OrderedDict([(k, v), ...])
+                let v = visitor.visit_map(PairsAccess(&mut *self))?;
+                self.expect_byte(b')', "OrderedDict")?;
+                return Ok(v);
+            }
+            // OrderedDict(). Its ")" ends the (empty) pairs.
+            self.push_frame(Frame {
+                right_bracket: Some(b')'),
+                count: 0,
+                size_hint: None,
+            })?;
+            return visitor.visit_map(PairsAccess(self));
+        }
         if self.maybe_push_bracket(b'{', b'}', None)? {
             visitor.visit_map(&mut self)
         } else {
@@ -825,17 +2365,36 @@ impl<'de, 'a, R: Read> de::Deserializer<'de> for &'a mut Deserializer<R> {
         visitor: V,
     ) -> Result<V::Value> {
         self.debug("deserialize_struct");
+        if self.peek_complex()? {
+            // struct Complex { re: f64, im: f64 }
+            return self.visit_complex(visitor);
+        }
+        if self.config.dataclass_repr {
+            if let Some(len) = self.peek_call_name()? {
+                // Name(field=value, ...)
+                self.skip(len)?;
+                self.maybe_push_bracket(b'(', b')', None)?;
+                return visitor.visit_map(KeywordArgumentsAccess(self));
+            }
+        }
         self.deserialize_map(visitor)
     }
 
     fn deserialize_enum<V: Visitor<'de>>(
         self,
-        _name: &'static str,
-        _variants: &'static [&'static str],
+        name: &'static str,
+        variants: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value> {
         self.debug("deserialize_enum");
-        if self.maybe_push_bracket(b'{', b'}', None)? {
+        if name == "Result"
+            && variants.contains(&"Ok")
+            && variants.contains(&"Err")
+            && self.maybe_push_bracket(b'(', b')', Some(2))?
+        {
+            // ('ok', value) or ('err', value), written by `result_as_tuple`.
+            visitor.visit_enum(ResultTupleAccess(self))
+        } else if self.maybe_push_bracket(b'{', b'}', None)? {
             // Map variant {'field': value}
             visitor.visit_enum(&mut *self)
         } else {
@@ -844,6 +2403,9 @@ impl<'de, 'a, R: Read> de::Deserializer<'de> for &'a mut Deserializer<R> {
                 // String for unit variant.
                 let name = self.read_string()?;
                 visitor.visit_enum(name.into_deserializer())
+            } else if let Some(variant) = self.read_enum_member()? {
+                // `Name.Variant` for unit variant.
+                visitor.visit_enum(variant.into_deserializer())
             } else {
                 self.type_mismatch("enum")
             }
@@ -861,15 +2423,680 @@ impl<'de, 'a, R: Read> de::Deserializer<'de> for &'a mut Deserializer<R> {
     }
 }
 
+/// Iterator over values read from a single reader. Created by
+/// [`Deserializer::into_iter`].
+pub struct StreamDeserializer<R, T, B = ()> {
+    de: Deserializer<R, B>,
+    failed: bool,
+    output: PhantomData<T>,
+}
+
+impl<'de, R: Read, B: Source<'de>, T: de::DeserializeOwned> Iterator
+    for StreamDeserializer<R, T, B>
+{
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Result<T>> {
+        if self.failed {
+            return None;
+        }
+        // Reset in case the previous value left frames behind.
+        self.de.stack.clear();
+        let result = match self.de.check_bom().and_then(|_| self.de.peek_type()) {
+            Ok(PeekType::Eof) => return None,
+            Ok(_) => de::Deserialize::deserialize(&mut self.de),
+            Err(e) => Err(e),
+        };
+        let result = result.map_err(|e| self.de.error_at(e));
+        self.failed = result.is_err();
+        Some(result)
+    }
+}
+
+/// Report a tuple or set as an enum variant named `.0` to `Value`, which
+/// cannot otherwise tell them from lists.
+struct VariantAccess<'a, R, B>(&'static str, &'a mut Deserializer<R, B>);
+
+impl<'de, R: Read, B: Source<'de>> de::EnumAccess<'de> for VariantAccess<'_, R, B> {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V: de::DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Self::Variant)> {
+        let name = IntoDeserializer::<Error>::into_deserializer(self.0);
+        Ok((seed.deserialize(name)?, self))
+    }
+}
+
+impl<'de, R: Read, B: Source<'de>> de::VariantAccess<'de> for VariantAccess<'_, R, B> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        self.1.read_unit()
+    }
+
+    fn newtype_variant_seed<T: de::DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value> {
+        seed.deserialize(self.1)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value> {
+        de::Deserializer::deserialize_seq(self.1, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        _visitor: V,
+    ) -> Result<V::Value> {
+        self.1.type_mismatch("struct")
+    }
+}
+
+/// Error out if a str or bytes of `len` bytes exceeds `limit`.
+fn check_len(len: usize, limit: Option<usize>, what: &'static str) -> Result<()> {
+    match limit {
+        Some(limit) if len > limit => Err(Error::LengthLimitExceeded(what, limit)),
+        _ => Ok(()),
+    }
+}
+
+/// Read `Result` from `('ok', value)` or `('err', value)`. The tuple frame
+/// is pushed by the caller.
+struct ResultTupleAccess<'a, R, B>(&'a mut Deserializer<R, B>);
+
+const RESULT_TAGS: &[&str] = &["ok", "err"];
+
+impl<'de, R: Read, B: Source<'de>> de::EnumAccess<'de> for ResultTupleAccess<'_, R, B> {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V: de::DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Self::Variant)> {
+        let tag: Option<String> = de::SeqAccess::next_element(&mut &mut *self.0)?;
+        let variant = match tag.as_deref() {
+            Some("ok") => "Ok",
+            Some("err") => "Err",
+            Some(tag) => return Err(de::Error::unknown_variant(tag, RESULT_TAGS)),
+            None => return Err(Error::TypeMismatch("Result", "empty tuple".into())),
+        };
+        let name = IntoDeserializer::<Error>::into_deserializer(variant);
+        Ok((seed.deserialize(name)?, self))
+    }
+}
+
+impl<'de, R: Read, B: Source<'de>> de::VariantAccess<'de> for ResultTupleAccess<'_, R, B> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        self.0.type_mismatch("unit")
+    }
+
+    fn newtype_variant_seed<T: de::DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value> {
+        match de::SeqAccess::next_element_seed(&mut &mut *self.0, seed)? {
+            Some(v) => Ok(v),
+            None => Err(Error::TypeMismatch("Result", "1-tuple".into())),
+        }
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, _visitor: V) -> Result<V::Value> {
+        self.0.type_mismatch("tuple")
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        _visitor: V,
+    ) -> Result<V::Value> {
+        self.0.type_mismatch("struct")
+    }
+}
+
+/// A part of a real number promoted to a complex number. Only floats can be
+/// read from it, so a 2-tuple of other types, like `(i32, String)`, still
+/// reports the real number as a type mismatch.
+struct PromotedFloat(f64, Cow<'static, str>);
+
+impl<'de> IntoDeserializer<'de, Error> for PromotedFloat {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl<'de> de::Deserializer<'de> for PromotedFloat {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value> {
+        Err(Error::TypeMismatch("tuple", self.1))
+    }
+
+    fn deserialize_f32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_f32(self.0 as f32)
+    }
+
+    fn deserialize_f64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_f64(self.0)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+/// Read the whole input as a tuple without brackets, like `1, 2, 3`. The
+/// tuple frame is pushed without reading `(` and ends at EOF.
+struct BareTupleDeserializer<'a, R, B>(&'a mut Deserializer<R, B>);
+
+impl<R: Read, B> BareTupleDeserializer<'_, R, B> {
+    fn push_eof_frame(&mut self, size_hint: Option<usize>) -> Result<()> {
+        self.0.push_frame(Frame {
+            right_bracket: None,
+            count: 0,
+            size_hint,
+        })
+    }
+}
+
+impl<'de, R: Read, B: Source<'de>> de::Deserializer<'de> for BareTupleDeserializer<'_, R, B> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(mut self, visitor: V) -> Result<V::Value> {
+        self.push_eof_frame(None)?;
+        visitor.visit_seq(self.0)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(mut self, len: usize, visitor: V) -> Result<V::Value> {
+        self.push_eof_frame(Some(len))?;
+        visitor.visit_seq(self.0)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        if name == value::VALUE_TOKEN {
+            // Report a tuple to `Value`, like `VariantAccess`.
+            visitor.visit_enum(self)
+        } else {
+            visitor.visit_newtype_struct(self)
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct seq map struct enum
+        identifier ignored_any
+    }
+}
+
+impl<'de, R: Read, B: Source<'de>> de::EnumAccess<'de> for BareTupleDeserializer<'_, R, B> {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V: de::DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Self::Variant)> {
+        let name = IntoDeserializer::<Error>::into_deserializer(value::TUPLE_TOKEN);
+        Ok((seed.deserialize(name)?, self))
+    }
+}
+
+impl<'de, R: Read, B: Source<'de>> de::VariantAccess<'de> for BareTupleDeserializer<'_, R, B> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        self.0.type_mismatch("unit")
+    }
+
+    fn newtype_variant_seed<T: de::DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value> {
+        de::Deserializer::deserialize_any(self, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        _visitor: V,
+    ) -> Result<V::Value> {
+        self.0.type_mismatch("struct")
+    }
+}
+
+/// Deserialize a dict key. With `stringify_keys`, non-str keys are
+/// converted to str if a str is expected.
+struct KeyDeserializer<'a, R, B>(&'a mut Deserializer<R, B>);
+
+impl<'a, R: Read, B> KeyDeserializer<'a, R, B> {
+    /// Read a non-str key as a Python literal str, if `stringify_keys` is
+    /// set. Return `None` otherwise, without consuming anything.
+    fn maybe_read_stringified<'de>(&mut self) -> Result<Option<String>>
+    where
+        B: Source<'de>,
+    {
+        if !self.0.config.stringify_keys {
+            return Ok(None);
+        }
+        if let PeekType::Str = self.0.peek_type()? {
+            return Ok(None);
+        }
+        let key: value::Value = de::Deserialize::deserialize(&mut *self.0)?;
+        crate::to_string(&key).map(Some)
+    }
+}
+
+macro_rules! forward_key_deserializer {
+    ($($name:ident($($arg:ident: $t:ty),*);)*) => {$(
+        fn $name<V: Visitor<'de>>(self, $($arg: $t,)* visitor: V) -> Result<V::Value> {
+            self.0.$name($($arg,)* visitor)
+        }
+    )*};
+}
+
+macro_rules! stringify_key_deserializer {
+    ($($name:ident;)*) => {$(
+        fn $name<V: Visitor<'de>>(mut self, visitor: V) -> Result<V::Value> {
+            match self.maybe_read_stringified()? {
+                Some(s) => visitor.visit_string(s),
+                None => self.0.$name(visitor),
+            }
+        }
+    )*};
+}
+
+impl<'de, R: Read, B: Source<'de>> de::Deserializer<'de> for KeyDeserializer<'_, R, B> {
+    type Error = Error;
+
+    stringify_key_deserializer! {
+        deserialize_str;
+        deserialize_string;
+        deserialize_identifier;
+    }
+
+    forward_key_deserializer! {
+        deserialize_any();
+        deserialize_bool();
+        deserialize_i8();
+        deserialize_i16();
+        deserialize_i32();
+        deserialize_i64();
+        deserialize_i128();
+        deserialize_u8();
+        deserialize_u16();
+        deserialize_u32();
+        deserialize_u64();
+        deserialize_u128();
+        deserialize_f32();
+        deserialize_f64();
+        deserialize_char();
+        deserialize_bytes();
+        deserialize_byte_buf();
+        deserialize_option();
+        deserialize_unit();
+        deserialize_unit_struct(name: &'static str);
+        deserialize_newtype_struct(name: &'static str);
+        deserialize_seq();
+        deserialize_tuple(len: usize);
+        deserialize_tuple_struct(name: &'static str, len: usize);
+        deserialize_map();
+        deserialize_struct(name: &'static str, fields: &'static [&'static str]);
+        deserialize_enum(name: &'static str, variants: &'static [&'static str]);
+        deserialize_ignored_any();
+    }
+}
+
+/// Read `field=value, ...` in `Name(...)` as a map. The frame is pushed by
+/// the caller.
+struct KeywordArgumentsAccess<'a, R, B>(&'a mut Deserializer<R, B>);
+
+impl<'de, R: Read, B: Source<'de>> de::MapAccess<'de> for KeywordArgumentsAccess<'_, R, B> {
+    type Error = Error;
+
+    fn next_key_seed<K: de::DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+        let de = &mut *self.0;
+        de.debug("keyword next_key_seed");
+        if de.check_end_of_container()? {
+            return Ok(None);
+        }
+        let name = de.read_keyword_argument()?;
+        seed.deserialize(name.into_deserializer()).map(Some)
+    }
+
+    fn next_value_seed<V: de::DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+        self.0.debug("keyword next_value_seed");
+        seed.deserialize(&mut *self.0)
+    }
+}
+
+/// Read a list of pairs, like `[('a', 1), ('b', 2)]` in `OrderedDict(...)`,
+/// as a map. The list frame is pushed by the caller.
+struct PairsAccess<'a, R, B>(&'a mut Deserializer<R, B>);
+
+impl<'de, R: Read, B: Source<'de>> de::MapAccess<'de> for PairsAccess<'_, R, B> {
+    type Error = Error;
+
+    fn next_key_seed<K: de::DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+        let de = &mut *self.0;
+        de.debug("pairs next_key_seed");
+        if de.check_end_of_container()? {
+            return Ok(None);
+        }
+        if !(de.maybe_push_bracket(b'(', b')', None)? || de.maybe_push_bracket(b'[', b']', None)?) {
+            return de.type_mismatch("pair");
+        }
+        if de.check_end_of_container()? {
+            return Err(Error::TypeMismatch("pair", "empty tuple".into()));
+        }
+        de.check_nan_key()?;
+        seed.deserialize(KeyDeserializer(de)).map(Some)
+    }
+
+    fn next_value_seed<V: de::DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+        let de = &mut *self.0;
+        de.debug("pairs next_value_seed");
+        if de.check_end_of_container()? {
+            return Err(Error::TypeMismatch("pair", "1-tuple".into()));
+        }
+        let v = seed.deserialize(&mut *de)?;
+        if !de.check_end_of_container()? {
+            return de.type_mismatch("end of pair");
+        }
+        Ok(v)
+    }
+}
+
+/// Scan `buf` starting with `(`. Return `Str` or `Bytes` if it only
+/// contains adjacent str or bytes literals, `Tuple` otherwise, or `None`
+/// if `buf` is too short to decide.
+fn scan_parenthesized_type(buf: &[u8]) -> Option<PeekType> {
+    let mut kind = None;
+    let mut i = 1;
+    loop {
+        // Skip spaces and comments.
+        loop {
+            match *buf.get(i)? {
+                b if b.is_ascii_whitespace() => i += 1,
+                b'#' => {
+                    while *buf.get(i)? != b'\n' {
+                        i += 1;
+                    }
+                }
+                _ => break,
+            }
+        }
+        let this_kind = match *buf.get(i)? {
+            b')' => return Some(kind.unwrap_or(PeekType::Tuple)),
+            b'\'' | b'"' => PeekType::Str,
+            b'u' | b'U' => match str_prefix_len(buf.get(i..i + 2)?) {
+                Some(len) => {
+                    i += len;
+                    PeekType::Str
+                }
+                None => return Some(PeekType::Tuple),
+            },
+            b'b' | b'B' | b'r' | b'R' => match bytes_prefix_len(buf.get(i..i + 3)?) {
+                Some(len) => {
+                    i += len;
+                    PeekType::Bytes
+                }
+                None => return Some(PeekType::Tuple),
+            },
+            _ => return Some(PeekType::Tuple),
+        };
+        let quote = *buf.get(i)?;
+        if quote != b'\'' && quote != b'"' {
+            return Some(PeekType::Tuple);
+        }
+        // Mixed str and bytes are reported by `read_string` or `read_bytes`.
+        kind.get_or_insert(this_kind);
+        i = scan_string_end(buf, i)?;
+    }
+}
+
+/// Scan `buf` starting with `{`. Return `Set` if the first item is not
+/// followed by `:`, `Map` otherwise, or `None` if `buf` is too short to
+/// decide. `{}` is a dict.
+fn scan_braced_type(buf: &[u8]) -> Option<PeekType> {
+    if buf.first() != Some(&b'{') {
+        return Some(PeekType::Map);
+    }
+    let mut depth = 0;
+    let mut empty = true;
+    let mut i = 1;
+    loop {
+        let b = *buf.get(i)?;
+        match b {
+            b if b.is_ascii_whitespace() => {}
+            b'#' => {
+                while *buf.get(i)? != b'\n' {
+                    i += 1;
+                }
+            }
+            b'\'' | b'"' => {
+                i = scan_string_end(buf, i)?;
+                empty = false;
+                continue;
+            }
+            b'(' | b'[' | b'{' => depth += 1,
+            b'}' if depth == 0 && empty => return Some(PeekType::Map),
+            b',' | b'}' if depth == 0 => return Some(PeekType::Set),
+            b')' | b']' | b'}' => depth -= 1,
+            b':' if depth == 0 => return Some(PeekType::Map),
+            _ => {}
+        }
+        if !b.is_ascii_whitespace() && b != b'#' {
+            empty = false;
+        }
+        i += 1;
+    }
+}
+
+/// Return the index after the str or bytes literal whose opening quote is
+/// at `buf[i]`, or `None` if `buf` ends first.
+fn scan_string_end(buf: &[u8], mut i: usize) -> Option<usize> {
+    let quote = *buf.get(i)?;
+    let triple = buf.get(i..i + 3)? == [quote; 3];
+    i += if triple { 3 } else { 1 };
+    loop {
+        match *buf.get(i)? {
+            b'\\' => i += 2,
+            b if b == quote && !triple => return Some(i + 1),
+            b if b == quote && buf.get(i..i + 3)? == [quote; 3] => return Some(i + 3),
+            _ => i += 1,
+        }
+    }
+}
+
+/// Check `s` against the Python 3 grammar of int and float literals, with
+/// an optional leading `-`.
+fn is_python_number(s: &str) -> bool {
+    // Digits with single `_` between them.
+    fn digits(s: &[u8], is_digit: impl Fn(&u8) -> bool) -> bool {
+        s.split(|&b| b == b'_')
+            .all(|part| !part.is_empty() && part.iter().all(&is_digit))
+    }
+    let decimal = |s: &[u8]| digits(s, u8::is_ascii_digit);
+
+    let s = s.strip_prefix('-').unwrap_or(s).as_bytes();
+    if s.len() >= 2 && s[0] == b'0' && matches!(s[1], b'x' | b'X' | b'o' | b'O' | b'b' | b'B') {
+        // `_` is allowed right after the prefix, like `0x_ff`.
+        let rest = &s[2..];
+        let rest = rest.strip_prefix(&b"_"[..]).unwrap_or(rest);
+        return match s[1] {
+            b'x' | b'X' => digits(rest, u8::is_ascii_hexdigit),
+            b'o' | b'O' => digits(rest, |b| (b'0'..=b'7').contains(b)),
+            b'b' | b'B' => digits(rest, |b| *b == b'0' || *b == b'1'),
+            _ => false,
+        };
+    }
+    let (mantissa, exponent) = match s.iter().position(|&b| b == b'e' || b == b'E') {
+        Some(i) => (&s[..i], Some(&s[i + 1..])),
+        None => (s, None),
+    };
+    if let Some(exponent) = exponent {
+        let exponent = match exponent.first() {
+            Some(b'+') | Some(b'-') => &exponent[1..],
+            _ => exponent,
+        };
+        if !decimal(exponent) {
+            return false;
+        }
+    }
+    match mantissa.iter().position(|&b| b == b'.') {
+        Some(i) => {
+            let (int, frac) = (&mantissa[..i], &mantissa[i + 1..]);
+            (!int.is_empty() || !frac.is_empty())
+                && (int.is_empty() || decimal(int))
+                && (frac.is_empty() || decimal(frac))
+        }
+        // Floats like `01e1` can have leading zeros.
+        None if exponent.is_some() => decimal(mantissa),
+        // Ints cannot, except for zero, like `00` or `0_0`.
+        None => {
+            decimal(mantissa)
+                && (mantissa[0] != b'0' || mantissa.iter().all(|&b| b == b'0' || b == b'_'))
+        }
+    }
+}
+
+/// Check if `b` continues a keyword or number, so a keyword cannot end
+/// before it.
+fn is_word_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_' || b == b'.'
+}
+
+/// Whether `buf` starts with the prefix of an f-string, like `f` or `rf`,
+/// followed by a quote.
+fn is_fstring_prefix(buf: &[u8]) -> bool {
+    let len = buf
+        .iter()
+        .take(2)
+        .take_while(|b| b"fFrR".contains(b))
+        .count();
+    let prefix = buf[..len].to_ascii_lowercase();
+    let valid = matches!(&prefix[..], b"f" | b"rf" | b"fr");
+    valid && matches!(buf.get(len), Some(b'"') | Some(b'\''))
+}
+
+/// Length of the prefix of a str literal, 1 for `u` or `U` and 0 for none,
+/// if `buf` starts with the prefix followed by a quote.
+fn str_prefix_len(buf: &[u8]) -> Option<usize> {
+    let len = match buf.first() {
+        Some(b'u') | Some(b'U') => 1,
+        _ => 0,
+    };
+    match buf.get(len) {
+        Some(b'"') | Some(b'\'') => Some(len),
+        _ => None,
+    }
+}
+
+/// Length of the prefix of a bytes literal, like `b` or `Rb`, if `buf`
+/// starts with the prefix followed by a quote.
+fn bytes_prefix_len(buf: &[u8]) -> Option<usize> {
+    let len = buf
+        .iter()
+        .take(2)
+        .take_while(|b| b"bBrR".contains(b))
+        .count();
+    let prefix = buf[..len].to_ascii_lowercase();
+    let valid = matches!(&prefix[..], b"b" | b"rb" | b"br");
+    match buf.get(len) {
+        Some(b'"') | Some(b'\'') if valid => Some(len),
+        _ => None,
+    }
+}
+
+fn unpaired_surrogate(value: u32) -> Error {
+    Error::ParseString(format!("unpaired surrogate: \\u{:04x}", value).into())
+}
+
 fn hex_to_u4(b: u8) -> Option<u8> {
     match b {
         b'0'..=b'9' => Some(b - b'0'),
         b'a'..=b'f' => Some(b - b'a' + 10),
+        b'A'..=b'F' => Some(b - b'A' + 10),
+        _ => None,
+    }
+}
+
+#[cfg(feature = "unicode-names")]
+fn unicode_name_to_char(name: &str) -> Result<char> {
+    unicode_names2::character(name)
+        .ok_or_else(|| Error::ParseString(format!("unknown unicode name: {}", name).into()))
+}
+
+#[cfg(not(feature = "unicode-names"))]
+fn unicode_name_to_char(name: &str) -> Result<char> {
+    Err(Error::ParseString(
+        format!(
+            "\\N{{{}}} requires the \"unicode-names\" feature of serde_pyliteral",
+            name
+        )
+        .into(),
+    ))
+}
+
+trait FromStrRadix: Sized {
+    fn from_str_radix(s: &str, radix: u32) -> std::result::Result<Self, ParseIntError>;
+}
+
+macro_rules! impl_from_str_radix {
+    ($($t:ty)*) => {$(
+        impl FromStrRadix for $t {
+            fn from_str_radix(s: &str, radix: u32) -> std::result::Result<Self, ParseIntError> {
+                <$t>::from_str_radix(s, radix)
+            }
+        }
+    )*};
+}
+
+impl_from_str_radix!(i8 i16 i32 i64 i128 u8 u16 u32 u64 u128);
+
+/// Parse an integer with an optional sign and 0x, 0o, or 0b prefix.
+fn parse_int<T: FromStrRadix>(s: &str) -> Result<T> {
+    let (sign, unsigned) = match s.as_bytes().first() {
+        Some(b'+' | b'-') => s.split_at(1),
+        _ => ("", s),
+    };
+    let radix = match unsigned.get(..2) {
+        Some("0x" | "0X") => 16,
+        Some("0o" | "0O") => 8,
+        Some("0b" | "0B") => 2,
+        _ => return Ok(T::from_str_radix(s, 10)?),
+    };
+    let digits = &unsigned[2..];
+    Ok(T::from_str_radix(&format!("{}{}", sign, digits), radix)?)
+}
+
+fn oct_to_u3(b: u8) -> Option<u8> {
+    match b {
+        b'0'..=b'7' => Some(b - b'0'),
         _ => None,
     }
 }
 
-impl<'de, 'a, R: Read> de::SeqAccess<'de> for &'a mut Deserializer<R> {
+impl<'de, R: Read, B: Source<'de>> de::SeqAccess<'de> for &mut Deserializer<R, B> {
     type Error = Error;
 
     fn next_element_seed<T: de::DeserializeSeed<'de>>(
@@ -890,7 +3117,7 @@ impl<'de, 'a, R: Read> de::SeqAccess<'de> for &'a mut Deserializer<R> {
     }
 }
 
-impl<'de, 'a, R: Read> de::MapAccess<'de> for &'a mut Deserializer<R> {
+impl<'de, R: Read, B: Source<'de>> de::MapAccess<'de> for &mut Deserializer<R, B> {
     type Error = Error;
 
     fn next_key_seed<K: de::DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
@@ -898,7 +3125,8 @@ impl<'de, 'a, R: Read> de::MapAccess<'de> for &'a mut Deserializer<R> {
         if self.check_end_of_container()? {
             return Ok(None);
         }
-        seed.deserialize(&mut **self).map(Some)
+        self.check_nan_key()?;
+        seed.deserialize(KeyDeserializer(&mut **self)).map(Some)
     }
 
     fn next_value_seed<V: de::DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
@@ -912,7 +3140,7 @@ impl<'de, 'a, R: Read> de::MapAccess<'de> for &'a mut Deserializer<R> {
     }
 }
 
-impl<'de, 'a, R: Read> de::EnumAccess<'de> for &'a mut Deserializer<R> {
+impl<'de, R: Read, B: Source<'de>> de::EnumAccess<'de> for &mut Deserializer<R, B> {
     type Error = Error;
     type Variant = Self;
 
@@ -931,7 +3159,7 @@ impl<'de, 'a, R: Read> de::EnumAccess<'de> for &'a mut Deserializer<R> {
     }
 }
 
-impl<'de, 'a, R: Read> de::VariantAccess<'de> for &'a mut Deserializer<R> {
+impl<'de, R: Read, B: Source<'de>> de::VariantAccess<'de> for &mut Deserializer<R, B> {
     type Error = Error;
 
     fn unit_variant(self) -> Result<()> {
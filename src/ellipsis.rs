@@ -0,0 +1,52 @@
+use serde::de;
+use serde::Deserialize;
+use serde::Deserializer;
+use serde::Serialize;
+use serde::Serializer;
+use std::fmt;
+
+/// Name passed to `serialize_unit_struct` by `Ellipsis`. The crate's
+/// `Serializer` writes `...` instead of `()`.
+pub(crate) const ELLIPSIS_TOKEN: &str = "$serde_pyliteral::private::Ellipsis";
+
+/// Python's `...`, which appears in some reprs, like of recursive lists.
+///
+/// `...` can also be read into unit, like `()` or a unit struct. This type
+/// is for writing it, and for accepting only `...` when reading. Other
+/// serializers see a unit struct.
+///
+/// ```
+/// use serde_pyliteral::Ellipsis;
+///
+/// assert_eq!(serde_pyliteral::to_string(&(1, Ellipsis)).unwrap(), "(1,...)");
+/// let v: (i32, Ellipsis) = serde_pyliteral::from_str("(1, ...)").unwrap();
+/// assert_eq!(v, (1, Ellipsis));
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Ellipsis;
+
+impl Serialize for Ellipsis {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_unit_struct(ELLIPSIS_TOKEN)
+    }
+}
+
+impl<'de> Deserialize<'de> for Ellipsis {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_unit_struct(ELLIPSIS_TOKEN, EllipsisVisitor)
+    }
+}
+
+struct EllipsisVisitor;
+
+impl<'de> de::Visitor<'de> for EllipsisVisitor {
+    type Value = Ellipsis;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("...")
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Ellipsis, E> {
+        Ok(Ellipsis)
+    }
+}
@@ -1,5 +1,7 @@
 use std::borrow::Cow;
 use std::fmt;
+use std::io;
+use std::num::IntErrorKind;
 use std::num::ParseFloatError;
 use std::num::ParseIntError;
 use thiserror::Error;
@@ -12,9 +14,27 @@ pub enum Error {
     #[error("expect {0}, got {1}")]
     TypeMismatch(&'static str, Cow<'static, str>),
 
+    /// The input ended where the named value was expected.
+    #[error("expect {0}, got end")]
+    UnexpectedEnd(&'static str),
+
+    /// The input at where the named value was expected does not start any
+    /// type this crate can read. The `String` is the text found there.
+    #[error("expect {0}, got unknown type ({1:?})")]
+    UnknownType(&'static str, String),
+
     #[error(transparent)]
     ParseInt(#[from] ParseIntError),
 
+    /// Like `ParseInt`, with the number being parsed, without `_`, and the
+    /// name of the target type, like `u8`.
+    #[error("cannot parse '{text}' as {target}: {source}")]
+    ParseIntAs {
+        text: String,
+        target: &'static str,
+        source: ParseIntError,
+    },
+
     #[error(transparent)]
     ParseFloat(#[from] ParseFloatError),
 
@@ -24,14 +44,170 @@ pub enum Error {
     #[error("cannot parse bytes: {0}")]
     ParseBytes(Cow<'static, str>),
 
+    /// The input ended inside a str or bytes literal.
+    #[error("cannot parse string: incomplete str")]
+    IncompleteStr,
+
     #[error("cannot auto-detect type: {0:?}")]
     ParseAny(String),
 
+    #[error("invalid Python number: {0:?}")]
+    ParseNumber(String),
+
+    /// A Python expression that is not a literal, like an f-string or a
+    /// call, was found where a value is expected.
+    #[error("{0} is not a literal and cannot be evaluated")]
+    UnsupportedExpression(Cow<'static, str>),
+
     #[error("cannot serialize nan")]
     NaN,
 
+    #[error("nan cannot be a dict key")]
+    NaNKey,
+
+    #[error("unsupported encoding {0}, detected by its byte order mark; only UTF-8 is supported")]
+    UnsupportedEncoding(&'static str),
+
+    #[error("trailing data after the value")]
+    TrailingData,
+
+    #[error("nesting depth exceeds limit ({0})")]
+    DepthLimitExceeded(usize),
+
+    #[error("{0} length exceeds limit ({1})")]
+    LengthLimitExceeded(&'static str, usize),
+
     #[error(transparent)]
     Io(#[from] std::io::Error),
+
+    /// `error` happened at byte `offset`, or `line` and `column`, of the
+    /// input. `line` and `column` start from 1. `column` counts characters.
+    #[error("{error} at line {line} column {column}")]
+    At {
+        offset: usize,
+        line: usize,
+        column: usize,
+        error: Box<Error>,
+    },
+}
+
+/// Rough kind of an [`Error`], like `serde_json::error::Category`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Category {
+    /// Reading from the underlying reader failed.
+    Io,
+    /// The input is not a valid literal.
+    Syntax,
+    /// The input is a valid literal, but does not fit the type being
+    /// deserialized. Serialization errors are also `Data`.
+    Data,
+    /// The input ended in the middle of a literal.
+    Eof,
+}
+
+impl Error {
+    /// Line of the input the error happened at, starting from 1. `None` if
+    /// the error has no position, like serialization errors.
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            Error::At { line, .. } => Some(*line),
+            _ => None,
+        }
+    }
+
+    /// Column of the input the error happened at, starting from 1. Counts
+    /// characters, not bytes.
+    pub fn column(&self) -> Option<usize> {
+        match self {
+            Error::At { column, .. } => Some(*column),
+            _ => None,
+        }
+    }
+
+    /// Byte offset of the input the error happened at.
+    pub fn offset(&self) -> Option<usize> {
+        match self {
+            Error::At { offset, .. } => Some(*offset),
+            _ => None,
+        }
+    }
+
+    /// Categorize the error, like `serde_json::Error::classify`.
+    pub fn classify(&self) -> Category {
+        match self {
+            Error::At { error, .. } => error.classify(),
+            Error::Io(e) if e.kind() == io::ErrorKind::UnexpectedEof => Category::Eof,
+            Error::Io(_) => Category::Io,
+            Error::UnexpectedEnd(_) | Error::IncompleteStr => Category::Eof,
+            Error::ParseAny(s) if s.is_empty() => Category::Eof,
+            Error::TypeMismatch(expected, _) => {
+                let punctuation = matches!(
+                    *expected,
+                    "comma" | "colon" | ")" | "pair" | "end of pair" | "keyword argument"
+                );
+                if punctuation {
+                    Category::Syntax
+                } else {
+                    Category::Data
+                }
+            }
+            Error::ParseInt(e) | Error::ParseIntAs { source: e, .. } => match e.kind() {
+                IntErrorKind::PosOverflow | IntErrorKind::NegOverflow => Category::Data,
+                _ => Category::Syntax,
+            },
+            Error::ParseFloat(_)
+            | Error::ParseString(_)
+            | Error::ParseBytes(_)
+            | Error::UnknownType(..)
+            | Error::ParseAny(_)
+            | Error::ParseNumber(_)
+            | Error::UnsupportedExpression(_)
+            | Error::UnsupportedEncoding(_)
+            | Error::TrailingData
+            | Error::DepthLimitExceeded(_)
+            | Error::LengthLimitExceeded(..) => Category::Syntax,
+            Error::Generic(_) | Error::NaN | Error::NaNKey => Category::Data,
+        }
+    }
+
+    /// Render the error with the line of `src` it happened at, and a caret
+    /// under the column, like:
+    ///
+    /// ```plain
+    /// error: expect bool, got str
+    ///  --> line 2, column 10
+    ///   |
+    /// 2 |     'b': 'x',
+    ///   |          ^
+    /// ```
+    ///
+    /// `src` should be the input passed to `from_str`. Errors without a
+    /// position are rendered as a single line.
+    pub fn display_with_source(&self, src: &str) -> String {
+        let (offset, error) = match self {
+            Error::At { offset, error, .. } => (*offset, error),
+            _ => return format!("error: {}", self),
+        };
+        let mut offset = offset.min(src.len());
+        while !src.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        let line_start = src[..offset].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = src[offset..].find('\n').map_or(src.len(), |i| offset + i);
+        let line = src[..line_start].matches('\n').count() + 1;
+        let text = src[line_start..line_end].trim_end_matches('\r');
+        // Keep tabs so the caret lines up.
+        let pad: String = src[line_start..offset]
+            .chars()
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        let column = src[line_start..offset].chars().count() + 1;
+        let gutter = " ".repeat(line.to_string().len());
+        format!(
+            "error: {}\n{}--> line {}, column {}\n{} |\n{} | {}\n{} | {}^",
+            error, gutter, line, column, gutter, line, text, gutter, pad
+        )
+    }
 }
 
 impl serde::ser::Error for Error {
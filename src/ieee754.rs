@@ -1,9 +1,20 @@
 pub(crate) trait IeeeFloat<const E: u16, const F: u16> {
     /// 2-based exponent. If the number can be written as `1.__ * (2 ** x)`,
     /// then this function returns the `x`.
+    /// Zero returns 0.
     fn exponent(&self) -> i16 {
         let bits = self.to_u64_bits();
-        (((bits >> F) & ((1 << E) - 1)) as i16) + 1 - (1 << (E - 1))
+        let biased = ((bits >> F) & ((1 << E) - 1)) as i16;
+        let mantissa = bits & ((1 << F) - 1);
+        // Exponent of the smallest normal number.
+        let min = 2 - (1 << (E - 1));
+        match (biased, mantissa) {
+            (0, 0) => 0,
+            // Subnormal, `0.__ * (2 ** min)`. Shift the highest set bit of
+            // the mantissa to be the leading `1`.
+            (0, _) => min - (F as i16) + (63 - mantissa.leading_zeros() as i16),
+            _ => biased - 1 + min,
+        }
     }
 
     /// Whether scientific notation is more proper to display the number.
@@ -22,7 +33,10 @@ pub(crate) trait IeeeFloat<const E: u16, const F: u16> {
             format!("{}", self)
         };
         // If it looks like an integer, append '.' to make it an explicit float.
-        if s.as_bytes().iter().all(|&b| b >= b'0' && b <= b'9') {
+        if s.trim_start_matches('-')
+            .bytes()
+            .all(|b| b.is_ascii_digit())
+        {
             s.push('.');
         }
         s
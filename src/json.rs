@@ -0,0 +1,311 @@
+//! Conversion between Python literals and [`serde_json::Value`].
+//!
+//! The conversion is lossy:
+//! - Tuples and sets become JSON arrays. The empty tuple `()` becomes `[]`.
+//! - Bytes become JSON arrays of integers, since JSON has no bytes type.
+//! - Complex numbers become `[real, imag]` arrays of floats.
+//! - Infinite floats become `null`, since JSON cannot represent them.
+//! - `...` becomes `null` with [`PyLiteral::into_json`]. Like `()`, it is
+//!   read as unit, and becomes `[]`, with [`pyliteral_string_to_json_value`].
+//! - Dicts with non-string keys cannot be converted, since JSON only
+//!   supports string keys. [`PyLiteral::into_json`] writes such keys as
+//!   Python literals instead, like `"(1, 2)"`.
+//!
+//! To keep tuples and sets apart from lists, deserialize into
+//! [`PyLiteral`] instead, and convert to JSON only when needed.
+//!
+//! Deserializing into [`serde_json::Value`] directly, like
+//! `serde_pyliteral::from_str::<serde_json::Value>`, reads `()` as `null`
+//! instead. `deserialize_any` reports `()` as unit, which is how unit is
+//! serialized, so untagged enums can read unit variants back.
+
+use crate::PyLiteral;
+use crate::Result;
+use serde::de;
+use serde::de::DeserializeSeed;
+use serde::Serialize;
+use serde::Serializer;
+use serde_json::Map;
+use serde_json::Number;
+use serde_json::Value;
+use std::convert::TryFrom;
+use std::fmt;
+
+/// Serialize a JSON value to a Python literal string.
+pub fn json_value_to_pyliteral_string(value: &Value) -> Result<String> {
+    crate::to_string(&JsonValueRef(value))
+}
+
+/// Parse a Python literal string into a JSON value. Like `from_str`, a
+/// UTF-8 byte order mark is skipped, and errors have positions.
+pub fn pyliteral_string_to_json_value(s: &str) -> Result<Value> {
+    let value: JsonValue = crate::from_str(s)?;
+    Ok(value.0)
+}
+
+impl PyLiteral {
+    /// Convert to a JSON value. See the [module](self) documentation for
+    /// what is lost.
+    pub fn into_json(self) -> Value {
+        match self {
+            PyLiteral::None | PyLiteral::Ellipsis => Value::Null,
+            PyLiteral::Bool(v) => Value::Bool(v),
+            PyLiteral::Int(v) => {
+                if let Ok(v) = i64::try_from(v) {
+                    v.into()
+                } else if let Ok(v) = u64::try_from(v) {
+                    v.into()
+                } else {
+                    Number::from_f64(v as f64).map_or(Value::Null, Value::Number)
+                }
+            }
+            PyLiteral::Float(v) => Number::from_f64(v).map_or(Value::Null, Value::Number),
+            PyLiteral::Complex(real, imag) => {
+                let part = |v| PyLiteral::Float(v).into_json();
+                Value::Array(vec![part(real), part(imag)])
+            }
+            PyLiteral::Str(v) => Value::String(v),
+            PyLiteral::Bytes(v) => Value::Array(v.into_iter().map(Value::from).collect()),
+            PyLiteral::List(v) | PyLiteral::Tuple(v) | PyLiteral::Set(v) => {
+                Value::Array(v.into_iter().map(PyLiteral::into_json).collect())
+            }
+            PyLiteral::Dict(v) => {
+                let map = v.into_iter().map(|(k, v)| {
+                    let k = match k {
+                        PyLiteral::Str(k) => k,
+                        // Not expected to fail: `PyLiteral` is always serializable.
+                        k => crate::to_string(&k).unwrap_or_default(),
+                    };
+                    (k, v.into_json())
+                });
+                Value::Object(map.collect())
+            }
+        }
+    }
+
+    /// Convert from a JSON value. Arrays become lists. Numbers that do not
+    /// fit in `i64` or `u64` become floats.
+    pub fn from_json(value: Value) -> Self {
+        match value {
+            Value::Null => PyLiteral::None,
+            Value::Bool(v) => PyLiteral::Bool(v),
+            Value::Number(v) => match (v.as_i64(), v.as_u64()) {
+                (Some(v), _) => v.into(),
+                (_, Some(v)) => v.into(),
+                _ => PyLiteral::Float(v.as_f64().unwrap_or(f64::NAN)),
+            },
+            Value::String(v) => PyLiteral::Str(v),
+            Value::Array(v) => v.into_iter().map(PyLiteral::from_json).collect(),
+            Value::Object(v) => PyLiteral::Dict(
+                v.into_iter()
+                    .map(|(k, v)| (PyLiteral::Str(k), PyLiteral::from_json(v)))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+// `Value::Null` serializes as unit, which is `()` in Python. Write `None` instead.
+struct JsonValueRef<'a>(&'a Value);
+
+impl Serialize for JsonValueRef<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        match self.0 {
+            Value::Null => serializer.serialize_none(),
+            Value::Array(values) => serializer.collect_seq(values.iter().map(JsonValueRef)),
+            Value::Object(map) => {
+                serializer.collect_map(map.iter().map(|(k, v)| (k, JsonValueRef(v))))
+            }
+            value => value.serialize(serializer),
+        }
+    }
+}
+
+/// JSON value read by `JsonValueSeed`, so `from_str` can read it.
+struct JsonValue(Value);
+
+impl<'de> de::Deserialize<'de> for JsonValue {
+    fn deserialize<D: de::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        JsonValueSeed.deserialize(deserializer).map(JsonValue)
+    }
+}
+
+#[derive(Clone, Copy)]
+struct JsonValueSeed;
+
+impl<'de> de::DeserializeSeed<'de> for JsonValueSeed {
+    type Value = Value;
+
+    fn deserialize<D: de::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> std::result::Result<Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> de::Visitor<'de> for JsonValueSeed {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a Python literal")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> std::result::Result<Value, E> {
+        Ok(Value::Bool(v))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> std::result::Result<Value, E> {
+        Ok(Value::Number(v.into()))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> std::result::Result<Value, E> {
+        Ok(Value::Number(v.into()))
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> std::result::Result<Value, E> {
+        Ok(Number::from_f64(v).map_or(Value::Null, Value::Number))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> std::result::Result<Value, E> {
+        Ok(Value::String(v.to_string()))
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> std::result::Result<Value, E> {
+        Ok(Value::String(v))
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> std::result::Result<Value, E> {
+        Ok(Value::Array(v.iter().map(|&b| b.into()).collect()))
+    }
+
+    fn visit_none<E: de::Error>(self) -> std::result::Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_some<D: de::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> std::result::Result<Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_unit<E: de::Error>(self) -> std::result::Result<Value, E> {
+        // Unit is read from `()`, an empty tuple.
+        Ok(Value::Array(Vec::new()))
+    }
+
+    fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> std::result::Result<Value, A::Error> {
+        let mut values = Vec::with_capacity(seq.size_hint().unwrap_or_default());
+        while let Some(v) = seq.next_element_seed(self)? {
+            values.push(v);
+        }
+        Ok(Value::Array(values))
+    }
+
+    fn visit_map<A: de::MapAccess<'de>>(self, mut map: A) -> std::result::Result<Value, A::Error> {
+        let mut values = Map::new();
+        while let Some(k) = map.next_key::<String>()? {
+            let v = map.next_value_seed(self)?;
+            values.insert(k, v);
+        }
+        Ok(Value::Object(values))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn p2j(s: &str) -> String {
+        pyliteral_string_to_json_value(s).unwrap().to_string()
+    }
+
+    fn j2p(s: &str) -> String {
+        let v: Value = serde_json::from_str(s).unwrap();
+        json_value_to_pyliteral_string(&v).unwrap()
+    }
+
+    #[test]
+    fn test_pyliteral_to_json() {
+        assert_eq!(p2j("None"), "null");
+        assert_eq!(p2j("[1, -2, 3.5, True, 'a']"), r#"[1,-2,3.5,true,"a"]"#);
+        assert_eq!(p2j("(1, (2,), ())"), "[1,[2],[]]");
+        assert_eq!(p2j("[(), None]"), "[[],null]");
+        let v: Value = crate::from_str("[(), None]").unwrap();
+        assert_eq!(v.to_string(), "[null,null]");
+        assert_eq!(p2j("b'a\\x00'"), "[97,0]");
+        assert_eq!(p2j("[1e999, -1e999]"), "[null,null]");
+        assert_eq!(p2j("{'a': {'b': [None]}}"), r#"{"a":{"b":[null]}}"#);
+        assert_eq!(p2j("[..., None]"), "[[],null]");
+        assert!(pyliteral_string_to_json_value("{1: 2}").is_err());
+
+        // Checked like `from_str`.
+        assert_eq!(p2j("\u{feff}[1]"), "[1]");
+        let src = "[1,\n 2x]";
+        let err = pyliteral_string_to_json_value(src).unwrap_err();
+        assert_eq!((err.line(), err.column()), (Some(2), Some(3)));
+        assert_eq!(
+            err.display_with_source(src),
+            crate::from_str::<Value>(src)
+                .unwrap_err()
+                .display_with_source(src)
+        );
+    }
+
+    #[test]
+    fn test_json_to_pyliteral() {
+        assert_eq!(j2p("null"), "None");
+        assert_eq!(j2p(r#"[1,-2,3.5,true,"a"]"#), r#"[1,-2,3.5,True,"a"]"#);
+        assert_eq!(j2p(r#"{"a":{"b":[null]}}"#), r#"{"a":{"b":[None]}}"#);
+    }
+
+    #[test]
+    fn test_pyliteral_into_json() {
+        let j = |s: &str| {
+            crate::from_str::<PyLiteral>(s)
+                .unwrap()
+                .into_json()
+                .to_string()
+        };
+        assert_eq!(j("[None, True, -1, 2.5, 'a']"), r#"[null,true,-1,2.5,"a"]"#);
+        assert_eq!(j("((1, (2,)), [()], {3})"), "[[1,[2]],[[]],[3]]");
+        assert_eq!(j("b'a\\x00'"), "[97,0]");
+        assert_eq!(j("[..., None]"), "[null,null]");
+        assert_eq!(j("[1+2j, complex(1e999)]"), "[[1.0,2.0],[null,0.0]]");
+        assert_eq!(
+            j("[1e999, 0xffffffffffffffff]"),
+            "[null,18446744073709551615]"
+        );
+        assert_eq!(j("[-0x10000000000000000]"), "[-1.8446744073709552e+19]");
+        assert_eq!(
+            j("{'a': {(1, 2): 'x', None: 1}}"),
+            r#"{"a":{"(1,2)":"x","None":1}}"#
+        );
+    }
+
+    #[test]
+    fn test_pyliteral_from_json() {
+        let p = |s: &str| {
+            let v = PyLiteral::from_json(serde_json::from_str(s).unwrap());
+            crate::to_string(&v).unwrap()
+        };
+        assert_eq!(p(r#"[null,true,-1,2.5,"a"]"#), r#"[None,True,-1,2.5,"a"]"#);
+        assert_eq!(p("[[1,[2]],[]]"), "[[1,[2]],[]]");
+        assert_eq!(p("18446744073709551615"), "18446744073709551615");
+        assert_eq!(p(r#"{"b":{"a":[]}}"#), r#"{"b":{"a":[]}}"#);
+
+        // Round trip through JSON turns tuples into lists.
+        let v: PyLiteral = crate::from_str("{'a': (1, [2])}").unwrap();
+        let v = PyLiteral::from_json(v.into_json());
+        assert_eq!(crate::to_string(&v).unwrap(), r#"{"a":[1,[2]]}"#);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let s = r#"{"a":[1,2.5,"x",{"b":null}],"c":false}"#;
+        assert_eq!(p2j(&j2p(s)), s);
+    }
+}
@@ -1,23 +1,55 @@
+#[cfg(feature = "datetime")]
+pub mod datetime;
 pub mod de;
+mod ellipsis;
 pub mod error;
 mod ieee754;
+#[cfg(feature = "json")]
+pub mod json;
 mod peek;
+mod probe;
 pub mod ser;
+mod set;
+pub mod tagged;
 mod unicode;
+pub mod value;
 
 #[cfg(test)]
 mod tests;
 
+pub use error::Category;
 pub use error::Error;
 pub type Result<T> = std::result::Result<T, Error>;
 
 pub use ser::to_string;
 pub use ser::to_string_pretty;
+pub use ser::to_string_with_config;
 pub use ser::to_vec;
 pub use ser::to_vec_pretty;
+pub use ser::to_vec_with_config;
 pub use ser::to_writer;
+pub use ser::to_writer_flush;
 pub use ser::to_writer_pretty;
+pub use ser::to_writer_seq;
+pub use ser::to_writer_with_config;
 
 pub use de::from_reader;
+pub use de::from_reader_strict;
+pub use de::from_reader_with_config;
 pub use de::from_slice;
+pub use de::from_slice_strict;
+pub use de::from_slice_with_config;
 pub use de::from_str;
+pub use de::from_str_strict;
+pub use de::from_str_tuple;
+pub use de::from_str_with_config;
+
+pub use ellipsis::Ellipsis;
+
+pub use set::PySet;
+pub use set::Set;
+
+pub use value::parse_dict_entries;
+pub use value::reformat;
+pub use value::PyLiteral;
+pub use value::Value;
@@ -5,6 +5,10 @@ use std::io::Read;
 pub struct PeekRead<R> {
     reader: R,
     peek: VecDeque<u8>,
+    // Position after consumed bytes. Peeked bytes are not counted.
+    offset: usize,
+    line: usize,
+    column: usize,
 }
 
 impl<R: Read> PeekRead<R> {
@@ -12,8 +16,22 @@ impl<R: Read> PeekRead<R> {
         Self {
             reader,
             peek: VecDeque::new(),
+            offset: 0,
+            line: 1,
+            column: 1,
         }
     }
+
+    /// Number of bytes consumed so far.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Line and column of the next byte, starting from 1. Columns count
+    /// UTF-8 characters.
+    pub fn line_column(&self) -> (usize, usize) {
+        (self.line, self.column)
+    }
 }
 
 impl<R: Read> PeekRead<R> {
@@ -37,6 +55,22 @@ impl<R: Read> PeekRead<R> {
         Ok(())
     }
 
+    /// Peek up to `n` bytes without copying. Return less than `n` bytes on
+    /// EOF.
+    pub fn peek_slice(&mut self, n: usize) -> io::Result<&[u8]> {
+        let mut buf = [0u8; 32];
+        while self.peek.len() < n {
+            let size = (n - self.peek.len()).min(buf.len());
+            let read = self.reader.read(&mut buf[..size])?;
+            if read == 0 {
+                break;
+            }
+            self.peek.extend(&buf[..read]);
+        }
+        let len = n.min(self.peek.len());
+        Ok(&self.peek.make_contiguous()[..len])
+    }
+
     /// Read while `predicate` returns `true`. `predicate` takes the next
     /// byte, and the current state to decide whether to accept the byte
     /// or not. `predicate` should mutate `T` in place if it decides to
@@ -52,14 +86,28 @@ impl<R: Read> PeekRead<R> {
             if buf.is_empty() {
                 break;
             }
+            // Consume accepted bytes in one go. Accepted bytes are consumed
+            // even if the predicate errors out, so error offsets point at
+            // the offending byte.
+            let mut accepted = 0;
+            let mut stop = false;
             for &b in buf.iter() {
-                if predicate(b, &mut result)? {
-                    // predicate accepts the byte.
-                    self.skip(1)?;
-                } else {
-                    break 'a;
+                match predicate(b, &mut result) {
+                    Ok(true) => accepted += 1,
+                    Ok(false) => {
+                        stop = true;
+                        break;
+                    }
+                    Err(e) => {
+                        self.skip(accepted)?;
+                        return Err(e);
+                    }
                 }
             }
+            self.skip(accepted)?;
+            if stop {
+                break 'a;
+            }
         }
         Ok(result)
     }
@@ -88,6 +136,16 @@ impl<R: Read> Read for PeekRead<R> {
         if n < buf.len() {
             n += self.reader.read(&mut buf[n..])?;
         }
+        self.offset += n;
+        for &b in &buf[..n] {
+            if b == b'\n' {
+                self.line += 1;
+                self.column = 1;
+            } else if b & 0xc0 != 0x80 {
+                // Not a UTF-8 continuation byte.
+                self.column += 1;
+            }
+        }
         Ok(n)
     }
 }
@@ -102,16 +160,68 @@ mod tests {
         assert_eq!(peek(2, &mut v), b"12");
         assert_eq!(peek(1, &mut v), b"1");
         assert_eq!(peek(3, &mut v), b"123");
+        assert_eq!(v.offset(), 0);
 
         assert_eq!(read(1, &mut v), b"1");
         assert_eq!(peek(2, &mut v), b"23");
+        assert_eq!(v.peek_slice(4).unwrap(), b"2345");
         assert_eq!(read(4, &mut v), b"2345");
+        assert_eq!(v.offset(), 5);
 
+        assert_eq!(v.peek_slice(3).unwrap(), b"6");
         assert_eq!(read(3, &mut v), b"6..");
         assert_eq!(read(3, &mut v), b"...");
+        assert_eq!(v.offset(), 6);
         assert_eq!(peek(2, &mut v), b"");
     }
 
+    #[test]
+    fn test_line_column() {
+        let mut v = PeekRead::from_reader("a\n\u{6587}\u{5b57}\r\n\nb".as_bytes());
+        assert_eq!(v.line_column(), (1, 1));
+        read(1, &mut v);
+        assert_eq!(v.line_column(), (1, 2));
+        read(1, &mut v);
+        assert_eq!(v.line_column(), (2, 1));
+        read(3, &mut v);
+        assert_eq!(v.line_column(), (2, 2));
+        peek(10, &mut v);
+        assert_eq!(v.line_column(), (2, 2));
+        read(5, &mut v);
+        assert_eq!(v.line_column(), (3, 1));
+        read(1, &mut v);
+        assert_eq!(v.line_column(), (4, 1));
+        assert_eq!(v.offset(), 11);
+    }
+
+    #[test]
+    fn test_read_while() {
+        let input = format!("{}-b", "a".repeat(100));
+        let mut v = PeekRead::from_reader(input.as_bytes());
+        let count = v
+            .read_while(|b, n: &mut usize| {
+                *n += 1;
+                Ok::<_, io::Error>(b == b'a')
+            })
+            .unwrap();
+        // The predicate also sees the rejected byte.
+        assert_eq!(count, 101);
+        assert_eq!(v.offset(), 100);
+        assert_eq!(peek(2, &mut v), b"-b");
+
+        // Bytes accepted before an error are consumed.
+        let r = v.read_while(|b, _: &mut ()| match b {
+            b'-' => Ok(true),
+            _ => Err(io::Error::other("x")),
+        });
+        assert!(r.is_err());
+        assert_eq!(v.offset(), 101);
+
+        let r = v.read_while(|_, _: &mut ()| Ok::<_, io::Error>(true));
+        assert!(r.is_ok());
+        assert_eq!(v.offset(), 102);
+    }
+
     fn peek(n: usize, peek: &mut PeekRead<&[u8]>) -> Vec<u8> {
         let mut buf = vec![b'.'; n];
         peek.peek(&mut buf).unwrap();
@@ -120,7 +230,7 @@ mod tests {
 
     fn read(n: usize, peek: &mut PeekRead<&[u8]>) -> Vec<u8> {
         let mut buf = vec![b'.'; n];
-        peek.read(&mut buf).unwrap();
+        let _n = peek.read(&mut buf).unwrap();
         buf
     }
 }
@@ -0,0 +1,186 @@
+use crate::Error;
+use crate::Result;
+use serde::ser;
+use serde::ser::Impossible;
+use serde::Serialize;
+
+/// Check if `value` serializes as `None`.
+pub(crate) fn is_none<T: ?Sized + Serialize>(value: &T) -> bool {
+    value.serialize(NoneProbe).unwrap_or(false)
+}
+
+/// Serializer that only tells whether a value is `None`. Containers are
+/// rejected with an error, which also means "not `None`".
+struct NoneProbe;
+
+macro_rules! not_none {
+    ($($name:ident($($t:ty),*);)*) => {$(
+        fn $name(self, $(_: $t),*) -> Result<bool> {
+            Ok(false)
+        }
+    )*};
+}
+
+macro_rules! not_none_container {
+    ($($name:ident($($t:ty),*) -> $ret:ident;)*) => {$(
+        fn $name(self, $(_: $t),*) -> Result<Self::$ret> {
+            Err(Error::Generic(String::new()))
+        }
+    )*};
+}
+
+impl ser::Serializer for NoneProbe {
+    type Ok = bool;
+    type Error = Error;
+    type SerializeSeq = Impossible<bool, Error>;
+    type SerializeTuple = Impossible<bool, Error>;
+    type SerializeTupleStruct = Impossible<bool, Error>;
+    type SerializeTupleVariant = Impossible<bool, Error>;
+    type SerializeMap = Impossible<bool, Error>;
+    type SerializeStruct = Impossible<bool, Error>;
+    type SerializeStructVariant = Impossible<bool, Error>;
+
+    fn serialize_none(self) -> Result<bool> {
+        Ok(true)
+    }
+
+    not_none! {
+        serialize_bool(bool);
+        serialize_i8(i8);
+        serialize_i16(i16);
+        serialize_i32(i32);
+        serialize_i64(i64);
+        serialize_i128(i128);
+        serialize_u8(u8);
+        serialize_u16(u16);
+        serialize_u32(u32);
+        serialize_u64(u64);
+        serialize_u128(u128);
+        serialize_f32(f32);
+        serialize_f64(f64);
+        serialize_char(char);
+        serialize_str(&str);
+        serialize_bytes(&[u8]);
+        serialize_unit();
+        serialize_unit_struct(&'static str);
+        serialize_unit_variant(&'static str, u32, &'static str);
+    }
+
+    not_none_container! {
+        serialize_seq(Option<usize>) -> SerializeSeq;
+        serialize_tuple(usize) -> SerializeTuple;
+        serialize_tuple_struct(&'static str, usize) -> SerializeTupleStruct;
+        serialize_tuple_variant(&'static str, u32, &'static str, usize) -> SerializeTupleVariant;
+        serialize_map(Option<usize>) -> SerializeMap;
+        serialize_struct(&'static str, usize) -> SerializeStruct;
+        serialize_struct_variant(&'static str, u32, &'static str, usize) -> SerializeStructVariant;
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, _value: &T) -> Result<bool> {
+        Ok(false)
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<bool> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<bool> {
+        Ok(false)
+    }
+}
+
+/// Get the float `value` serializes as. Ints are not accepted.
+pub(crate) fn as_f64<T: ?Sized + Serialize>(value: &T) -> Option<f64> {
+    value.serialize(FloatProbe).ok()
+}
+
+/// Serializer that only accepts a float. Anything else is an error.
+struct FloatProbe;
+
+macro_rules! not_float {
+    ($($name:ident($($t:ty),*) -> $ret:ty;)*) => {$(
+        fn $name(self, $(_: $t),*) -> Result<$ret> {
+            Err(Error::Generic(String::new()))
+        }
+    )*};
+}
+
+impl ser::Serializer for FloatProbe {
+    type Ok = f64;
+    type Error = Error;
+    type SerializeSeq = Impossible<f64, Error>;
+    type SerializeTuple = Impossible<f64, Error>;
+    type SerializeTupleStruct = Impossible<f64, Error>;
+    type SerializeTupleVariant = Impossible<f64, Error>;
+    type SerializeMap = Impossible<f64, Error>;
+    type SerializeStruct = Impossible<f64, Error>;
+    type SerializeStructVariant = Impossible<f64, Error>;
+
+    fn serialize_f32(self, v: f32) -> Result<f64> {
+        Ok(v.into())
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<f64> {
+        Ok(v)
+    }
+
+    not_float! {
+        serialize_bool(bool) -> f64;
+        serialize_i8(i8) -> f64;
+        serialize_i16(i16) -> f64;
+        serialize_i32(i32) -> f64;
+        serialize_i64(i64) -> f64;
+        serialize_i128(i128) -> f64;
+        serialize_u8(u8) -> f64;
+        serialize_u16(u16) -> f64;
+        serialize_u32(u32) -> f64;
+        serialize_u64(u64) -> f64;
+        serialize_u128(u128) -> f64;
+        serialize_char(char) -> f64;
+        serialize_str(&str) -> f64;
+        serialize_bytes(&[u8]) -> f64;
+        serialize_none() -> f64;
+        serialize_unit() -> f64;
+        serialize_unit_struct(&'static str) -> f64;
+        serialize_unit_variant(&'static str, u32, &'static str) -> f64;
+        serialize_seq(Option<usize>) -> Self::SerializeSeq;
+        serialize_tuple(usize) -> Self::SerializeTuple;
+        serialize_tuple_struct(&'static str, usize) -> Self::SerializeTupleStruct;
+        serialize_tuple_variant(&'static str, u32, &'static str, usize) -> Self::SerializeTupleVariant;
+        serialize_map(Option<usize>) -> Self::SerializeMap;
+        serialize_struct(&'static str, usize) -> Self::SerializeStruct;
+        serialize_struct_variant(&'static str, u32, &'static str, usize) -> Self::SerializeStructVariant;
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, _value: &T) -> Result<f64> {
+        Err(Error::Generic(String::new()))
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<f64> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<f64> {
+        Err(Error::Generic(String::new()))
+    }
+}
@@ -1,5 +1,10 @@
+use crate::de::DEFAULT_MAX_DEPTH;
+use crate::ellipsis;
 use crate::ieee754::IeeeFloat;
+use crate::probe;
+use crate::set;
 use crate::unicode;
+use crate::value;
 use crate::Error;
 use crate::Result;
 use serde::ser::SerializeMap;
@@ -11,19 +16,72 @@ use serde::ser::SerializeTupleStruct;
 use serde::ser::SerializeTupleVariant;
 use serde::Serialize;
 use std::borrow::Cow;
+use std::fmt;
 use std::io;
 use std::io::Write;
+use std::sync::Arc;
 
+/// Serialize `value` into `writer`.
+///
+/// `writer` is not flushed. Data written to a buffered writer, like
+/// `BufWriter`, can be lost if it is not flushed before being dropped. Use
+/// `to_writer_flush` to flush at the end.
 pub fn to_writer<W: io::Write, T: ?Sized + Serialize>(writer: W, value: &T) -> Result<()> {
     let mut ser = Serializer::from_writer(writer);
     value.serialize(&mut ser)
 }
 
+/// Like `to_writer`, but flush `writer` at the end.
+pub fn to_writer_flush<W: io::Write, T: ?Sized + Serialize>(writer: W, value: &T) -> Result<()> {
+    let mut ser = Serializer::from_writer(writer);
+    value.serialize(&mut ser)?;
+    ser.finish()
+}
+
 pub fn to_writer_pretty<W: io::Write, T: ?Sized + Serialize>(writer: W, value: &T) -> Result<()> {
     let mut ser = Serializer::from_writer(writer).pretty();
     value.serialize(&mut ser)
 }
 
+/// Serialize `value` into `writer`, with options set by `config`.
+///
+/// Like `to_writer`, `writer` is not flushed.
+pub fn to_writer_with_config<W: io::Write, T: ?Sized + Serialize>(
+    writer: W,
+    value: &T,
+    config: Config,
+) -> Result<()> {
+    let mut ser = Serializer::from_writer(writer).with_config(config);
+    value.serialize(&mut ser)
+}
+
+/// Serialize items of `iter` into `writer` as a list, with options set by
+/// `config`. Each item is written as soon as the iterator yields it, so a
+/// huge iterator does not need to be collected into a `Vec` first. The
+/// output is the same as serializing the collected `Vec`.
+///
+/// ```
+/// let mut out = Vec::new();
+/// let config = serde_pyliteral::ser::Config::new();
+/// serde_pyliteral::to_writer_seq(&mut out, (1..4).map(|i| i * i), config).unwrap();
+/// assert_eq!(out, b"[1,4,9]");
+/// ```
+///
+/// Like `to_writer`, `writer` is not flushed.
+pub fn to_writer_seq<W, I>(writer: W, iter: I, config: Config) -> Result<()>
+where
+    W: io::Write,
+    I: IntoIterator,
+    I::Item: Serialize,
+{
+    let mut ser = Serializer::from_writer(writer).with_config(config);
+    let mut seq = serde::Serializer::serialize_seq(&mut ser, None)?;
+    for item in iter {
+        SerializeSeq::serialize_element(&mut seq, &item)?;
+    }
+    SerializeSeq::end(seq)
+}
+
 pub fn to_vec<T: ?Sized + Serialize>(value: &T) -> Result<Vec<u8>> {
     let mut writer = Vec::with_capacity(128);
     to_writer(&mut writer, value)?;
@@ -54,41 +112,422 @@ pub fn to_string_pretty<T: ?Sized + Serialize>(value: &T) -> Result<String> {
     Ok(string)
 }
 
+pub fn to_vec_with_config<T: ?Sized + Serialize>(value: &T, config: Config) -> Result<Vec<u8>> {
+    let mut writer = Vec::with_capacity(128);
+    to_writer_with_config(&mut writer, value, config)?;
+    Ok(writer)
+}
+
+pub fn to_string_with_config<T: ?Sized + Serialize>(value: &T, config: Config) -> Result<String> {
+    let vec = to_vec_with_config(value, config)?;
+    let string = unsafe {
+        // We do not emit invalid UTF-8.
+        String::from_utf8_unchecked(vec)
+    };
+    Ok(string)
+}
+
 pub struct Serializer<W> {
     writer: W,
-    written_bytes: usize,
     writing_key: usize,
     stack: Vec<Frame>,
     config: Config,
+    // Write the next seq as a set. Set by `Set`.
+    next_seq_is_set: bool,
+    // Parts of the complex number being written. Set by `Value::Complex`.
+    complex_parts: Option<Vec<f64>>,
+    // Write the next str as-is. Set by `datetime` helpers.
+    #[cfg(feature = "datetime")]
+    next_str_is_expr: bool,
+    // Buffers that receive writes instead of `writer`. Used by `sort_keys`.
+    captures: Vec<Vec<u8>>,
+    // Buffered entries of maps being sorted, innermost last.
+    sorted_maps: Vec<SortedMap>,
 }
 
-#[derive(Debug, Default)]
+#[derive(Default)]
+struct SortedMap {
+    // (key, value) as serialized bytes.
+    entries: Vec<(Vec<u8>, Vec<u8>)>,
+    // Key waiting for its value.
+    key: Vec<u8>,
+}
+
+/// Options of `Serializer`.
+///
+/// Start with `Config::new()`, then chain setters:
+///
+/// ```
+/// use serde_pyliteral::ser::Config;
+/// use std::collections::HashMap;
+///
+/// let map: HashMap<_, _> = vec![(2, "b"), (1, "a")].into_iter().collect();
+/// let config = Config::new().pretty(true).sort_keys(true);
+/// let text = serde_pyliteral::to_string_with_config(&map, config).unwrap();
+/// assert_eq!(text, "{1: \"a\", 2: \"b\"}");
+/// ```
+#[derive(Debug, Clone)]
+#[non_exhaustive]
 pub struct Config {
     pretty: bool,
+    multiline_strings: bool,
+    #[cfg(feature = "base64")]
+    bytes_as_base64: bool,
+    bytes_passthrough_utf8: bool,
+    int_radix: Radix,
+    group_digits: bool,
+    indent: Option<usize>,
+    max_serialize_depth: usize,
+    skip_none_fields: bool,
+    sort_keys: bool,
+    quote_style: QuoteStyle,
+    ensure_ascii: bool,
+    lossy_control_replacement: Option<char>,
+    trailing_newline: bool,
+    field_filter: Option<FieldFilter>,
+    enum_member_access: bool,
+    result_as_tuple: bool,
+    pretty_width: Option<usize>,
+    tight_colon: bool,
+    tight_commas: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            pretty: false,
+            multiline_strings: false,
+            #[cfg(feature = "base64")]
+            bytes_as_base64: false,
+            bytes_passthrough_utf8: false,
+            int_radix: Radix::Dec,
+            group_digits: false,
+            indent: None,
+            max_serialize_depth: DEFAULT_MAX_DEPTH,
+            skip_none_fields: false,
+            sort_keys: false,
+            quote_style: QuoteStyle::Auto,
+            ensure_ascii: false,
+            lossy_control_replacement: None,
+            trailing_newline: false,
+            field_filter: None,
+            enum_member_access: false,
+            result_as_tuple: false,
+            pretty_width: Some(79),
+            tight_colon: false,
+            tight_commas: false,
+        }
+    }
+}
+
+/// Radix used to write integers.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Radix {
+    /// `255`
+    #[default]
+    Dec,
+    /// `0xff`
+    Hex,
+    /// `0o377`
+    Oct,
+    /// `0b11111111`
+    Bin,
+}
+
+/// Quote used to write str and bytes.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum QuoteStyle {
+    /// `"`, or `'` if the str contains `"` but not `'`, like `repr`
+    /// preferring the quote that needs no escaping. Bytes always use `"`,
+    /// as they did before this option existed.
+    #[default]
+    Auto,
+    /// Always `"`. `"` in the value is escaped.
+    Double,
+    /// Always `'`. `'` in the value is escaped.
+    Single,
+    /// `'`, or `"` if the value contains `'` but not `"`, exactly like
+    /// `repr` in CPython. If both are present, `'` is used and escaped.
+    CPythonRepr,
+}
+
+/// What to do with a struct field. Returned by the `Config::field_filter`
+/// callback.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldAction {
+    /// Write the field as usual.
+    Keep,
+    /// Omit the field.
+    Skip,
+    /// Write the given str instead of the field value.
+    Replace(String),
+}
+
+#[derive(Clone)]
+struct FieldFilter(Arc<dyn Fn(&str) -> FieldAction + Send + Sync>);
+
+impl fmt::Debug for FieldFilter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("FieldFilter")
+    }
+}
+
+impl QuoteStyle {
+    fn pick(self, value: &[u8]) -> u8 {
+        match self {
+            QuoteStyle::Auto if value.contains(&b'"') && !value.contains(&b'\'') => b'\'',
+            QuoteStyle::Auto | QuoteStyle::Double => b'"',
+            QuoteStyle::CPythonRepr if value.contains(&b'\'') && !value.contains(&b'"') => b'"',
+            QuoteStyle::Single | QuoteStyle::CPythonRepr => b'\'',
+        }
+    }
+
+    fn pick_bytes(self, value: &[u8]) -> u8 {
+        match self {
+            QuoteStyle::Auto => b'"',
+            _ => self.pick(value),
+        }
+    }
 }
 
 impl Config {
+    /// Options matching `to_string`: compact output with no extra options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Put items of a container on separate lines, aligned like `pprint`.
+    /// Empty containers, like `()`, `[]` and `{}`, never span lines.
     pub fn pretty(mut self, value: bool) -> Self {
         self.pretty = value;
         self
     }
+
+    /// Indent nested items by `n` spaces per level, with closing brackets
+    /// on their own lines, like `json.dumps(indent=n)`. Implies `pretty`.
+    /// `None` aligns items to the opening bracket instead, like `pprint`.
+    pub fn indent(mut self, value: Option<usize>) -> Self {
+        self.indent = value;
+        self
+    }
+
+    /// Write a container on one line if it fits in `value` columns,
+    /// including the comma or bracket after it, like `pprint` with
+    /// `width=value`. Only containers that do not fit put their items on
+    /// separate lines. Has no effect unless `pretty` or `indent` is set.
+    /// Defaults to 79.
+    /// `None` puts items of every non-empty container on separate lines.
+    ///
+    /// A container is buffered in memory until it is known not to fit.
+    pub fn pretty_width(mut self, value: Option<usize>) -> Self {
+        self.pretty_width = value;
+        self
+    }
+
+    /// Write `key:value` instead of `key: value` in pretty output. The
+    /// compact output never has the space.
+    pub fn tight_colon(mut self, value: bool) -> Self {
+        self.tight_colon = value;
+        self
+    }
+
+    /// Write `[1,2]` instead of `[1, 2]` for containers that `pretty_width`
+    /// puts on one line. Items on separate lines are not affected.
+    pub fn tight_commas(mut self, value: bool) -> Self {
+        self.tight_commas = value;
+        self
+    }
+
+    /// Use triple quotes for strings containing "\n", and write "\n"
+    /// as-is instead of escaping it.
+    pub fn multiline_strings(mut self, value: bool) -> Self {
+        self.multiline_strings = value;
+        self
+    }
+
+    /// Write integers using the given radix. Negative integers are written
+    /// like `-0xff`.
+    pub fn int_radix(mut self, value: Radix) -> Self {
+        self.int_radix = value;
+        self
+    }
+
+    /// Choose the quote of str and bytes. Forcing a quote can make the
+    /// output more stable, at the cost of escaping that quote.
+    pub fn quote_style(mut self, value: QuoteStyle) -> Self {
+        self.quote_style = value;
+        self
+    }
+
+    /// Escape all non-ASCII characters in str using `\\u` or `\\U`, like
+    /// `json.dumps(ensure_ascii=True)`, so the output is pure ASCII. By
+    /// default, only non-printable characters are escaped.
+    pub fn ensure_ascii(mut self, value: bool) -> Self {
+        self.ensure_ascii = value;
+        self
+    }
+
+    /// Write printable non-ASCII UTF-8 in bytes as-is, like `b"文\xff"`,
+    /// instead of `b"\xe6\x96\x87\xff"`. This is easier to read, but
+    /// Python only allows ASCII in bytes literals, so `ast.literal_eval`
+    /// rejects it. This crate reads it back.
+    pub fn bytes_passthrough_utf8(mut self, value: bool) -> Self {
+        self.bytes_passthrough_utf8 = value;
+        self
+    }
+
+    /// Replace control characters in str, except `\n`, `\r` and `\t`, with
+    /// `value`, instead of escaping them like `\u0001`. This loses
+    /// information and is meant for display. The replacement is escaped if
+    /// needed, for example, `'\u{fffd}'` with `ensure_ascii`. Defaults to
+    /// `None`, which escapes them.
+    pub fn lossy_control_replacement(mut self, value: Option<char>) -> Self {
+        self.lossy_control_replacement = value;
+        self
+    }
+
+    /// Write `\n` after the root value, as files usually end with a newline.
+    pub fn trailing_newline(mut self, value: bool) -> Self {
+        self.trailing_newline = value;
+        self
+    }
+
+    /// Decide what to do with struct fields by their names, for example, to
+    /// redact secrets in logs. Applies to fields of nested structs and
+    /// struct variants too. Map entries are not affected.
+    pub fn field_filter(
+        mut self,
+        filter: impl Fn(&str) -> FieldAction + Send + Sync + 'static,
+    ) -> Self {
+        self.field_filter = Some(FieldFilter(Arc::new(filter)));
+        self
+    }
+
+    /// Write unit variants as `Name.Variant`, like members of a Python
+    /// `enum.Enum` class named after the Rust enum, instead of
+    /// `{"Variant": ()}`. Other variants are not affected. The output
+    /// needs that class to be defined to evaluate.
+    pub fn enum_member_access(mut self, value: bool) -> Self {
+        self.enum_member_access = value;
+        self
+    }
+
+    /// Write `Result` as `("ok", value)` or `("err", value)`, instead of
+    /// `{"Ok": value}` or `{"Err": value}`. Python code often returns such
+    /// tuples. Both forms are read back as `Result`. Other variants of an
+    /// enum named `Result` use the default form.
+    pub fn result_as_tuple(mut self, value: bool) -> Self {
+        self.result_as_tuple = value;
+        self
+    }
+
+    /// Separate every three decimal digits of integers with `_`, like
+    /// `-1_000_000`. Has no effect on other radixes.
+    pub fn group_digits(mut self, value: bool) -> Self {
+        self.group_digits = value;
+        self
+    }
+
+    /// Error out with `DepthLimitExceeded` if containers are nested deeper
+    /// than `value`, instead of risking a stack overflow. Defaults to 128,
+    /// like `de::Config::max_depth`.
+    pub fn max_serialize_depth(mut self, value: usize) -> Self {
+        self.max_serialize_depth = value;
+        self
+    }
+
+    /// Omit struct fields that are `None`, like `skip_serializing_if =
+    /// "Option::is_none"` on every `Option` field. Map entries are kept.
+    pub fn skip_none_fields(mut self, value: bool) -> Self {
+        self.skip_none_fields = value;
+        self
+    }
+
+    /// Sort map entries by their serialized keys, for output that does not
+    /// depend on the iteration order of, for example, `HashMap`. Struct
+    /// fields keep their declared order.
+    ///
+    /// Entries of each map are buffered in memory until the map ends, which
+    /// costs an extra allocation per key and value.
+    pub fn sort_keys(mut self, value: bool) -> Self {
+        self.sort_keys = value;
+        self
+    }
+
+    /// Write bytes as `base64.b64decode("...")`. Note this is a Python
+    /// expression that `ast.literal_eval` cannot evaluate.
+    #[cfg(feature = "base64")]
+    pub fn bytes_as_base64(mut self, value: bool) -> Self {
+        self.bytes_as_base64 = value;
+        self
+    }
 }
 
 struct Frame {
     count: usize,
     indent: usize,
     right_bracket: &'static [u8],
-    key_len: usize,
+    /// Column where the value of the current map entry starts.
+    value_indent: usize,
+    /// A comment was just written. The comma after the last item, and the
+    /// line break, are already written.
+    after_comment: bool,
+    /// A map key was written, and its value is not yet.
+    value_pending: bool,
+    /// Set if the container is being captured for `pretty_width`.
+    compact: Option<Compact>,
+}
+
+/// A container written to its own capture, so it can be rewritten on one
+/// line if it fits `pretty_width`.
+struct Compact {
+    /// `captures.len()` when the capture is the innermost one.
+    depth: usize,
+    /// Max chars of the one-line form.
+    budget: usize,
+    /// Line breaks in the capture, as (start, end, replacement).
+    breaks: Vec<(usize, usize, &'static [u8])>,
+    /// A nested container or a comment spans lines, so this one does too.
+    broken: bool,
+}
+
+impl Compact {
+    /// The one-line form of `capture`, if it fits the budget.
+    fn one_line(&self, capture: &[u8]) -> Option<Vec<u8>> {
+        if self.broken {
+            return None;
+        }
+        let mut out = Vec::with_capacity(capture.len());
+        let mut pos = 0;
+        for &(start, end, replacement) in &self.breaks {
+            out.extend_from_slice(&capture[pos..start]);
+            out.extend_from_slice(replacement);
+            pos = end;
+        }
+        out.extend_from_slice(&capture[pos..]);
+        let multiline = out.iter().any(|&b| b == b'\n' || b == b'\r');
+        let width = out.iter().filter(|&&b| b & 0xc0 != 0x80).count();
+        if multiline || width > self.budget {
+            None
+        } else {
+            Some(out)
+        }
+    }
 }
 
 impl<W: Write> Serializer<W> {
     pub fn from_writer(w: W) -> Self {
         Serializer {
             writer: w,
-            written_bytes: 0,
             writing_key: 0,
             stack: Vec::new(),
             config: Config::default(),
+            next_seq_is_set: false,
+            complex_parts: None,
+            #[cfg(feature = "datetime")]
+            next_str_is_expr: false,
+            captures: Vec::new(),
+            sorted_maps: Vec::new(),
         }
     }
 
@@ -102,16 +541,164 @@ impl<W: Write> Serializer<W> {
         self
     }
 
+    /// Flush the underlying writer. Call this after serializing a value if
+    /// the writer is buffered.
+    pub fn finish(&mut self) -> Result<()> {
+        self.flush().map_err(From::from)
+    }
+
+    /// Consume the serializer and return the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    /// Write `comment` as `# comment` lines before the next item of the
+    /// current container, or before the root value. Call it between items,
+    /// like between `serialize_entry` calls of a `SerializeMap`:
+    ///
+    /// ```
+    /// use serde::ser::SerializeMap;
+    /// use serde::Serializer as _;
+    /// use serde_pyliteral::ser::Serializer;
+    ///
+    /// let mut out = Vec::new();
+    /// let mut ser = Serializer::from_writer(&mut out).pretty();
+    /// let mut map = ser.serialize_map(None).unwrap();
+    /// map.serialize_entry("a", &1).unwrap();
+    /// map.write_comment("b is 2").unwrap();
+    /// map.serialize_entry("b", &2).unwrap();
+    /// SerializeMap::end(map).unwrap();
+    /// assert_eq!(out, b"{\"a\": 1,\n # b is 2\n \"b\": 2}");
+    /// ```
+    ///
+    /// The comma after the previous item goes before the comment, and the
+    /// next item starts on a new line, aligned like other items. Each line
+    /// of `comment` gets its own `#`. Comments are not supported with
+    /// `sort_keys`, since entries are reordered after being written, or
+    /// between a map key and its value.
+    pub fn write_comment(&mut self, comment: &str) -> Result<()> {
+        if !self.sorted_maps.is_empty() {
+            return Err(Error::Generic(
+                "comments are not supported with sort_keys".to_string(),
+            ));
+        }
+        if self.stack.last().is_some_and(|f| f.value_pending) {
+            return Err(Error::Generic(
+                "comments are not supported between a key and its value".to_string(),
+            ));
+        }
+        // Containers with comments span lines.
+        self.break_compact_frames()?;
+        let pretty = self.is_pretty();
+        // With a fixed indent, items are preceded by line breaks instead of
+        // followed by them.
+        let break_before = pretty && self.config.indent.is_some() && !self.stack.is_empty();
+        let (indent, need_comma) = match self.stack.last_mut() {
+            Some(frame) => {
+                let need_comma = frame.count > 0 && !frame.after_comment;
+                frame.after_comment = true;
+                (frame.indent, need_comma)
+            }
+            None => (0, false),
+        };
+        if need_comma {
+            self.write_raw_bytes(b",")?;
+            if pretty && !break_before {
+                self.write_raw_bytes(b"\n")?;
+                self.write_raw_bytes(&spaces(indent))?;
+            }
+        }
+        // A lone `\r` also ends a comment in Python.
+        let lines: Vec<&str> = if comment.is_empty() {
+            vec![""]
+        } else {
+            comment.lines().flat_map(|line| line.split('\r')).collect()
+        };
+        for line in lines {
+            if break_before {
+                self.write_raw_bytes(b"\n")?;
+                self.write_raw_bytes(&spaces(indent))?;
+            }
+            self.write_raw_bytes(b"#")?;
+            if !line.is_empty() {
+                self.write_raw_bytes(b" ")?;
+                self.write_raw_bytes(line.as_bytes())?;
+            }
+            if !break_before {
+                self.write_raw_bytes(b"\n")?;
+                if pretty {
+                    self.write_raw_bytes(&spaces(indent))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
     fn is_pretty(&self) -> bool {
-        self.config.pretty && self.writing_key == 0
+        (self.config.pretty || self.config.indent.is_some()) && self.writing_key == 0
+    }
+
+    /// What follows a dict key.
+    fn colon(&self) -> &'static [u8] {
+        if self.is_pretty() && !self.config.tight_colon {
+            b": "
+        } else {
+            b":"
+        }
     }
 }
 
-impl<'a, W: Write> Serializer<W> {
+impl<W: Write> Serializer<W> {
     fn write_str<V: ToString>(&mut self, v: V) -> Result<()> {
         self.write_raw_bytes(v.to_string().as_bytes())
     }
 
+    fn write_int<T>(&mut self, negative: bool, abs: T) -> Result<()>
+    where
+        T: fmt::Display + fmt::LowerHex + fmt::Octal + fmt::Binary,
+    {
+        if negative {
+            self.write_raw_bytes(b"-")?;
+        }
+        match self.config.int_radix {
+            Radix::Dec if self.config.group_digits => {
+                let grouped = group_digits(&abs.to_string());
+                self.write_raw_bytes(&grouped)?;
+            }
+            Radix::Dec => self.write_str(abs)?,
+            Radix::Hex => self.write_str(format_args!("{:#x}", abs))?,
+            Radix::Oct => self.write_str(format_args!("{:#o}", abs))?,
+            Radix::Bin => self.write_str(format_args!("{:#b}", abs))?,
+        }
+        self.end_value()
+    }
+
+    /// Write a complex number like `(1.0-2.0j)`, which `ast.literal_eval`
+    /// accepts. Infinities are written as `1e999`, like floats.
+    fn write_complex(&mut self, real: f64, imag: f64) -> Result<()> {
+        if real.is_nan() || imag.is_nan() {
+            return Err(Error::NaN);
+        }
+        let abs = |v: f64| match v.abs() {
+            v if v.is_infinite() => "1e999".to_string(),
+            v => v.to_human_string(),
+        };
+        let sign = |v: f64| if v.is_sign_negative() { "-" } else { "+" };
+        let real_sign = sign(real).trim_start_matches('+');
+        let text = format!("({}{}{}{}j)", real_sign, abs(real), sign(imag), abs(imag));
+        self.write_raw_bytes(text.as_bytes())?;
+        self.end_value()
+    }
+
+    /// Called after writing a value. Write the trailing newline if it is
+    /// the root value.
+    fn end_value(&mut self) -> Result<()> {
+        if self.config.trailing_newline && self.stack.is_empty() {
+            self.write_raw_bytes(b"\n")?;
+        }
+        Ok(())
+    }
+
     fn write_raw_bytes(&mut self, v: &[u8]) -> Result<()> {
         self.write_all(v).map_err(From::from)
     }
@@ -121,45 +708,167 @@ impl<'a, W: Write> Serializer<W> {
         left_bracket: &'static [u8],
         right_bracket: &'static [u8],
     ) -> Result<()> {
-        let indent = if self.is_pretty() {
+        let max = self.config.max_serialize_depth;
+        if self.stack.len() >= max {
+            return Err(Error::DepthLimitExceeded(max));
+        }
+        let indent = if !self.is_pretty() {
+            0
+        } else if let Some(n) = self.config.indent {
+            self.stack.last().map(|f| f.indent).unwrap_or_default() + n
+        } else {
             self.stack
                 .last()
-                .map(|f| f.indent + f.key_len)
+                .map(|f| f.value_indent)
                 .unwrap_or_default()
                 + left_bracket.len()
-        } else {
-            0
         };
+        let compact = match self.config.pretty_width {
+            Some(width) if self.is_pretty() => {
+                let column = self.stack.last().map_or(0, |f| f.value_indent);
+                // Room for the comma or bracket that follows.
+                let allowance = if self.stack.is_empty() { 0 } else { 1 };
+                self.captures.push(Vec::new());
+                Some(Compact {
+                    depth: self.captures.len(),
+                    budget: width.saturating_sub(column + allowance),
+                    breaks: Vec::new(),
+                    broken: false,
+                })
+            }
+            _ => None,
+        };
+        // Only a seq can be a set.
+        self.next_seq_is_set = false;
         let frame = Frame {
             count: 0,
             indent,
             right_bracket,
-            key_len: 0,
+            value_indent: indent,
+            after_comment: false,
+            value_pending: false,
+            compact,
         };
         self.stack.push(frame);
-        self.write_raw_bytes(left_bracket).map_err(From::from)
+        self.write_raw_bytes(left_bracket)
     }
 
     fn pop_bracket(&mut self) -> Result<()> {
-        if let Some(frame) = self.stack.pop() {
-            if frame.right_bracket == b")" && frame.count == 1 {
+        if let Some(frame) = self.stack.last() {
+            let (right_bracket, count, after_comment) =
+                (frame.right_bracket, frame.count, frame.after_comment);
+            let indent = frame.indent;
+            if right_bracket == b")" && count == 1 && !after_comment {
                 // Tailing comma needed for tuple of a single item.
                 self.write_raw_bytes(b",")?;
             }
-            self.write_raw_bytes(frame.right_bracket)?;
+            if let (Some(n), true) = (self.config.indent, self.is_pretty()) {
+                if count > 0 || after_comment {
+                    self.write_line_break(indent - n, b"")?;
+                }
+            }
+            self.write_raw_bytes(right_bracket)?;
+            if let Some(compact) = self.stack.pop().and_then(|f| f.compact) {
+                let capture = self.end_capture();
+                match compact.one_line(&capture) {
+                    Some(line) => self.write_raw_bytes(&line)?,
+                    None => {
+                        self.write_raw_bytes(&capture)?;
+                        self.break_compact_frames()?;
+                    }
+                }
+            }
+        }
+        self.end_value()
+    }
+
+    /// Write a line break, then `indent` spaces. `replacement` is written
+    /// instead if the container ends up on one line.
+    fn write_line_break(&mut self, indent: usize, replacement: &'static [u8]) -> Result<()> {
+        let depth = self.captures.len();
+        let start = self.captures.last().map_or(0, |c| c.len());
+        if let Some(compact) = self.stack.last_mut().and_then(|f| f.compact.as_mut()) {
+            if compact.depth == depth {
+                compact
+                    .breaks
+                    .push((start, start + 1 + indent, replacement));
+            }
+        }
+        self.write_raw_bytes(b"\n")?;
+        self.write_raw_bytes(&spaces(indent))
+    }
+
+    /// Give up writing the innermost container on one line if it no longer
+    /// fits.
+    fn check_compact_frame(&mut self) -> Result<()> {
+        let depth = self.captures.len();
+        let fits = match (self.stack.last(), self.captures.last()) {
+            (Some(frame), Some(capture)) => match &frame.compact {
+                Some(compact) if compact.depth == depth => compact.one_line(capture).is_some(),
+                _ => true,
+            },
+            _ => true,
+        };
+        if !fits {
+            self.break_compact_frames()?;
+        }
+        Ok(())
+    }
+
+    /// Give up writing the innermost container, and the ones containing it,
+    /// on one line. Captures that are innermost are written out now. Others
+    /// are written when they become innermost.
+    fn break_compact_frames(&mut self) -> Result<()> {
+        let mut flush = true;
+        for i in (0..self.stack.len()).rev() {
+            let depth = match &mut self.stack[i].compact {
+                Some(compact) => {
+                    compact.broken = true;
+                    compact.depth
+                }
+                None => continue,
+            };
+            flush = flush && depth == self.captures.len();
+            if flush {
+                self.stack[i].compact = None;
+                let capture = self.end_capture();
+                self.write_raw_bytes(&capture)?;
+            }
         }
         Ok(())
     }
 
     fn write_comma(&mut self) -> Result<()> {
         let pretty = self.is_pretty();
+        let fixed_indent = self.config.indent.is_some();
+        // Replaces the line break after a comma on one line.
+        let inline_space: &[u8] = if self.config.tight_commas { b"" } else { b" " };
+        if pretty {
+            self.check_compact_frame()?;
+        }
         if let Some(frame) = self.stack.last_mut() {
             frame.count += 1;
-            if frame.count > 1 {
+            if std::mem::take(&mut frame.after_comment) {
+                // The comma is written before the comment.
+                if pretty && fixed_indent {
+                    let indent = frame.indent;
+                    self.write_line_break(indent, b"")?;
+                }
+            } else if pretty && fixed_indent {
+                // Every item starts on a new line.
+                let indent = frame.indent;
+                let replacement: &[u8] = if frame.count > 1 {
+                    self.write_raw_bytes(b",")?;
+                    inline_space
+                } else {
+                    b""
+                };
+                self.write_line_break(indent, replacement)?;
+            } else if frame.count > 1 {
                 if pretty {
                     let indent = frame.indent;
-                    self.write_raw_bytes(b",\n")?;
-                    self.write_raw_bytes(&spaces(indent))?;
+                    self.write_raw_bytes(b",")?;
+                    self.write_line_break(indent, inline_space)?;
                 } else {
                     self.write_raw_bytes(b",")?;
                 }
@@ -170,28 +879,74 @@ impl<'a, W: Write> Serializer<W> {
 
     fn write_key_colon(&mut self, key: impl Serialize) -> Result<()> {
         let pretty = self.is_pretty();
-        let orig_written_bytes = self.written_bytes;
+        let colon = self.colon();
         // Disable pretty when writing keys.
         self.writing_key += 1;
-        key.serialize(&mut *self)?;
-        self.write_raw_bytes(if pretty { b": " } else { b":" })?;
-        if let Some(frame) = self.stack.last_mut() {
-            frame.key_len = self.written_bytes - orig_written_bytes;
+        if pretty {
+            // Capture the key to find the column where the value starts.
+            // The key can span lines, like a multi-line str.
+            self.captures.push(Vec::new());
+            key.serialize(&mut *self)?;
+            let key = self.end_capture();
+            self.write_raw_bytes(&key)?;
+            self.write_raw_bytes(colon)?;
+            if let Some(frame) = self.stack.last_mut() {
+                frame.value_indent = column_after(frame.indent, &key) + colon.len();
+            }
+        } else {
+            key.serialize(&mut *self)?;
+            self.write_raw_bytes(colon)?;
         }
         self.writing_key -= 1;
         Ok(())
     }
 
+    /// Write a field of a struct or struct variant, respecting
+    /// `field_filter` and `skip_none_fields`.
+    fn serialize_field_with_filter<V: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &V,
+    ) -> Result<()> {
+        let action = match &self.config.field_filter {
+            Some(filter) => (filter.0)(key),
+            None => FieldAction::Keep,
+        };
+        match action {
+            FieldAction::Keep => {
+                if self.config.skip_none_fields && probe::is_none(value) {
+                    return Ok(());
+                }
+                self.write_comma()?;
+                self.write_key_colon(key)?;
+                value.serialize(&mut *self)
+            }
+            FieldAction::Skip => Ok(()),
+            FieldAction::Replace(placeholder) => {
+                self.write_comma()?;
+                self.write_key_colon(key)?;
+                placeholder.serialize(&mut *self)
+            }
+        }
+    }
+
+    fn end_capture(&mut self) -> Vec<u8> {
+        self.captures.pop().unwrap_or_default()
+    }
+
     fn push_enum_variant(&mut self, name: &str) -> Result<()> {
         self.push_bracket(b"{", b"}")?;
+        self.write_comma()?;
         self.write_key_colon(name)
     }
 }
 
-impl<'a, W: Write> Write for Serializer<W> {
+impl<W: Write> Write for Serializer<W> {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
-        let n = self.writer.write(buf)?;
-        self.written_bytes += n;
+        let n = match self.captures.last_mut() {
+            Some(capture) => capture.write(buf)?,
+            None => self.writer.write(buf)?,
+        };
         Ok(n)
     }
 
@@ -200,7 +955,7 @@ impl<'a, W: Write> Write for Serializer<W> {
     }
 }
 
-impl<'a, W: Write> serde::Serializer for &'a mut Serializer<W> {
+impl<W: Write> serde::Serializer for &mut Serializer<W> {
     type Ok = ();
     type Error = Error;
 
@@ -213,97 +968,143 @@ impl<'a, W: Write> serde::Serializer for &'a mut Serializer<W> {
     type SerializeStructVariant = Self;
 
     fn serialize_unit(self) -> Result<()> {
-        self.write_raw_bytes(b"()")
+        self.write_raw_bytes(b"()")?;
+        self.end_value()
     }
 
-    fn serialize_unit_struct(self, _: &'static str) -> Result<()> {
+    fn serialize_unit_struct(self, name: &'static str) -> Result<()> {
+        if name == ellipsis::ELLIPSIS_TOKEN {
+            self.write_raw_bytes(b"...")?;
+            return self.end_value();
+        }
         self.serialize_unit()
     }
 
     fn serialize_bool(self, v: bool) -> Result<()> {
-        self.write_raw_bytes(if v { b"True" } else { b"False" })
+        self.write_raw_bytes(if v { b"True" } else { b"False" })?;
+        self.end_value()
     }
 
     fn serialize_u8(self, v: u8) -> Result<()> {
-        self.write_str(v)
+        self.write_int(false, v)
     }
 
     fn serialize_u16(self, v: u16) -> Result<()> {
-        self.write_str(v)
+        self.write_int(false, v)
     }
 
     fn serialize_u32(self, v: u32) -> Result<()> {
-        self.write_str(v)
+        self.write_int(false, v)
     }
 
     fn serialize_u64(self, v: u64) -> Result<()> {
-        self.write_str(v)
+        self.write_int(false, v)
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<()> {
+        self.write_int(false, v)
     }
 
     #[inline]
     fn serialize_i8(self, v: i8) -> Result<()> {
-        self.write_str(v)
+        self.write_int(v < 0, v.unsigned_abs())
     }
 
     #[inline]
     fn serialize_i16(self, v: i16) -> Result<()> {
-        self.write_str(v)
+        self.write_int(v < 0, v.unsigned_abs())
     }
 
     #[inline]
     fn serialize_i32(self, v: i32) -> Result<()> {
-        self.write_str(v)
+        self.write_int(v < 0, v.unsigned_abs())
     }
 
     #[inline]
     fn serialize_i64(self, v: i64) -> Result<()> {
-        self.write_str(v)
+        self.write_int(v < 0, v.unsigned_abs())
+    }
+
+    #[inline]
+    fn serialize_i128(self, v: i128) -> Result<()> {
+        self.write_int(v < 0, v.unsigned_abs())
     }
 
     fn serialize_f32(self, v: f32) -> Result<()> {
         if v.is_nan() {
-            Err(Error::NaN)
+            return Err(Error::NaN);
         } else if v.is_infinite() {
             if v.is_sign_negative() {
                 self.write_raw_bytes(b"-")?;
             }
-            self.write_raw_bytes(b"1e999")
+            self.write_raw_bytes(b"1e999")?;
         } else {
-            self.write_raw_bytes(v.to_human_string().as_bytes())
+            self.write_raw_bytes(v.to_human_string().as_bytes())?;
         }
+        self.end_value()
     }
 
     fn serialize_f64(self, v: f64) -> Result<()> {
         if v.is_nan() {
-            Err(Error::NaN)
+            return Err(Error::NaN);
         } else if v.is_infinite() {
             if v.is_sign_negative() {
                 self.write_raw_bytes(b"-")?;
             }
-            self.write_raw_bytes(b"1e999")
+            self.write_raw_bytes(b"1e999")?;
         } else {
-            self.write_raw_bytes(v.to_human_string().as_bytes())
+            self.write_raw_bytes(v.to_human_string().as_bytes())?;
         }
+        self.end_value()
     }
 
     #[inline]
     fn serialize_str(self, v: &str) -> Result<()> {
-        write_escaped_string(v, self).map_err(From::from)
+        #[cfg(feature = "datetime")]
+        if std::mem::take(&mut self.next_str_is_expr) {
+            self.write_raw_bytes(v.as_bytes())?;
+            return self.end_value();
+        }
+        let multiline = self.config.multiline_strings;
+        let ensure_ascii = self.config.ensure_ascii;
+        let v = replace_control(v, self.config.lossy_control_replacement);
+        let quote = self.config.quote_style.pick(v.as_bytes());
+        write_escaped_string(&v, quote, multiline, ensure_ascii, self)?;
+        self.end_value()
     }
 
     #[inline]
     fn serialize_char(self, c: char) -> Result<()> {
-        write_escaped_string(&c.to_string(), self).map_err(From::from)
+        let multiline = self.config.multiline_strings;
+        let ensure_ascii = self.config.ensure_ascii;
+        let s = c.to_string();
+        let s = replace_control(&s, self.config.lossy_control_replacement);
+        let quote = self.config.quote_style.pick(s.as_bytes());
+        write_escaped_string(&s, quote, multiline, ensure_ascii, self)?;
+        self.end_value()
     }
 
     #[inline]
     fn serialize_bytes(self, v: &[u8]) -> Result<()> {
-        write_escaped_bytes(v, self).map_err(From::from)
+        #[cfg(feature = "base64")]
+        if self.config.bytes_as_base64 {
+            use base64::Engine;
+            let encoded = base64::engine::general_purpose::STANDARD.encode(v);
+            self.write_raw_bytes(b"base64.b64decode(\"")?;
+            self.write_raw_bytes(encoded.as_bytes())?;
+            self.write_raw_bytes(b"\")")?;
+            return self.end_value();
+        }
+        let quote = self.config.quote_style.pick_bytes(v);
+        let passthrough_utf8 = self.config.bytes_passthrough_utf8;
+        write_escaped_bytes(v, quote, passthrough_utf8, self)?;
+        self.end_value()
     }
 
     #[inline]
     fn serialize_none(self) -> Result<()> {
-        self.write_raw_bytes(b"None")
+        self.write_raw_bytes(b"None")?;
+        self.end_value()
     }
 
     #[inline]
@@ -312,8 +1113,17 @@ impl<'a, W: Write> serde::Serializer for &'a mut Serializer<W> {
     }
 
     #[inline]
-    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
-        self.push_bracket(b"[", b"]")?;
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq> {
+        if std::mem::take(&mut self.next_seq_is_set) {
+            if len == Some(0) {
+                // `{}` is an empty dict.
+                self.push_bracket(b"set(", b")")?;
+            } else {
+                self.push_bracket(b"{", b"}")?;
+            }
+        } else {
+            self.push_bracket(b"[", b"]")?;
+        }
         Ok(self)
     }
 
@@ -326,9 +1136,13 @@ impl<'a, W: Write> serde::Serializer for &'a mut Serializer<W> {
     #[inline]
     fn serialize_tuple_struct(
         self,
-        _name: &'static str,
+        name: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeTupleStruct> {
+        if name == value::COMPLEX_TOKEN {
+            self.complex_parts = Some(Vec::with_capacity(2));
+            return Ok(self);
+        }
         self.push_bracket(b"(", b")")?;
         Ok(self)
     }
@@ -349,6 +1163,9 @@ impl<'a, W: Write> serde::Serializer for &'a mut Serializer<W> {
     #[inline]
     fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
         self.push_bracket(b"{", b"}")?;
+        if self.config.sort_keys {
+            self.sorted_maps.push(SortedMap::default());
+        }
         Ok(self)
     }
 
@@ -374,20 +1191,48 @@ impl<'a, W: Write> serde::Serializer for &'a mut Serializer<W> {
     #[inline]
     fn serialize_newtype_struct<T: ?Sized + Serialize>(
         self,
-        _name: &'static str,
+        name: &'static str,
         value: &T,
     ) -> Result<()> {
-        value.serialize(self)
+        #[cfg(feature = "datetime")]
+        if name == crate::datetime::EXPR_TOKEN {
+            self.next_str_is_expr = true;
+            value.serialize(&mut *self)?;
+            self.next_str_is_expr = false;
+            return Ok(());
+        }
+        if name == set::SET_TOKEN {
+            self.next_seq_is_set = true;
+            value.serialize(&mut *self)?;
+            // In case `value` is not a seq.
+            self.next_seq_is_set = false;
+            Ok(())
+        } else {
+            value.serialize(self)
+        }
     }
 
     #[inline]
     fn serialize_newtype_variant<T: ?Sized + Serialize>(
         self,
-        _name: &'static str,
+        name: &'static str,
         _variant_index: u32,
         variant: &'static str,
         value: &T,
     ) -> Result<()> {
+        let tag = match variant {
+            "Ok" => Some("ok"),
+            "Err" => Some("err"),
+            _ => None,
+        };
+        if let (true, "Result", Some(tag)) = (self.config.result_as_tuple, name, tag) {
+            self.push_bracket(b"(", b")")?;
+            self.write_comma()?;
+            self.serialize_str(tag)?;
+            self.write_comma()?;
+            value.serialize(&mut *self)?;
+            return self.pop_bracket();
+        }
         self.push_enum_variant(variant)?;
         value.serialize(&mut *self)?;
         self.pop_bracket()
@@ -396,17 +1241,21 @@ impl<'a, W: Write> serde::Serializer for &'a mut Serializer<W> {
     #[inline]
     fn serialize_unit_variant(
         self,
-        _name: &'static str,
+        name: &'static str,
         _variant_index: u32,
         variant: &'static str,
     ) -> Result<()> {
+        if self.config.enum_member_access {
+            self.write_raw_bytes(format!("{}.{}", name, variant).as_bytes())?;
+            return self.end_value();
+        }
         self.push_enum_variant(variant)?;
         self.serialize_unit()?;
         self.pop_bracket()
     }
 }
 
-impl<'a, W: Write> SerializeSeq for &'a mut Serializer<W> {
+impl<W: Write> SerializeSeq for &mut Serializer<W> {
     type Ok = ();
     type Error = Error;
 
@@ -420,7 +1269,7 @@ impl<'a, W: Write> SerializeSeq for &'a mut Serializer<W> {
     }
 }
 
-impl<'a, W: Write> SerializeTuple for &'a mut Serializer<W> {
+impl<W: Write> SerializeTuple for &mut Serializer<W> {
     type Ok = ();
     type Error = Error;
 
@@ -434,21 +1283,33 @@ impl<'a, W: Write> SerializeTuple for &'a mut Serializer<W> {
     }
 }
 
-impl<'a, W: Write> SerializeTupleStruct for &'a mut Serializer<W> {
+impl<W: Write> SerializeTupleStruct for &mut Serializer<W> {
     type Ok = ();
     type Error = Error;
 
     fn serialize_field<V: ?Sized + Serialize>(&mut self, value: &V) -> Result<()> {
+        if let Some(parts) = self.complex_parts.as_mut() {
+            let part = probe::as_f64(value);
+            let part = part.ok_or_else(|| Error::Generic("expect float in complex".into()))?;
+            parts.push(part);
+            return Ok(());
+        }
         self.write_comma()?;
         value.serialize(&mut **self)
     }
 
     fn end(self) -> Result<()> {
+        if let Some(parts) = self.complex_parts.take() {
+            return match parts[..] {
+                [real, imag] => self.write_complex(real, imag),
+                _ => Err(Error::Generic("expect 2 parts in complex".into())),
+            };
+        }
         self.pop_bracket()
     }
 }
 
-impl<'a, W: Write> SerializeTupleVariant for &'a mut Serializer<W> {
+impl<W: Write> SerializeTupleVariant for &mut Serializer<W> {
     type Ok = ();
     type Error = Error;
 
@@ -463,26 +1324,69 @@ impl<'a, W: Write> SerializeTupleVariant for &'a mut Serializer<W> {
     }
 }
 
-impl<'a, W: Write> SerializeMap for &'a mut Serializer<W> {
+impl<W: Write> SerializeMap for &mut Serializer<W> {
     type Ok = ();
     type Error = Error;
 
     fn serialize_key<K: ?Sized + Serialize>(&mut self, key: &K) -> Result<()> {
+        if self.config.sort_keys {
+            // Capture the key, then the value. `end` writes them sorted.
+            self.captures.push(Vec::new());
+            self.writing_key += 1;
+            key.serialize(&mut **self)?;
+            self.writing_key -= 1;
+            let key = self.end_capture();
+            let colon_len = self.colon().len();
+            if let Some(frame) = self.stack.last_mut() {
+                // Indent of the value does not depend on the entry position.
+                frame.value_indent = column_after(frame.indent, &key) + colon_len;
+            }
+            if let Some(map) = self.sorted_maps.last_mut() {
+                map.key = key;
+            }
+            self.captures.push(Vec::new());
+            return Ok(());
+        }
         self.write_comma()?;
         self.write_key_colon(key)?;
+        if let Some(frame) = self.stack.last_mut() {
+            frame.value_pending = true;
+        }
         Ok(())
     }
 
     fn serialize_value<V: ?Sized + Serialize>(&mut self, value: &V) -> Result<()> {
-        value.serialize(&mut **self)
+        if let Some(frame) = self.stack.last_mut() {
+            frame.value_pending = false;
+        }
+        value.serialize(&mut **self)?;
+        if self.config.sort_keys {
+            let value = self.end_capture();
+            if let Some(map) = self.sorted_maps.last_mut() {
+                let key = std::mem::take(&mut map.key);
+                map.entries.push((key, value));
+            }
+        }
+        Ok(())
     }
 
     fn end(self) -> Result<()> {
+        if self.config.sort_keys {
+            let mut entries = self.sorted_maps.pop().unwrap_or_default().entries;
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            let colon = self.colon();
+            for (key, value) in entries {
+                self.write_comma()?;
+                self.write_raw_bytes(&key)?;
+                self.write_raw_bytes(colon)?;
+                self.write_raw_bytes(&value)?;
+            }
+        }
         self.pop_bracket()
     }
 }
 
-impl<'a, W: Write> SerializeStruct for &'a mut Serializer<W> {
+impl<W: Write> SerializeStruct for &mut Serializer<W> {
     type Ok = ();
     type Error = Error;
 
@@ -491,9 +1395,7 @@ impl<'a, W: Write> SerializeStruct for &'a mut Serializer<W> {
         key: &'static str,
         value: &V,
     ) -> Result<()> {
-        self.write_comma()?;
-        self.write_key_colon(key)?;
-        value.serialize(&mut **self)
+        self.serialize_field_with_filter(key, value)
     }
 
     fn end(self) -> Result<()> {
@@ -501,7 +1403,7 @@ impl<'a, W: Write> SerializeStruct for &'a mut Serializer<W> {
     }
 }
 
-impl<'a, W: Write> SerializeStructVariant for &'a mut Serializer<W> {
+impl<W: Write> SerializeStructVariant for &mut Serializer<W> {
     type Ok = ();
     type Error = Error;
 
@@ -510,9 +1412,7 @@ impl<'a, W: Write> SerializeStructVariant for &'a mut Serializer<W> {
         key: &'static str,
         value: &V,
     ) -> Result<()> {
-        self.write_comma()?;
-        self.write_key_colon(key)?;
-        value.serialize(&mut **self)
+        self.serialize_field_with_filter(key, value)
     }
 
     fn end(self) -> Result<()> {
@@ -521,6 +1421,53 @@ impl<'a, W: Write> SerializeStructVariant for &'a mut Serializer<W> {
     }
 }
 
+/// Writer backed by a fixed-size buffer. Writing beyond its capacity is an
+/// error, instead of a panic or reallocation.
+pub struct FixedBuf<'a> {
+    buf: &'a mut [u8],
+    len: usize,
+}
+
+impl<'a> FixedBuf<'a> {
+    pub fn new(buf: &'a mut [u8]) -> Self {
+        Self { buf, len: 0 }
+    }
+
+    /// Bytes written so far.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buf[..self.len]
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl Write for FixedBuf<'_> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        let end = self.len + data.len();
+        match self.buf.get_mut(self.len..end) {
+            Some(dest) => {
+                dest.copy_from_slice(data);
+                self.len = end;
+                Ok(data.len())
+            }
+            None => Err(io::Error::new(
+                io::ErrorKind::WriteZero,
+                format!("buffer capacity ({}) exceeded", self.buf.len()),
+            )),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
 fn to_hex_char(b: u8) -> u8 {
     assert!(b < 16);
     b"0123456789abcdef"[b as usize]
@@ -536,15 +1483,33 @@ fn to_hex_string(bytes: &[u8]) -> Vec<u8> {
     v
 }
 
+/// Replace control characters other than `\n`, `\r` and `\t` with
+/// `replacement`.
+fn replace_control(value: &str, replacement: Option<char>) -> Cow<'_, str> {
+    let is_replaced = |c: char| c.is_control() && !matches!(c, '\n' | '\r' | '\t');
+    match replacement {
+        Some(r) if value.chars().any(is_replaced) => Cow::Owned(
+            value
+                .chars()
+                .map(|c| if is_replaced(c) { r } else { c })
+                .collect(),
+        ),
+        _ => Cow::Borrowed(value),
+    }
+}
+
 // See unicode_repr in cpython and
 // https://docs.python.org/3/reference/lexical_analysis.html#string-and-bytes-literals
-fn write_escaped_string(value: &str, out: &mut impl io::Write) -> io::Result<()> {
-    let quote = if value.contains('\"') && !value.contains('\'') {
-        b'\''
-    } else {
-        b'"'
-    };
-    out.write_all(&[quote])?;
+fn write_escaped_string(
+    value: &str,
+    quote: u8,
+    multiline: bool,
+    ensure_ascii: bool,
+    out: &mut impl io::Write,
+) -> io::Result<()> {
+    let triple = multiline && value.contains('\n');
+    let quotes = if triple { vec![quote; 3] } else { vec![quote] };
+    out.write_all(&quotes)?;
 
     let mut state = WriteBytesState::from_value(value.as_bytes());
     let mut skipping = false;
@@ -554,15 +1519,24 @@ fn write_escaped_string(value: &str, out: &mut impl io::Write) -> io::Result<()>
             skipping = false;
         }
         let escape: &[u8] = match ch {
-            '\0' => br"\0",
-            '"' if quote == b'"' => br#"\""#,
-            '\'' if quote == b'\'' => br"'",
+            '\0' => nul_escape(value.as_bytes().get(i + 1)),
+            '"' | '\'' if ch as u8 == quote => {
+                // Inside """...""" or '''...''', a quote only needs
+                // escaping if it is followed by another quote or the closing
+                // quotes.
+                let next = value.as_bytes().get(i + 1);
+                if triple && next.is_some_and(|&b| b != quote) {
+                    continue;
+                }
+                quote_escape(quote)
+            }
             '\\' => br"\\",
+            '\n' if triple => continue,
             '\n' => br"\n",
             '\r' => br"\r",
             '\t' => br"\t",
             _ => {
-                if unicode::need_escape(ch) {
+                if unicode::need_escape(ch) || (ensure_ascii && !ch.is_ascii()) {
                     // Use \uxxxx or \Uxxxxxxxx to escape.
                     out.write_all(state.pending(i))?;
                     let v = ch as u32;
@@ -584,31 +1558,46 @@ fn write_escaped_string(value: &str, out: &mut impl io::Write) -> io::Result<()>
         skipping = true;
     }
     if !skipping {
-        out.write_all(state.pending(value.as_bytes().len()))?;
+        out.write_all(state.pending(value.len()))?;
     }
-    out.write_all(&[quote])
+    out.write_all(&quotes)
 }
 
-fn write_escaped_bytes(value: &[u8], out: &mut impl io::Write) -> io::Result<()> {
-    out.write_all(b"b\"")?;
+fn write_escaped_bytes(
+    value: &[u8],
+    quote: u8,
+    passthrough_utf8: bool,
+    out: &mut impl io::Write,
+) -> io::Result<()> {
+    out.write_all(&[b'b', quote])?;
     let mut state = WriteBytesState::from_value(value);
     let mut skipping = false;
+    // End of the printable UTF-8 character being written as-is.
+    let mut raw_end = 0;
     for (i, &b) in value.iter().enumerate() {
         if skipping {
             state.skip_to(i);
             skipping = false;
         }
+        if i < raw_end {
+            continue;
+        }
         let escape = match b {
-            0 => br"\0",
-            b'"' => br#"\""#,
+            0 => nul_escape(value.get(i + 1)),
+            b'"' | b'\'' if b == quote => quote_escape(quote),
             b'\\' => br"\\",
             b'\n' => br"\n",
             b'\r' => br"\r",
             b'\t' => br"\t",
             _ => {
-                if b >= b' ' && b < 0x7f {
+                if (b' '..0x7f).contains(&b) {
                     // No need to escape. Flush later.
                     continue;
+                } else if let Some(len) =
+                    printable_utf8_len(&value[i..]).filter(|_| passthrough_utf8)
+                {
+                    raw_end = i + len;
+                    continue;
                 } else {
                     // Use \xxx to escape.
                     out.write_all(state.pending(i))?;
@@ -628,7 +1617,54 @@ fn write_escaped_bytes(value: &[u8], out: &mut impl io::Write) -> io::Result<()>
     if !skipping {
         out.write_all(state.pending(value.len()))?;
     }
-    out.write_all(b"\"")
+    out.write_all(&[quote])
+}
+
+/// Length of the printable, non-ASCII UTF-8 character at the start of
+/// `value`, or `None` if there is no such character.
+fn printable_utf8_len(value: &[u8]) -> Option<usize> {
+    let len = match value.first()? {
+        0xc2..=0xdf => 2,
+        0xe0..=0xef => 3,
+        0xf0..=0xf4 => 4,
+        _ => return None,
+    };
+    let s = std::str::from_utf8(value.get(..len)?).ok()?;
+    let ch = s.chars().next()?;
+    if unicode::need_escape(ch) {
+        None
+    } else {
+        Some(len)
+    }
+}
+
+fn quote_escape(quote: u8) -> &'static [u8] {
+    if quote == b'"' {
+        br#"\""#
+    } else {
+        br"\'"
+    }
+}
+
+// "\0" followed by an octal digit would be parsed as a longer octal escape.
+fn nul_escape(next: Option<&u8>) -> &'static [u8] {
+    match next {
+        Some(b'0'..=b'7') => br"\000",
+        _ => br"\0",
+    }
+}
+
+/// Insert `_` between every three digits, counting from the right.
+#[allow(clippy::manual_is_multiple_of)]
+fn group_digits(digits: &str) -> Vec<u8> {
+    let mut out = Vec::with_capacity(digits.len() * 4 / 3);
+    for (i, b) in digits.bytes().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            out.push(b'_');
+        }
+        out.push(b);
+    }
+    out
 }
 
 fn spaces(n: usize) -> Cow<'static, [u8]> {
@@ -639,6 +1675,16 @@ fn spaces(n: usize) -> Cow<'static, [u8]> {
     }
 }
 
+/// Column after `written`, which starts at column `indent`. Columns count
+/// chars, and restart after a newline.
+fn column_after(indent: usize, written: &[u8]) -> usize {
+    let (start, last_line) = match written.iter().rposition(|&b| b == b'\n') {
+        Some(i) => (0, &written[i + 1..]),
+        None => (indent, written),
+    };
+    start + last_line.iter().filter(|&&b| b & 0xc0 != 0x80).count()
+}
+
 // Used to reduce small "write" calls if no escape is needed.
 struct WriteBytesState<'a> {
     value: &'a [u8],
@@ -0,0 +1,57 @@
+use serde::Deserialize;
+use serde::Deserializer;
+use serde::Serialize;
+use serde::Serializer;
+use std::collections::BTreeSet;
+use std::iter::FromIterator;
+
+/// Name passed to `serialize_newtype_struct` by `Set`. The crate's
+/// `Serializer` writes the next seq as a set.
+pub(crate) const SET_TOKEN: &str = "$serde_pyliteral::private::Set";
+
+/// Wrapper to serialize a collection as a Python set, like `{1, 2}`, or
+/// `set()` if it is empty. `T` is expected to serialize as a seq with a
+/// known length, like `Vec`, `HashSet` or `BTreeSet`.
+///
+/// serde serializes sets and lists the same way, so sets like `HashSet`
+/// are written as lists unless wrapped. Other serializers see a seq.
+///
+/// ```
+/// use std::collections::BTreeSet;
+/// use serde_pyliteral::Set;
+///
+/// let set: BTreeSet<i32> = vec![2, 1].into_iter().collect();
+/// assert_eq!(serde_pyliteral::to_string(&set).unwrap(), "[1,2]");
+/// assert_eq!(serde_pyliteral::to_string(&Set(&set)).unwrap(), "{1,2}");
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Set<T>(pub T);
+
+/// A Python set of `T`, ordered so the output is stable.
+///
+/// ```
+/// use serde_pyliteral::PySet;
+///
+/// let set: PySet<i32> = vec![2, 1, 2].into_iter().collect();
+/// assert_eq!(serde_pyliteral::to_string(&set).unwrap(), "{1,2}");
+/// assert_eq!(serde_pyliteral::from_str::<PySet<i32>>("{2, 1}").unwrap(), set);
+/// ```
+pub type PySet<T> = Set<BTreeSet<T>>;
+
+impl<A, T: FromIterator<A>> FromIterator<A> for Set<T> {
+    fn from_iter<I: IntoIterator<Item = A>>(iter: I) -> Self {
+        Set(T::from_iter(iter))
+    }
+}
+
+impl<T: Serialize> Serialize for Set<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_newtype_struct(SET_TOKEN, &self.0)
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Set<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize(deserializer).map(Set)
+    }
+}
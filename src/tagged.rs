@@ -0,0 +1,71 @@
+//! Serialize enums as `{'type': 'Variant', 'data': ...}`.
+//!
+//! Use `#[serde(with = "serde_pyliteral::tagged")]` on a field to get the
+//! adjacently tagged form for an enum that cannot be annotated with
+//! `#[serde(tag = "type", content = "data")]`. Variants without data, like
+//! unit variants, omit `'data'`.
+//!
+//! The value is converted through [`Value`], so this is slower than using
+//! the enum directly.
+
+use crate::Value;
+use serde::de;
+use serde::de::DeserializeOwned;
+use serde::ser;
+use serde::Deserialize;
+use serde::Serialize;
+
+const TYPE: &str = "type";
+const DATA: &str = "data";
+
+pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: ?Sized + Serialize,
+    S: ser::Serializer,
+{
+    let s = crate::to_string(value).map_err(ser::Error::custom)?;
+    let external: Value = crate::from_str(&s).map_err(ser::Error::custom)?;
+    // Externally tagged: {'Variant': data}.
+    let (variant, data) = match external {
+        Value::Dict(mut entries) if entries.len() == 1 => match entries.pop() {
+            Some((Value::Str(variant), data)) => (variant, data),
+            _ => return Err(ser::Error::custom("tagged: expect an enum")),
+        },
+        _ => return Err(ser::Error::custom("tagged: expect an enum")),
+    };
+    let mut entries = vec![(Value::Str(TYPE.to_string()), Value::Str(variant))];
+    if data != Value::Tuple(Vec::new()) {
+        entries.push((Value::Str(DATA.to_string()), data));
+    }
+    Value::Dict(entries).serialize(serializer)
+}
+
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    T: DeserializeOwned,
+    D: de::Deserializer<'de>,
+{
+    let entries = match Value::deserialize(deserializer)? {
+        Value::Dict(entries) => entries,
+        _ => return Err(de::Error::custom("tagged: expect a dict")),
+    };
+    let mut variant = None;
+    let mut data = None;
+    for (key, value) in entries {
+        match (key, value) {
+            (Value::Str(key), Value::Str(value)) if key == TYPE => variant = Some(value),
+            (Value::Str(key), value) if key == DATA => data = Some(value),
+            (key, _) => {
+                return Err(de::Error::custom(format!(
+                    "tagged: unexpected key {:?}",
+                    key
+                )))
+            }
+        }
+    }
+    let variant = variant.ok_or_else(|| de::Error::missing_field(TYPE))?;
+    let data = data.unwrap_or_else(|| Value::Tuple(Vec::new()));
+    let external = Value::Dict(vec![(Value::Str(variant), data)]);
+    let s = crate::to_string(&external).map_err(de::Error::custom)?;
+    crate::from_str(&s).map_err(de::Error::custom)
+}
@@ -1,22 +1,31 @@
+use crate::ser::Config;
 use serde::de;
 use serde::Deserialize;
 use serde::Serialize;
 use serde_bytes::ByteBuf;
 use serde_json::Value;
 use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::collections::HashSet;
 
 fn s<T: ?Sized + Serialize>(v: &T) -> String {
     crate::to_string(v).unwrap()
 }
 
+/// Pretty output with every non-empty container on separate lines.
 fn p<T: ?Sized + Serialize>(v: &T) -> String {
-    let mut s = crate::to_string_pretty(v).unwrap();
+    let mut s = sc(v, Config::new().pretty(true).pretty_width(None));
     if s.contains('\n') {
         s = format!("\n{}", s);
     }
     s
 }
 
+fn sc<T: ?Sized + Serialize>(v: &T, config: Config) -> String {
+    crate::to_string_with_config(v, config).unwrap()
+}
+
 fn b(bytes: &[u8]) -> ByteBuf {
     ByteBuf::from(bytes.to_vec())
 }
@@ -52,6 +61,481 @@ fn test_serialize_basic_types() {
     assert_eq!(s(&vec!["a", "bc"]), "[\"a\",\"bc\"]");
 }
 
+#[test]
+fn test_serialize_subnormal() {
+    use crate::ieee754::IeeeFloat;
+
+    // Subnormals have a biased exponent of 0, like zero.
+    assert_eq!(IeeeFloat::exponent(&f64::MIN_POSITIVE), -1022);
+    assert_eq!(IeeeFloat::exponent(&(f64::MIN_POSITIVE / 2.0)), -1023);
+    assert_eq!(IeeeFloat::exponent(&5e-324f64), -1074);
+    assert_eq!(IeeeFloat::exponent(&f32::MIN_POSITIVE), -126);
+    assert_eq!(IeeeFloat::exponent(&1e-45f32), -149);
+    assert_eq!(IeeeFloat::exponent(&0f64), 0);
+    assert_eq!(IeeeFloat::exponent(&1f64), 0);
+    assert_eq!(IeeeFloat::exponent(&-3f32), 1);
+
+    assert_eq!(s(&f64::MIN_POSITIVE), "2.2250738585072014e-308");
+    assert_eq!(s(&5e-324f64), "5e-324");
+    assert_eq!(s(&-1e-310f64), "-1e-310");
+    assert_eq!(s(&1e-45f32), "1e-45");
+    assert_eq!(s(&0f64), "0.");
+    assert_eq!(s(&-0f32), "-0.");
+    assert_eq!(s(&-2f64), "-2.");
+
+    let values = [
+        f64::MIN_POSITIVE,
+        f64::MIN_POSITIVE / 2.0,
+        f64::MIN_POSITIVE - 5e-324,
+        5e-324,
+        -5e-324,
+        1e-310,
+        0.0,
+        -0.0,
+    ];
+    for v in values {
+        let back: f64 = d(&s(&v));
+        assert_eq!(back.to_bits(), v.to_bits(), "{:e}", v);
+    }
+    for v in [f32::MIN_POSITIVE, 1e-45f32, 1e-40, -0.0] {
+        let back: f32 = d(&s(&v));
+        assert_eq!(back.to_bits(), v.to_bits(), "{:e}", v);
+    }
+}
+
+#[test]
+fn test_serialize_multiline_strings() {
+    let c = || Config::default().multiline_strings(true);
+    assert_eq!(sc(&"a\tb", c()), r#""a\tb""#);
+    assert_eq!(sc(&"a\nb\n", c()), "\"\"\"a\nb\n\"\"\"");
+    assert_eq!(sc(&"'\n\"", c()), "\"\"\"'\n\\\"\"\"\"");
+    assert_eq!(
+        sc(&"'\n\"\"\"x\"\"", c()),
+        "\"\"\"'\n\\\"\\\"\"x\\\"\\\"\"\"\""
+    );
+    assert_eq!(sc(&"\"\n", c()), "'''\"\n'''");
+    assert_eq!(sc(&vec!["a\nb"], c().pretty(true)), "[\"\"\"a\nb\"\"\"]");
+
+    for v in [
+        "a\nb",
+        "\n",
+        "\"\n\"",
+        "\n\"\"\"\"\"\"",
+        "'''\n\"\"\"",
+        "\\\n\r\n",
+    ] {
+        assert_eq!(d::<String>(&sc(&v, c())), v);
+    }
+}
+
+#[test]
+fn test_serialize_quote_style() {
+    use crate::ser::QuoteStyle;
+    let c = |q| Config::default().quote_style(q);
+
+    let v = ("a", "'", "\"", "'\"", b(b"'"), b(b"\""), 'x');
+    assert_eq!(
+        sc(&v, c(QuoteStyle::Auto)),
+        r#"("a","'",'"',"'\"",b"'",b"\"","x")"#
+    );
+    assert_eq!(
+        sc(&v, c(QuoteStyle::Double)),
+        r#"("a","'","\"","'\"",b"'",b"\"","x")"#
+    );
+    assert_eq!(
+        sc(&v, c(QuoteStyle::Single)),
+        r#"('a','\'','"','\'"',b'\'',b'"','x')"#
+    );
+    // Compared with `repr` in CPython.
+    assert_eq!(
+        sc(&v, c(QuoteStyle::CPythonRepr)),
+        r#"('a',"'",'"','\'"',b"'",b'"','x')"#
+    );
+    let reprs = [
+        ("abc", r#"'abc'"#),
+        ("it's", r#""it's""#),
+        (r#"say "hi""#, r#"'say "hi"'"#),
+        (r#"both ' and ""#, r#"'both \' and "'"#),
+        ("", "''"),
+    ];
+    for (v, repr) in reprs {
+        assert_eq!(sc(&v, c(QuoteStyle::CPythonRepr)), repr);
+        let bytes_repr = format!("b{}", repr);
+        assert_eq!(sc(&b(v.as_bytes()), c(QuoteStyle::CPythonRepr)), bytes_repr);
+    }
+
+    // Triple quotes.
+    let c = |q| c(q).multiline_strings(true);
+    assert_eq!(sc(&"'\n", c(QuoteStyle::Single)), "''''\n'''");
+    assert_eq!(sc(&"'a\n''", c(QuoteStyle::Single)), "''''a\n\\'\\''''");
+
+    let styles = [
+        QuoteStyle::Auto,
+        QuoteStyle::Double,
+        QuoteStyle::Single,
+        QuoteStyle::CPythonRepr,
+    ];
+    for q in styles {
+        for v in ["", "'", "\"", "'\"", "\\'", "'''\n\"\"\"", "'\n'"] {
+            assert_eq!(d::<String>(&sc(&v, c(q))), v);
+        }
+        for v in [&b"'"[..], b"\"", b"'\"\\"] {
+            assert_eq!(d::<ByteBuf>(&sc(&b(v), c(q))), b(v));
+        }
+    }
+}
+
+#[test]
+fn test_serialize_ensure_ascii() {
+    let v = ("a文字", "😀", 'é', "\u{7f}\u{80}", b(b"\xe6\x96\x87"));
+    assert_eq!(
+        s(&v),
+        "(\"a文字\",\"😀\",\"é\",\"\\u007f\\u0080\",b\"\\xe6\\x96\\x87\")"
+    );
+    let c = || Config::default().ensure_ascii(true);
+    let ascii = sc(&v, c());
+    assert_eq!(
+        ascii,
+        r#"("a\u6587\u5b57","\U0001f600","\u00e9","\u007f\u0080",b"\xe6\x96\x87")"#
+    );
+    assert!(ascii.is_ascii());
+    assert_eq!(
+        sc(&"文\n字", c().multiline_strings(true)),
+        "\"\"\"\\u6587\n\\u5b57\"\"\""
+    );
+
+    for v in ["文字", "😀a", "\u{10ffff}", "'é\""] {
+        assert_eq!(d::<String>(&sc(&v, c())), v);
+    }
+}
+
+#[test]
+fn test_serialize_bytes_passthrough_utf8() {
+    let c = || Config::default().bytes_passthrough_utf8(true);
+    let v = b(&[
+        &b"a"[..],
+        "文".as_bytes(),
+        b"\xff",
+        "字😀\n\u{85}é".as_bytes(),
+    ]
+    .concat());
+    assert_eq!(
+        s(&v),
+        r#"b"a\xe6\x96\x87\xff\xe5\xad\x97\xf0\x9f\x98\x80\n\xc2\x85\xc3\xa9""#
+    );
+    assert_eq!(sc(&v, c()), "b\"a文\\xff字😀\\n\\xc2\\x85é\"");
+
+    // Truncated or invalid sequences are escaped byte by byte.
+    let v = b(b"\xe6\x96 \xe6\x96\x87\xe6 \xc0\x80 \xed\xa0\x80");
+    assert_eq!(sc(&v, c()), r#"b"\xe6\x96 文\xe6 \xc0\x80 \xed\xa0\x80""#);
+
+    for v in ["文字", "a\u{85}é\"'"] {
+        let v = b(v.as_bytes());
+        assert_eq!(d::<ByteBuf>(&sc(&v, c())), v);
+    }
+}
+
+#[test]
+fn test_serialize_lossy_control_replacement() {
+    let v = ("a\x01b", '\x01', "\0\x7f\u{85}", "\t\r\n");
+    assert_eq!(s(&v), r#"("a\u0001b","\u0001","\0\u007f\u0085","\t\r\n")"#);
+    let c = |r| Config::default().lossy_control_replacement(Some(r));
+    assert_eq!(sc(&v, c(' ')), r#"("a b"," ","   ","\t\r\n")"#);
+    let none = c(' ').lossy_control_replacement(None);
+    assert_eq!(sc(&v, none), s(&v));
+    assert_eq!(sc(&"a\x01b", c('\u{fffd}')), "\"a\u{fffd}b\"");
+    assert_eq!(
+        sc(&"a\x01b", c('\u{fffd}').ensure_ascii(true)),
+        r#""a\ufffdb""#
+    );
+    assert_eq!(sc(&"a\x01b", c('"')), r#"'a"b'"#);
+}
+
+#[test]
+fn test_serialize_trailing_newline() {
+    let c = || Config::default().trailing_newline(true);
+    assert_eq!(sc(&1, c()), "1\n");
+    assert_eq!(sc(&-1.5, c()), "-1.5\n");
+    assert_eq!(sc(&"a", c()), "\"a\"\n");
+    assert_eq!(sc(&b(b"a"), c()), "b\"a\"\n");
+    assert_eq!(sc(&Some(true), c()), "True\n");
+    assert_eq!(sc(&None::<i32>, c()), "None\n");
+    assert_eq!(sc(&(), c()), "()\n");
+    assert_eq!(sc(&vec![1, 2], c()), "[1,2]\n");
+    assert_eq!(sc(&crate::Set(vec![1]), c()), "{1}\n");
+    assert_eq!(sc(&vec![(1, "a")], c().pretty(true)), "[(1, \"a\")]\n");
+    let c = || c().pretty_width(None);
+    assert_eq!(sc(&vec![(1, "a")], c().pretty(true)), "[(1,\n  \"a\")]\n");
+    assert_eq!(sc(&vec![1], c().indent(Some(2))), "[\n  1\n]\n");
+
+    let m: HashMap<&str, Vec<i32>> = vec![("b", vec![]), ("a", vec![1])].into_iter().collect();
+    assert_eq!(sc(&m, c().sort_keys(true)), "{\"a\":[1],\"b\":[]}\n");
+
+    #[derive(Serialize)]
+    enum E {
+        U,
+        N(i32),
+        T(i32, i32),
+        S { a: i32 },
+    }
+    assert_eq!(
+        sc(&(E::U, E::N(1), E::T(1, 2), E::S { a: 1 }), c()),
+        r#"({"U":()},{"N":1},{"T":(1,2)},{"S":{"a":1}})"#.to_owned() + "\n"
+    );
+    assert_eq!(sc(&E::T(1, 2), c()), "{\"T\":(1,2)}\n");
+    assert_eq!(sc(&E::U, c()), "{\"U\":()}\n");
+
+    // Not added by default.
+    assert_eq!(s(&vec![1]), "[1]");
+}
+
+#[cfg(feature = "base64")]
+#[test]
+fn test_serialize_bytes_as_base64() {
+    let c = || Config::default().bytes_as_base64(true);
+    assert_eq!(sc(&b(b""), c()), r#"base64.b64decode("")"#);
+    assert_eq!(sc(&b(b"\0\xff1"), c()), r#"base64.b64decode("AP8x")"#);
+    assert_eq!(
+        sc(&(b(b"ab"), "c"), c().pretty(true).pretty_width(None)),
+        "(base64.b64decode(\"YWI=\"),\n \"c\")"
+    );
+
+    for v in [&b""[..], b"a", b"\0\xff\n", &[7; 100]] {
+        assert_eq!(d::<ByteBuf>(&sc(&b(v), c())), b(v));
+    }
+    let v: Vec<ByteBuf> = d("[ base64.b64decode( 'YQ==' ), b'b']");
+    assert_eq!(v, [b(b"a"), b(b"b")]);
+    assert!(crate::from_str::<ByteBuf>("base64.b64decode('!')").is_err());
+}
+
+#[test]
+fn test_serialize_int_radix() {
+    use crate::ser::Radix;
+    let c = |radix| Config::default().int_radix(radix);
+    let v = (255u8, -255i16, 0u32, i64::MIN, u64::MAX);
+    assert_eq!(
+        sc(&v, c(Radix::Dec)),
+        "(255,-255,0,-9223372036854775808,18446744073709551615)"
+    );
+    assert_eq!(
+        sc(&v, c(Radix::Hex)),
+        "(0xff,-0xff,0x0,-0x8000000000000000,0xffffffffffffffff)"
+    );
+    assert_eq!(
+        sc(&v, c(Radix::Oct)),
+        "(0o377,-0o377,0o0,-0o1000000000000000000000,0o1777777777777777777777)"
+    );
+    assert_eq!(sc(&(5u8, -5i8), c(Radix::Bin)), "(0b101,-0b101)");
+    assert_eq!(sc(&1.5f64, c(Radix::Hex)), "1.5");
+
+    for radix in [Radix::Dec, Radix::Hex, Radix::Oct, Radix::Bin] {
+        let s = sc(&v, c(radix));
+        assert_eq!(d::<(u8, i16, u32, i64, u64)>(&s), v);
+        let s = sc(&(i8::MIN, i8::MAX), c(radix));
+        assert_eq!(d::<(i8, i8)>(&s), (i8::MIN, i8::MAX));
+    }
+}
+
+#[test]
+fn test_serialize_group_digits() {
+    let c = || Config::default().group_digits(true);
+    let v = (0u8, 999u16, 1000u32, -1000i32, 1234567i64, -12345678i64);
+    assert_eq!(sc(&v, c()), "(0,999,1_000,-1_000,1_234_567,-12_345_678)");
+    assert_eq!(sc(&u64::MAX, c()), "18_446_744_073_709_551_615");
+    assert_eq!(
+        sc(&i128::MIN, c()),
+        "-170_141_183_460_469_231_731_687_303_715_884_105_728"
+    );
+    assert_eq!(d::<i128>(&sc(&i128::MIN, c())), i128::MIN);
+    assert_eq!(d::<u64>(&sc(&u64::MAX, c())), u64::MAX);
+
+    // Floats and other radixes are not grouped.
+    assert_eq!(sc(&1234567.0f64, c()), "1234567.");
+    let hex = c().int_radix(crate::ser::Radix::Hex);
+    assert_eq!(sc(&0x123456u32, hex), "0x123456");
+}
+
+#[test]
+fn test_serialize_max_depth() {
+    use serde::ser::SerializeSeq;
+
+    // [[[...]]] nested `depth` times, without allocating.
+    struct Nested(usize);
+    impl Serialize for Nested {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            if self.0 == 0 {
+                serializer.serialize_seq(Some(0))?.end()
+            } else {
+                serializer.collect_seq(std::iter::once(Nested(self.0 - 1)))
+            }
+        }
+    }
+
+    let c = || Config::default().max_serialize_depth(3);
+    assert_eq!(sc(&Nested(2), c()), "[[[]]]");
+    assert_eq!(sc(&vec![(1, vec![2])], c()), "[(1,[2])]");
+
+    let mut out = Vec::new();
+    let mut ser = crate::ser::Serializer::from_writer(&mut out).with_config(c());
+    let err = Nested(10000).serialize(&mut ser).unwrap_err();
+    assert_eq!(err.to_string(), "nesting depth exceeds limit (3)");
+
+    let mut m = BTreeMap::new();
+    m.insert("a", (((1,),),));
+    let mut ser = crate::ser::Serializer::from_writer(Vec::new()).with_config(c());
+    assert!(matches!(
+        m.serialize(&mut ser),
+        Err(crate::Error::DepthLimitExceeded(3))
+    ));
+
+    // The default limit is 128.
+    assert_eq!(s(&Nested(127)).len(), 256);
+    assert!(matches!(
+        crate::to_string(&Nested(128)),
+        Err(crate::Error::DepthLimitExceeded(128))
+    ));
+    let c = Config::default().max_serialize_depth(1000);
+    assert_eq!(sc(&Nested(500), c).len(), 1002);
+}
+
+#[test]
+fn test_serialize_flush() {
+    use std::io::BufWriter;
+    use std::io::Write;
+
+    // `to_writer` leaves data in the buffer.
+    let mut w = BufWriter::with_capacity(1024, Vec::new());
+    crate::to_writer(&mut w, &vec![1, 2]).unwrap();
+    assert!(w.get_ref().is_empty());
+    w.flush().unwrap();
+    assert_eq!(w.get_ref(), b"[1,2]");
+
+    let mut w = BufWriter::with_capacity(1024, Vec::new());
+    crate::to_writer_flush(&mut w, &vec![1, 2]).unwrap();
+    assert_eq!(w.get_ref(), b"[1,2]");
+
+    let w = BufWriter::with_capacity(1024, Vec::new());
+    let mut ser = crate::ser::Serializer::from_writer(w);
+    "a".serialize(&mut ser).unwrap();
+    ser.finish().unwrap();
+    assert_eq!(ser.into_inner().get_ref(), b"\"a\"");
+
+    // Flush errors are reported.
+    struct FailFlush;
+    impl Write for FailFlush {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Err(std::io::Error::other("flush failed"))
+        }
+    }
+    assert!(crate::to_writer(FailFlush, &1).is_ok());
+    let err = crate::to_writer_flush(FailFlush, &1).unwrap_err();
+    assert_eq!(err.to_string(), "flush failed");
+}
+
+#[test]
+fn test_serialize_fixed_buf() {
+    let v = vec!["abc", "def"];
+
+    let mut buf = [0u8; 13];
+    let mut fixed = crate::ser::FixedBuf::new(&mut buf);
+    crate::to_writer(&mut fixed, &v).unwrap();
+    assert_eq!(fixed.as_bytes(), br#"["abc","def"]"#);
+    assert_eq!(fixed.len(), 13);
+
+    let mut buf = [0u8; 12];
+    let mut fixed = crate::ser::FixedBuf::new(&mut buf);
+    let e = crate::to_writer(&mut fixed, &v).unwrap_err();
+    assert_eq!(e.to_string(), "buffer capacity (12) exceeded");
+    assert_eq!(fixed.as_bytes(), br#"["abc","def""#);
+}
+
+#[test]
+fn test_serialize_seq_stream() {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    let stream = |config: Config| {
+        let mut out = Vec::new();
+        crate::to_writer_seq(&mut out, 0..100000, config).unwrap();
+        String::from_utf8(out).unwrap()
+    };
+    let v: Vec<u32> = (0..100000).collect();
+    assert_eq!(stream(Config::new()), s(&v));
+    assert_eq!(
+        stream(Config::new().pretty(true)),
+        sc(&v, Config::new().pretty(true))
+    );
+    let c = || Config::new().indent(Some(2));
+    assert_eq!(stream(c()), sc(&v, c()));
+
+    let nested = vec![vec!["a", "b"], vec![], vec!["c"]];
+    let mut out = Vec::new();
+    crate::to_writer_seq(&mut out, nested.iter(), c()).unwrap();
+    assert_eq!(out, sc(&nested, c()).as_bytes());
+
+    let mut out = Vec::new();
+    crate::to_writer_seq(&mut out, std::iter::empty::<u8>(), c()).unwrap();
+    assert_eq!(out, b"[]");
+
+    // Items are written before the iterator is exhausted.
+    struct Counter(Rc<Cell<usize>>);
+    impl std::io::Write for Counter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.set(self.0.get() + buf.len());
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+    let written = Rc::new(Cell::new(0));
+    let iter = (0..1000).inspect(|&i| {
+        if i == 999 {
+            assert!(written.get() > 1000);
+        }
+    });
+    crate::to_writer_seq(Counter(written.clone()), iter, Config::new()).unwrap();
+    assert_eq!(written.get(), s(&(0..1000).collect::<Vec<_>>()).len());
+}
+
+#[test]
+fn test_serialize_64_bit_int() {
+    // Python ints are unbounded. Unlike JSON, there is no precision loss
+    // beyond 2**53.
+    assert_eq!(s(&u64::MAX), "18446744073709551615");
+    assert_eq!(s(&i64::MIN), "-9223372036854775808");
+    assert_eq!(s(&i64::MAX), "9223372036854775807");
+    assert_eq!(s(&9007199254740993u64), "9007199254740993");
+    assert_eq!(
+        s(&(u64::MAX - 2, i64::MIN + 1)),
+        "(18446744073709551613,-9223372036854775807)"
+    );
+    assert_eq!(d::<u64>(&s(&u64::MAX)), u64::MAX);
+    assert_eq!(d::<i64>(&s(&i64::MIN)), i64::MIN);
+    assert_eq!(
+        d::<crate::Value>(&s(&u64::MAX)),
+        crate::Value::Int(u64::MAX.into())
+    );
+}
+
+#[test]
+fn test_serialize_128_bit_int() {
+    assert_eq!(s(&u128::MAX), u128::MAX.to_string());
+    assert_eq!(s(&u128::MAX), "340282366920938463463374607431768211455");
+    assert_eq!(s(&i128::MIN), i128::MIN.to_string());
+    assert_eq!(s(&i128::MIN), "-170141183460469231731687303715884105728");
+    assert_eq!(
+        sc(
+            &i128::MIN,
+            Config::default().int_radix(crate::ser::Radix::Hex)
+        ),
+        "-0x80000000000000000000000000000000"
+    );
+}
+
 #[test]
 fn test_serialize_map() {
     let mut m = BTreeMap::new();
@@ -61,6 +545,34 @@ fn test_serialize_map() {
     assert_eq!(s(&m), r#"{1:"a",2:"b"}"#);
 }
 
+#[test]
+fn test_serialize_hashmap_non_string_keys() {
+    // Single-entry maps have deterministic output.
+    let m: HashMap<i32, &str> = vec![(-1, "a")].into_iter().collect();
+    assert_eq!(s(&m), r#"{-1:"a"}"#);
+    assert_eq!(p(&m), r#"{-1: "a"}"#);
+
+    let m: HashMap<(i32, i32), Vec<i32>> = vec![((1, 2), vec![3, 4])].into_iter().collect();
+    assert_eq!(s(&m), "{(1,2):[3,4]}");
+    assert_eq!(p(&m), "\n{(1,2): [3,\n         4]}");
+
+    let m: HashMap<bool, i32> = vec![(true, 1)].into_iter().collect();
+    assert_eq!(s(&m), "{True:1}");
+
+    // Multi-entry maps round-trip.
+    let m: HashMap<i32, String> = (0..20).map(|i| (i - 10, format!("'{}\"", i))).collect();
+    assert_eq!(d::<HashMap<i32, String>>(&s(&m)), m);
+    assert_eq!(d::<HashMap<i32, String>>(&p(&m)), m);
+
+    let m: HashMap<(i32, i32), Vec<i32>> = (0..20).map(|i| ((i, -i), vec![i; 3])).collect();
+    assert_eq!(d::<HashMap<(i32, i32), Vec<i32>>>(&s(&m)), m);
+    assert_eq!(d::<HashMap<(i32, i32), Vec<i32>>>(&p(&m)), m);
+
+    let m: HashMap<bool, i32> = vec![(true, 1), (false, 0)].into_iter().collect();
+    assert_eq!(d::<HashMap<bool, i32>>(&s(&m)), m);
+    assert_eq!(d::<HashMap<bool, i32>>(&p(&m)), m);
+}
+
 #[test]
 fn test_serialize_struct() {
     #[derive(Serialize)]
@@ -103,12 +615,327 @@ fn test_serialize_struct() {
     );
 }
 
+#[test]
+fn test_serialize_none_fields() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct S {
+        a: Option<i32>,
+        b: Option<Option<i32>>,
+        c: i32,
+        d: Option<Vec<i32>>,
+        e: BTreeMap<String, Option<i32>>,
+    }
+    let v = S {
+        a: None,
+        b: Some(None),
+        c: 1,
+        d: None,
+        e: vec![("x".to_string(), None)].into_iter().collect(),
+    };
+
+    // None fields are included by default.
+    let included = s(&v);
+    assert_eq!(
+        included,
+        r#"{"a":None,"b":None,"c":1,"d":None,"e":{"x":None}}"#
+    );
+    let v2: S = d(&included);
+    assert_eq!(v2.b, None);
+
+    // Skipped. Map entries are kept.
+    let c = || Config::default().skip_none_fields(true);
+    let skipped = sc(&v, c());
+    assert_eq!(skipped, r#"{"b":None,"c":1,"e":{"x":None}}"#);
+    assert_eq!(d::<S>(&skipped), v2);
+    assert_eq!(
+        sc(&v, c().pretty(true).pretty_width(None)),
+        "{\"b\": None,\n \"c\": 1,\n \"e\": {\"x\": None}}"
+    );
+
+    #[derive(Serialize)]
+    enum E {
+        V { a: Option<u8>, b: u8 },
+    }
+    assert_eq!(sc(&E::V { a: None, b: 2 }, c()), r#"{"V":{"b":2}}"#);
+}
+
+#[test]
+fn test_serialize_field_filter() {
+    use crate::ser::FieldAction;
+
+    #[derive(Serialize)]
+    struct Login {
+        user: &'static str,
+        password: &'static str,
+        token: Option<u32>,
+        inner: Vec<Inner>,
+    }
+    #[derive(Serialize)]
+    struct Inner {
+        password: u32,
+    }
+    #[derive(Serialize)]
+    enum E {
+        V { password: u8, b: u8 },
+    }
+
+    let c = || {
+        Config::default().field_filter(|name| match name {
+            "password" => FieldAction::Replace("***".to_string()),
+            "token" => FieldAction::Skip,
+            _ => FieldAction::Keep,
+        })
+    };
+    let v = Login {
+        user: "alice",
+        password: "secret",
+        token: Some(1),
+        inner: vec![Inner { password: 2 }],
+    };
+    assert_eq!(
+        sc(&v, c()),
+        r#"{"user":"alice","password":"***","inner":[{"password":"***"}]}"#
+    );
+    assert_eq!(
+        sc(&v, c().pretty(true).pretty_width(None)),
+        "{\"user\": \"alice\",\n \"password\": \"***\",\n \"inner\": [{\"password\": \"***\"}]}"
+    );
+    assert_eq!(
+        sc(&E::V { password: 1, b: 2 }, c()),
+        r#"{"V":{"password":"***","b":2}}"#
+    );
+
+    // Map entries are not filtered.
+    let m: BTreeMap<_, _> = vec![("password", 1)].into_iter().collect();
+    assert_eq!(sc(&m, c()), r#"{"password":1}"#);
+
+    // Skipped by the filter, or by `skip_none_fields` when kept.
+    let v = Login { token: None, ..v };
+    let c = || {
+        Config::default()
+            .skip_none_fields(true)
+            .field_filter(|name| match name {
+                "password" | "inner" => FieldAction::Skip,
+                _ => FieldAction::Keep,
+            })
+    };
+    assert_eq!(sc(&v, c()), r#"{"user":"alice"}"#);
+}
+
+#[test]
+fn test_serialize_comments() {
+    use crate::ser::Serializer;
+    use serde::ser::SerializeMap;
+    use serde::ser::SerializeSeq;
+    use serde::Serializer as _;
+
+    type Ser<'a> = Serializer<&'a mut Vec<u8>>;
+    fn write(config: Config, f: impl FnOnce(&mut Ser) -> crate::Result<()>) -> String {
+        let mut out = Vec::new();
+        let mut ser = Serializer::from_writer(&mut out).with_config(config);
+        f(&mut ser).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    // {"a": 1, "b": [2, 3]}, with comments.
+    let commented_map = |ser: &mut Ser| {
+        ser.write_comment("header")?;
+        let mut map = ser.serialize_map(None)?;
+        map.serialize_entry("a", &1)?;
+        map.write_comment("b\nhas two lines")?;
+        map.serialize_key("b")?;
+        let mut seq = (&mut *map).serialize_seq(None)?;
+        seq.serialize_element(&2)?;
+        seq.write_comment("three")?;
+        seq.serialize_element(&3)?;
+        SerializeSeq::end(seq)?;
+        SerializeMap::end(map)
+    };
+    let expected = [
+        (
+            Config::default(),
+            "# header\n{\"a\":1,# b\n# has two lines\n\"b\":[2,# three\n3]}",
+        ),
+        (
+            Config::default().pretty(true),
+            r#"# header
+{"a": 1,
+ # b
+ # has two lines
+ "b": [2,
+       # three
+       3]}"#,
+        ),
+        (
+            Config::default().indent(Some(2)),
+            r#"# header
+{
+  "a": 1,
+  # b
+  # has two lines
+  "b": [
+    2,
+    # three
+    3
+  ]
+}"#,
+        ),
+    ];
+    for (config, expected) in expected {
+        let text = write(config, commented_map);
+        assert_eq!(text, expected);
+        assert_eq!(d::<Value>(&text).to_string(), r#"{"a":1,"b":[2,3]}"#);
+    }
+
+    // Comments before the first item, after the last item, and in a
+    // 1-tuple, which needs a trailing comma.
+    let edges = |ser: &mut Ser| {
+        let mut map = ser.serialize_map(None)?;
+        map.write_comment("first")?;
+        map.serialize_key("t")?;
+        let mut tuple = (&mut *map).serialize_tuple(1)?;
+        tuple.serialize_element(&1)?;
+        tuple.write_comment("")?;
+        serde::ser::SerializeTuple::end(tuple)?;
+        map.serialize_key("e")?;
+        let empty = (&mut *map).serialize_seq(Some(0))?;
+        empty.write_comment("only")?;
+        SerializeSeq::end(empty)?;
+        SerializeMap::end(map)
+    };
+    for (config, expected) in [
+        (
+            Config::default(),
+            "{# first\n\"t\":(1,#\n),\"e\":[# only\n]}",
+        ),
+        (
+            Config::default().pretty(true),
+            "{# first\n \"t\": (1,\n       #\n       ),\n \"e\": [# only\n       ]}",
+        ),
+        (
+            Config::default().indent(Some(1)),
+            "{\n # first\n \"t\": (\n  1,\n  #\n ),\n \"e\": [\n  # only\n ]\n}",
+        ),
+    ] {
+        let text = write(config, edges);
+        assert_eq!(text, expected);
+        assert_eq!(d::<Value>(&text).to_string(), r#"{"e":[],"t":[1]}"#);
+        let v: BTreeMap<String, crate::Value> = d(&text);
+        assert_eq!(v["t"], crate::Value::Tuple(vec![crate::Value::Int(1)]));
+    }
+
+    // Line breaks, including a lone "\r", cannot end the comment early.
+    let text = write(Config::default(), |ser| {
+        let mut seq = ser.serialize_seq(None)?;
+        seq.write_comment("a\r\nb\rc\n")?;
+        seq.serialize_element(&1)?;
+        SerializeSeq::end(seq)
+    });
+    assert_eq!(text, "[# a\n# b\n# c\n1]");
+
+    let c = Config::default().sort_keys(true);
+    let mut out = Vec::new();
+    let mut ser = Serializer::from_writer(&mut out).with_config(c);
+    let mut map = (&mut ser).serialize_map(None).unwrap();
+    map.serialize_key("a").unwrap();
+    assert!(map.write_comment("x").is_err());
+
+    // Not between a key and its value.
+    let mut out = Vec::new();
+    let mut ser = Serializer::from_writer(&mut out);
+    let mut map = (&mut ser).serialize_map(None).unwrap();
+    map.serialize_key("a").unwrap();
+    assert!(map.write_comment("between").is_err());
+    map.serialize_value(&1).unwrap();
+    map.write_comment("after").unwrap();
+    SerializeMap::end(map).unwrap();
+    assert_eq!(out, b"{\"a\":1,# after\n}");
+}
+
+#[test]
+fn test_serialize_sort_keys() {
+    let c = || Config::default().sort_keys(true);
+
+    // Each `HashMap` has its own random iteration order.
+    let m = || -> HashMap<String, HashMap<i32, &str>> {
+        (0..10)
+            .map(|i| {
+                let inner = (0..10).map(|j| (j * 7 % 10, "x")).collect();
+                (format!("k{}", 9 - i), inner)
+            })
+            .collect()
+    };
+    let expected = sc(&m(), c());
+    for _ in 0..10 {
+        assert_eq!(sc(&m(), c()), expected);
+        assert_eq!(sc(&m(), c().pretty(true)), sc(&m(), c().pretty(true)));
+    }
+    assert!(expected.starts_with(r#"{"k0":{0:"x",1:"x",2:"x","#));
+    assert!(expected.ends_with(r#"8:"x",9:"x"}}"#));
+
+    // Keys are compared as written.
+    let m: HashMap<&str, i32> = vec![("ab", 1), ("a", 2), ("a1", 3)].into_iter().collect();
+    assert_eq!(sc(&m, c()), r#"{"a":2,"a1":3,"ab":1}"#);
+    let m: HashMap<(i32, i32), Vec<i32>> = vec![((2, 1), vec![]), ((1, 2), vec![3, 4])]
+        .into_iter()
+        .collect();
+    assert_eq!(sc(&m, c()), "{(1,2):[3,4],(2,1):[]}");
+    assert_eq!(
+        sc(&m, c().pretty(true).pretty_width(None)),
+        "{(1,2): [3,\n         4],\n (2,1): []}"
+    );
+    assert_eq!(
+        sc(&m, c().indent(Some(2)).pretty_width(None)),
+        "{\n  (1,2): [\n    3,\n    4\n  ],\n  (2,1): []\n}"
+    );
+
+    // Struct fields keep their order.
+    #[derive(Serialize)]
+    struct S {
+        b: i32,
+        a: BTreeMap<i32, i32>,
+    }
+    let v = S {
+        b: 1,
+        a: vec![(10, 1), (9, 2)].into_iter().collect(),
+    };
+    assert_eq!(sc(&v, c()), r#"{"b":1,"a":{10:1,9:2}}"#);
+}
+
+#[test]
+fn test_serialize_renamed_fields() {
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    #[serde(rename_all = "camelCase")]
+    struct A {
+        foo_bar: u8,
+        #[serde(rename = "naïve 字段")]
+        naive: u8,
+        #[serde(rename = "with-dash")]
+        dash: u8,
+        #[serde(rename = "it's \"quoted\"")]
+        quoted: u8,
+    }
+    let a = A {
+        foo_bar: 1,
+        naive: 2,
+        dash: 3,
+        quoted: 4,
+    };
+    // Keys are always quoted, no matter whether they are valid identifiers.
+    assert_eq!(
+        s(&a),
+        r#"{"fooBar":1,"naïve 字段":2,"with-dash":3,"it's \"quoted\"":4}"#
+    );
+    assert_eq!(d::<A>(&s(&a)), a);
+}
+
 #[test]
 fn test_serialize_enum() {
     #[derive(Serialize)]
     struct D;
 
     #[derive(Serialize)]
+    #[allow(clippy::enum_variant_names)]
     enum A {
         A,
         B(u32),
@@ -124,36 +951,241 @@ fn test_serialize_enum() {
 }
 
 #[test]
-fn test_pretty() {
-    assert_eq!(p(&[1]), "(1,)");
-    assert_eq!(p(&[1, 2]), "\n(1,\n 2)");
+fn test_pretty_fixed_indent() {
+    let c = || Config::default().indent(Some(2)).pretty_width(None);
 
-    assert_eq!(p(&vec![1]), "[1]");
-    assert_eq!(p(&vec![1, 2]), "\n[1,\n 2]");
-    assert_eq!(p(&vec![vec![1], vec![2, 2]]), "\n[[1],\n [2,\n  2]]");
+    #[derive(Serialize)]
+    enum E {
+        A,
+        B(u8),
+        C { x: u8 },
+    }
+    let mut m = BTreeMap::new();
+    m.insert("a", (vec![1, 2], (3,), Vec::<u8>::new()));
+    m.insert("b", (vec![], (4,), vec![5]));
+    assert_eq!(
+        sc(&m, c()),
+        r#"{
+  "a": (
+    [
+      1,
+      2
+    ],
+    (
+      3,
+    ),
+    []
+  ),
+  "b": (
+    [],
+    (
+      4,
+    ),
+    [
+      5
+    ]
+  )
+}"#
+    );
 
+    assert_eq!(sc(&vec![E::A], c()), "[\n  {\n    \"A\": ()\n  }\n]");
+    assert_eq!(
+        sc(&(E::B(1), E::C { x: 2 }), c().indent(Some(1))),
+        "(\n {\n  \"B\": 1\n },\n {\n  \"C\": {\n   \"x\": 2\n  }\n }\n)"
+    );
+
+    // Keys are not indented.
     let mut m = BTreeMap::new();
-    assert_eq!(p(&m), "{}");
-    m.insert(1, "a");
-    assert_eq!(p(&m), "{1: \"a\"}");
-    m.insert(222, "b");
-    assert_eq!(p(&m), "\n{1: \"a\",\n 222: \"b\"}");
+    m.insert((1, 2), crate::Set(vec![3]));
+    assert_eq!(sc(&m, c()), "{\n  (1,2): {\n    3\n  }\n}");
 
+    // Scalars and empty containers.
+    assert_eq!(sc(&1, c()), "1");
+    assert_eq!(sc(&BTreeMap::<u8, u8>::new(), c()), "{}");
+    assert_eq!(sc(&crate::Set(Vec::<u8>::new()), c()), "set()");
+
+    // Output can be parsed back.
     let mut m = BTreeMap::new();
-    m.insert((1, (2, 4)), vec![vec![1], vec![2]]);
-    m.insert((222, (333, 0)), vec![vec![3, 4], vec![5]]);
+    m.insert("a".to_string(), (vec![1], (2,), vec![3]));
+    assert_eq!(d::<BTreeMap<_, _>>(&sc(&m, c())), m);
+}
+
+#[test]
+fn test_pretty_width() {
+    let c = |width| Config::new().pretty(true).pretty_width(Some(width));
+
+    // Short containers stay on one line. Long ones wrap, like `pprint`.
+    assert_eq!(sc(&vec![1, 2, 3], c(79)), "[1, 2, 3]");
+    assert_eq!(sc(&vec![1, 2, 3], c(9)), "[1, 2, 3]");
+    assert_eq!(sc(&vec![1, 2, 3], c(8)), "[1,\n 2,\n 3]");
+    assert_eq!(sc(&(1,), c(4)), "(1,)");
+    assert_eq!(sc(&Vec::<u8>::new(), c(0)), "[]");
+
+    // The default width is 79.
     assert_eq!(
-        p(&m),
-        r#"
-{(1,(2,4)): [[1],
-             [2]],
- (222,(333,0)): [[3,
-                  4],
-                 [5]]}"#
+        crate::to_string_pretty(&vec![1, 2, 3]).unwrap(),
+        "[1, 2, 3]"
     );
+    let long: Vec<u32> = (0..30).collect();
+    assert_eq!(crate::to_string_pretty(&long).unwrap(), sc(&long, c(79)));
+    assert!(crate::to_string_pretty(&long)
+        .unwrap()
+        .starts_with("[0,\n 1,\n"));
 
     #[derive(Serialize)]
-    struct A {
+    struct S {
+        name: &'static str,
+        tags: Vec<&'static str>,
+        matrix: Vec<Vec<u32>>,
+    }
+    let v = S {
+        name: "x",
+        tags: vec!["a", "b"],
+        matrix: vec![vec![1, 2, 3], vec![4, 5, 6], vec![70000, 80000, 90000]],
+    };
+    assert_eq!(
+        sc(&v, c(30)),
+        r#"{"name": "x",
+ "tags": ["a", "b"],
+ "matrix": [[1, 2, 3],
+            [4, 5, 6],
+            [70000,
+             80000,
+             90000]]}"#
+    );
+    assert_eq!(
+        sc(&v, c(100)),
+        r#"{"name": "x", "tags": ["a", "b"], "matrix": [[1, 2, 3], [4, 5, 6], [70000, 80000, 90000]]}"#
+    );
+    assert_eq!(d::<Value>(&sc(&v, c(30))), d::<Value>(&s(&v)));
+
+    // With a fixed indent.
+    assert_eq!(
+        sc(&v, c(30).indent(Some(2))),
+        r#"{
+  "name": "x",
+  "tags": ["a", "b"],
+  "matrix": [
+    [1, 2, 3],
+    [4, 5, 6],
+    [70000, 80000, 90000]
+  ]
+}"#
+    );
+
+    // With sorted keys.
+    let m: HashMap<&str, Vec<u8>> = vec![("b", vec![1]), ("a", vec![2, 3])]
+        .into_iter()
+        .collect();
+    assert_eq!(sc(&m, c(23).sort_keys(true)), r#"{"a": [2, 3], "b": [1]}"#);
+    assert_eq!(
+        sc(&m, c(22).sort_keys(true)),
+        "{\"a\": [2, 3],\n \"b\": [1]}"
+    );
+
+    // Multi-line strings and comments always span lines.
+    let v = vec![vec!["a\nb", "c"]];
+    assert_eq!(
+        sc(&v, c(80).multiline_strings(true)),
+        "[[\"\"\"a\nb\"\"\",\n  \"c\"]]"
+    );
+    let mut out = Vec::new();
+    let mut ser = crate::ser::Serializer::from_writer(&mut out).with_config(c(80));
+    let mut seq = serde::Serializer::serialize_seq(&mut ser, None).unwrap();
+    serde::ser::SerializeSeq::serialize_element(&mut seq, &[1, 2]).unwrap();
+    seq.write_comment("x").unwrap();
+    serde::ser::SerializeSeq::serialize_element(&mut seq, &3).unwrap();
+    serde::ser::SerializeSeq::end(seq).unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), "[(1, 2),\n # x\n 3]");
+}
+
+#[test]
+fn test_pretty_tight() {
+    let mut m = BTreeMap::new();
+    m.insert("a", vec![1, 2]);
+    m.insert("bc", vec![3]);
+    let c = || Config::new().pretty(true).pretty_width(None);
+
+    // Values are aligned after the colon.
+    assert_eq!(
+        sc(&m, c().tight_colon(true)),
+        "{\"a\":[1,\n      2],\n \"bc\":[3]}"
+    );
+    assert_eq!(
+        sc(&m, c().tight_colon(true).indent(Some(1))),
+        "{\n \"a\":[\n  1,\n  2\n ],\n \"bc\":[\n  3\n ]\n}"
+    );
+    assert_eq!(
+        sc(&m, c().tight_colon(true).sort_keys(true)),
+        "{\"a\":[1,\n      2],\n \"bc\":[3]}"
+    );
+
+    // Only one-line containers are affected by `tight_commas`.
+    assert_eq!(
+        sc(&m, c().tight_commas(true)),
+        "{\"a\": [1,\n       2],\n \"bc\": [3]}"
+    );
+    let w = || c().pretty_width(Some(80)).tight_commas(true);
+    assert_eq!(sc(&m, w()), "{\"a\": [1,2],\"bc\": [3]}");
+    assert_eq!(sc(&m, w().tight_colon(true)), "{\"a\":[1,2],\"bc\":[3]}");
+    assert_eq!(sc(&m, w().indent(Some(2))), "{\"a\": [1,2],\"bc\": [3]}");
+
+    // The compact output is unaffected.
+    let tight = Config::new().tight_colon(true).tight_commas(true);
+    assert_eq!(sc(&m, tight), s(&m));
+    assert_eq!(
+        d::<Value>(&sc(&m, c().tight_colon(true))),
+        d::<Value>(&s(&m))
+    );
+}
+
+#[test]
+fn test_pretty() {
+    assert_eq!(p(&[1]), "(1,)");
+    assert_eq!(p(&[1, 2]), "\n(1,\n 2)");
+
+    assert_eq!(p(&vec![1]), "[1]");
+    assert_eq!(p(&vec![1, 2]), "\n[1,\n 2]");
+    assert_eq!(p(&vec![vec![1], vec![2, 2]]), "\n[[1],\n [2,\n  2]]");
+
+    let mut m = BTreeMap::new();
+    assert_eq!(p(&m), "{}");
+    m.insert(1, "a");
+    assert_eq!(p(&m), "{1: \"a\"}");
+    m.insert(222, "b");
+    assert_eq!(p(&m), "\n{1: \"a\",\n 222: \"b\"}");
+
+    let mut m = BTreeMap::new();
+    m.insert((1, (2, 4)), vec![vec![1], vec![2]]);
+    m.insert((222, (333, 0)), vec![vec![3, 4], vec![5]]);
+    assert_eq!(
+        p(&m),
+        r#"
+{(1,(2,4)): [[1],
+             [2]],
+ (222,(333,0)): [[3,
+                  4],
+                 [5]]}"#
+    );
+
+    // A key spanning lines aligns the value to its last line.
+    let c = || {
+        Config::default()
+            .pretty(true)
+            .multiline_strings(true)
+            .pretty_width(None)
+    };
+    let mut m = BTreeMap::new();
+    m.insert(("a\nbc", 1), vec![1, 2]);
+    m.insert(("名称", 2), vec![3, 4]);
+    assert_eq!(
+        sc(&m, c()),
+        "{(\"\"\"a\nbc\"\"\",1): [1,\n           2],\n (\"名称\",2): [3,\n            4]}"
+    );
+    assert_eq!(sc(&m, c().sort_keys(true)), sc(&m, c()));
+
+    #[derive(Serialize)]
+    struct A {
         foo: Vec<u32>,
         inner: Vec<A>,
     }
@@ -178,11 +1210,44 @@ fn test_pretty() {
     );
 }
 
+#[test]
+fn test_pretty_empty_containers() {
+    #[derive(Serialize)]
+    struct Empty {}
+    #[derive(Serialize)]
+    struct EmptyTuple();
+
+    let empty_map: BTreeMap<u8, u8> = BTreeMap::new();
+    let empty_vec: Vec<u8> = Vec::new();
+    assert_eq!(p(&()), "()");
+    assert_eq!(p(&empty_vec), "[]");
+    assert_eq!(p(&empty_map), "{}");
+    assert_eq!(p(&Empty {}), "{}");
+    assert_eq!(p(&EmptyTuple()), "()");
+    assert_eq!(p(&((), vec![empty_vec.clone()])), "\n((),\n [[]])");
+    assert_eq!(
+        p(&vec![(vec![(), ()], empty_map.clone())]),
+        "\n[([(),\n   ()],\n  {})]"
+    );
+
+    let mut inner = BTreeMap::new();
+    inner.insert(1, empty_map);
+    let mut m = BTreeMap::new();
+    m.insert("a", BTreeMap::new());
+    m.insert("b", inner);
+    assert_eq!(p(&m), "\n{\"a\": {},\n \"b\": {1: {}}}");
+}
+
 #[test]
 fn test_deserialize_basic() {
     let v: String = d(r#"'abcd文字\0\n\t\\\uf230"'"#);
     assert_eq!(v, "abcd文字\u{0}\n\t\\\u{f230}\"");
 
+    let v: String = d(r"'\x41\xe9\u00e9\U0001F600'");
+    assert_eq!(v, "Aéé😀");
+    assert!(crate::from_str::<String>(r"'\x4'").is_err());
+    assert!(crate::from_str::<String>(r"'\U00110000'").is_err());
+
     let v: ByteBuf = d(r#"b"\0\n\t\x12\xff123 \\\'\"\r""#);
     assert_eq!(v, [0, 10, 9, 18, 255, 49, 50, 51, 32, 92, 39, 34, 13]);
 
@@ -212,6 +1277,1434 @@ fn test_deserialize_basic() {
     assert_eq!(v, ());
 }
 
+#[test]
+fn test_deserialize_bool() {
+    let ok = |s: &str| crate::from_str::<bool>(s).unwrap();
+    let err = |s: &str| crate::from_str::<bool>(s).unwrap_err();
+    for (input, expected) in [
+        ("True", true),
+        ("False", false),
+        ("true", true),
+        ("false", false),
+        ("1", true),
+        ("0", false),
+        (" True ", true),
+        ("# c\n False", false),
+        ("True#c", true),
+        ("0 ", false),
+    ] {
+        assert_eq!(ok(input), expected, "input: {:?}", input);
+    }
+    for input in [
+        "", "T", "Tru", "Fals", "TRUE", "tRUE", "Truee", "True_", "True1", "Falsey", "10", "01",
+        "1.0", "0.", "2", "-1", "None", "'True'",
+    ] {
+        err(input);
+    }
+    assert_eq!(
+        err("Truthy").to_string(),
+        r#"expect bool, got unknown type ("Truthy") at line 1 column 1"#
+    );
+
+    // Word boundaries inside containers.
+    assert_eq!(
+        d::<Vec<bool>>("[True,False,1,0,true]"),
+        [true, false, true, false, true]
+    );
+    assert_eq!(d::<(bool, bool)>("(1,0)"), (true, false));
+    assert_eq!(d::<BTreeMap<bool, bool>>("{True:0, False:1}").len(), 2);
+    assert!(crate::from_str::<Vec<bool>>("[Truee]").is_err());
+    assert!(crate::from_str::<Vec<bool>>("[10]").is_err());
+
+    // None is also matched as a keyword.
+    assert_eq!(d::<Option<bool>>("None"), None);
+    assert_eq!(d::<Option<bool>>("null"), None);
+    assert_eq!(d::<Option<bool>>("true"), Some(true));
+    assert_eq!(
+        d::<Vec<Option<i32>>>("[None,null,1]"),
+        [None, None, Some(1)]
+    );
+    assert!(crate::from_str::<Option<bool>>("Nonesuch").is_err());
+
+    // Keywords followed by word characters are not detected as keywords.
+    for input in [
+        "Nonesuch",
+        "[None_]",
+        "(None1,)",
+        "None.x",
+        "{Truex: 1}",
+        "[Falsey]",
+    ] {
+        assert!(crate::from_str::<crate::Value>(input).is_err(), "{}", input);
+        assert!(crate::from_str::<Value>(input).is_err(), "{}", input);
+    }
+    let err = crate::from_str::<crate::Value>("[None, Nonex]").unwrap_err();
+    assert_eq!(err.column(), Some(8));
+    assert_eq!(d::<Value>("[null, true]").to_string(), "[null,true]");
+}
+
+#[test]
+fn test_match_keyword() {
+    use std::io::Read;
+
+    fn match_keyword(input: &str, candidates: &[&str]) -> (Option<usize>, String) {
+        let mut de = crate::de::Deserializer::new(input.as_bytes());
+        let matched = de.match_keyword(candidates).unwrap();
+        let mut rest = String::new();
+        de.read_to_string(&mut rest).unwrap();
+        (matched, rest)
+    }
+
+    let m = |input| match_keyword(input, &["None", "No", "x"]);
+    assert_eq!(m("None"), (Some(0), "".into()));
+    assert_eq!(m(" # c\n None, 1"), (Some(0), ", 1".into()));
+    assert_eq!(m("No]"), (Some(1), "]".into()));
+    assert_eq!(m("x"), (Some(2), "".into()));
+    assert_eq!(m("x.1"), (None, "x.1".into()));
+
+    // Word boundaries. Spaces are consumed even without a match.
+    assert_eq!(m(" Nonesuch"), (None, "Nonesuch".into()));
+    assert_eq!(m("None_"), (None, "None_".into()));
+    assert_eq!(m("Non"), (None, "Non".into()));
+    assert_eq!(m("N"), (None, "N".into()));
+    assert_eq!(m(""), (None, "".into()));
+    assert_eq!(m("NONE"), (None, "NONE".into()));
+
+    // Punctuation keywords.
+    let m = |input| match_keyword(input, &["()"]);
+    assert_eq!(m("(),"), (Some(0), ",".into()));
+    assert_eq!(m("(1)"), (None, "(1)".into()));
+}
+
+#[test]
+fn test_deserialize_octal_escape() {
+    let v: String = d(r#"'\101\0\12\7x\1234\777'"#);
+    assert_eq!(v, "A\0\n\x07x\u{53}4\u{1ff}");
+
+    let v: ByteBuf = d(r#"b'\0\00\000\0000\377\18\1'"#);
+    assert_eq!(v, [0, 0, 0, 0, b'0', 255, 1, b'8', 1]);
+
+    assert_eq!(s(&"\0\x31\08"), r#""\0001\08""#);
+    assert_eq!(d::<String>(&s(&"\0\x31\08")), "\0\x31\08");
+    assert_eq!(s(&b(b"\0\x37")), r#"b"\0007""#);
+    assert_eq!(d::<ByteBuf>(&s(&b(b"\0\x37"))), b(b"\0\x37"));
+
+    assert!(crate::from_str::<ByteBuf>(r"b'\400'").is_err());
+    assert!(crate::from_str::<String>(r"'\8'").is_err());
+}
+
+#[test]
+fn test_deserialize_control_escape() {
+    let v: String = d(r"'\a\b\f\v'");
+    assert_eq!(v, "\x07\x08\x0c\x0b");
+
+    let v: ByteBuf = d(r#"b"\a\b\f\v""#);
+    assert_eq!(v, [7, 8, 12, 11]);
+}
+
+#[test]
+fn test_deserialize_line_continuation() {
+    let v: String = d("'a\\\nb'");
+    assert_eq!(v, "ab");
+    let v: String = d("\"a\\\r\nb\\\rc\"");
+    assert_eq!(v, "abc");
+    let v: String = d("'''a\\\n\\\r\n'''");
+    assert_eq!(v, "a");
+    let v: String = d("'\\\\\nx'");
+    assert_eq!(v, "\\\nx");
+
+    let v: ByteBuf = d("b'a\\\nb\\\r\nc\\\r'");
+    assert_eq!(v, b(b"abc"));
+
+    let v: Vec<String> = d("['a\\\n', 'b\\\r']");
+    assert_eq!(v, ["a", "b"]);
+}
+
+#[test]
+fn test_deserialize_surrogate_pair() {
+    let v: String = d(r"'\ud83d\ude00x\uD83D\uDE00'");
+    assert_eq!(v, "😀x😀");
+    let v: char = d(r#""\ud800\udc00""#);
+    assert_eq!(v, '\u{10000}');
+
+    for (input, message) in [
+        (r"'\ud83d'", r"\ud83d at line 1 column 8"),
+        (r"'\ud83dx'", r"\ud83d at line 1 column 8"),
+        (r"'\ud83d\n'", r"\ud83d at line 1 column 9"),
+        (r"'\ud83d\U0001f600'", r"\ud83d at line 1 column 9"),
+        (r"'\ud83d\ud83d'", r"\ud83d at line 1 column 13"),
+        (r"'\ude00'", r"\ude00 at line 1 column 7"),
+        (r"'\U0000d83d'", r"\ud83d at line 1 column 11"),
+    ] {
+        let e = crate::from_str::<String>(input).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            format!("cannot parse string: unpaired surrogate: {}", message)
+        );
+    }
+}
+
+#[test]
+fn test_deserialize_char() {
+    let v: Vec<char> = d(r#"['a', "\n", 'é', '\U0001f600', '\x00', '\'', '\101', '文']"#);
+    assert_eq!(v, ['a', '\n', 'é', '😀', '\0', '\'', 'A', '文']);
+    let v: char = d(r"'\ud83d\ude00'");
+    assert_eq!(v, '😀');
+    for v in ['\n', 'é', '😀', '\u{7f}', '"'] {
+        assert_eq!(d::<char>(&s(&v)), v);
+    }
+
+    let e = |s| match crate::from_str::<char>(s).unwrap_err() {
+        crate::Error::At { error, .. } => error.to_string(),
+        e => panic!("unexpected error: {}", e),
+    };
+    assert_eq!(e("''"), "expect char, got empty str");
+    assert_eq!(e("'ab'"), "expect char, got str of 2 chars");
+    assert_eq!(e(r"'\n\n\n'"), "expect char, got str of 3 chars");
+    assert_eq!(e("1"), "expect str, got int");
+}
+
+#[test]
+fn test_deserialize_triple_quoted() {
+    let v: String = d(r#""""a"b""c\"""\n""""#);
+    assert_eq!(v, "a\"b\"\"c\"\"\"\n");
+
+    let v: String = d("'''\n'\"'''");
+    assert_eq!(v, "\n'\"");
+
+    let v: Vec<String> = d(r#"["", '', """""", '''''', """'""", "x"]"#);
+    assert_eq!(v, ["", "", "", "", "'", "x"]);
+
+    // Line endings inside triple quotes are kept as-is.
+    let v: String = d("'''a\r\nb\nc\r\n\r\n'''");
+    assert_eq!(v, "a\r\nb\nc\r\n\r\n");
+    let v: String = d("\"\"\"\r\n\"\\r\\n\"\n\"\"\"");
+    assert_eq!(v, "\r\n\"\r\n\"\n");
+    let v: Vec<String> = crate::from_slice(b"['''\r\n''', \"\"\"\n\r\n\"\"\"]").unwrap();
+    assert_eq!(v, ["\r\n", "\n\r\n"]);
+    let input = "x\r\ny\nz\r\n";
+    let c = Config::default().multiline_strings(true);
+    assert_eq!(d::<String>(&sc(&input, c)), input);
+}
+
+#[test]
+fn test_deserialize_128_bit_int() {
+    let v: u128 = d("340282366920938463463374607431768211455");
+    assert_eq!(v, u128::MAX);
+    let v: i128 = d("-170141183460469231731687303715884105728");
+    assert_eq!(v, i128::MIN);
+    let v: (u128, i128) = d("(0xffffffffffffffffffffffffffffffff, -0x1_0000_0000_0000_0000)");
+    assert_eq!(v, (u128::MAX, -(1i128 << 64)));
+    assert_eq!(d::<u128>(&s(&u128::MAX)), u128::MAX);
+    assert_eq!(d::<i128>(&s(&i128::MIN)), i128::MIN);
+    assert!(crate::from_str::<u128>("340282366920938463463374607431768211456").is_err());
+}
+
+#[test]
+fn test_deserialize_int_overflow() {
+    #[derive(Deserialize, Debug)]
+    struct S {
+        #[allow(dead_code)]
+        port: u16,
+    }
+    let err = crate::from_str::<S>("{'port': 0x1_0000}").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "cannot parse '0x10000' as u16: number too large to fit in target type at line 1 column 18"
+    );
+    assert_eq!(err.classify(), crate::Category::Data);
+    let err = match err {
+        crate::Error::At { error, .. } => *error,
+        e => e,
+    };
+    match err {
+        crate::Error::ParseIntAs {
+            text,
+            target,
+            source,
+        } => {
+            assert_eq!(text, "0x10000");
+            assert_eq!(target, "u16");
+            assert_eq!(*source.kind(), std::num::IntErrorKind::PosOverflow);
+        }
+        e => panic!("unexpected error: {:?}", e),
+    }
+
+    let err = crate::from_str::<Vec<i8>>("[1, -129]").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "cannot parse '-129' as i8: number too small to fit in target type at line 1 column 9"
+    );
+}
+
+#[test]
+fn test_deserialize_int_fast_path() {
+    let v: Vec<i32> = d("[0, -1, +2, 1_000, -2147483648, 2147483647 , 7#c\n]");
+    assert_eq!(v, [0, -1, 2, 1000, i32::MIN, i32::MAX, 7]);
+
+    let v: (u8, i8, u128, i128) = d("(255,-128,340282366920938463463374607431768211455,-170_141_183_460_469_231_731_687_303_715_884_105_728)");
+    assert_eq!(v, (255, -128, u128::MAX, i128::MIN));
+
+    // Forms handled by the slow path.
+    let v: Vec<u16> = d("[0x_ff, 0o7, 0b1]");
+    assert_eq!(v, [255, 7, 1]);
+
+    // Errors are the same as the slow path.
+    let e = |s| match crate::from_str::<u8>(s).unwrap_err() {
+        crate::Error::At { error, .. } => error.to_string(),
+        e => panic!("unexpected error: {}", e),
+    };
+    assert_eq!(
+        e("256"),
+        "cannot parse '256' as u8: number too large to fit in target type"
+    );
+    assert_eq!(
+        e("-1"),
+        "cannot parse '-1' as u8: invalid digit found in string"
+    );
+    assert!(crate::from_str::<i8>("1.5").is_err());
+    assert!(crate::from_str::<i8>("1e2").is_err());
+    assert!(crate::from_str::<u128>("340282366920938463463374607431768211456").is_err());
+    assert!(crate::from_str::<i32>("'1'").is_err());
+    assert!(crate::from_str::<i32>("").is_err());
+}
+
+#[test]
+fn test_deserialize_radix_int() {
+    let v: Vec<i64> = d("[0xff, 0XFF, 0o17, 0O17, 0b1010, 0B1010, 0xff_ff, 0b_1_0]");
+    assert_eq!(v, [255, 255, 15, 15, 10, 10, 65535, 2]);
+
+    let v: Vec<i8> = d("[-0x10, +0x10, -0x80, -0o7, -0b1, 0x0]");
+    assert_eq!(v, [-16, 16, -128, -7, -1, 0]);
+
+    let v: (u8, u16, u32, u64) = d("(0xff, 0o177777, 0b1, 0xffffffffffffffff)");
+    assert_eq!(v, (255, 65535, 1, u64::MAX));
+
+    let v: Value = d("[0x1e, -0x1e, 0b1, 0o10]");
+    assert_eq!(v.to_string(), "[30,-30,1,8]");
+
+    assert!(crate::from_str::<u8>("0x100").is_err());
+    assert!(crate::from_str::<u8>("-0x1").is_err());
+    assert!(crate::from_str::<u8>("0b2").is_err());
+    assert!(crate::from_str::<u8>("0o8").is_err());
+    assert!(crate::from_str::<u8>("0x").is_err());
+}
+
+#[test]
+fn test_deserialize_dot_float() {
+    let v: Vec<f64> = d("[.5, -.25, +.5, 10., -10., .5e1, 1.e1]");
+    assert_eq!(v, [0.5, -0.25, 0.5, 10.0, -10.0, 5.0, 10.0]);
+
+    let v: Value = d("[.5, -.25, 10.]");
+    assert_eq!(v.to_string(), "[0.5,-0.25,10.0]");
+
+    let v: (f32, f64) = d("(.5, 10.)");
+    assert_eq!(v, (0.5, 10.0));
+
+    assert!(crate::from_str::<f64>(".").is_err());
+    assert!(crate::from_str::<Value>("-.").is_err());
+}
+
+#[test]
+fn test_deserialize_uppercase_exponent() {
+    let v: Vec<f64> = d("[1E3, 1.0E-5, 1.5E+10, -2E2, .5E1, 1e3]");
+    assert_eq!(v, [1e3, 1e-5, 1.5e10, -2e2, 5.0, 1e3]);
+
+    let v: Value = d("[1E3, 'E', \"1E3\"]");
+    assert_eq!(v.to_string(), r#"[1000.0,"E","1E3"]"#);
+
+    let v: (String, f64, String) = d("('1e3', 1E3, 'eE')");
+    assert_eq!(v, ("1e3".to_string(), 1000.0, "eE".to_string()));
+
+    let v: Vec<(f64, f64)> = d("[1E3+2E-1j, complex(1E1, 1E0)]");
+    assert_eq!(v, [(1e3, 0.2), (10.0, 1.0)]);
+
+    assert!(crate::from_str::<f64>("1eE3").is_err());
+}
+
+#[test]
+fn test_deserialize_inf_nan() {
+    let v: Vec<f64> = d("[inf, -inf, +inf, 1e999, -1e999]");
+    assert_eq!(
+        v,
+        [
+            f64::INFINITY,
+            f64::NEG_INFINITY,
+            f64::INFINITY,
+            f64::INFINITY,
+            f64::NEG_INFINITY
+        ]
+    );
+
+    let v: (f32, f64, f64) = d("(nan, -nan, nan)");
+    assert!(v.0.is_nan() && v.1.is_nan() && v.2.is_nan());
+
+    let v: Value = d("[inf, -inf]");
+    assert_eq!(v, Value::Array(vec![Value::Null, Value::Null]));
+
+    // Identifiers starting with "inf" or "nan" are not numbers.
+    assert!(crate::from_str::<Value>("inner").is_err());
+    assert!(crate::from_str::<Value>("info").is_err());
+    assert!(crate::from_str::<Value>("-nana").is_err());
+    assert!(crate::from_str::<f64>("infinite").is_err());
+    assert!(crate::from_str::<f64>("nan_").is_err());
+    assert!(crate::from_str::<f64>("-infinity").is_err());
+    assert_eq!(d::<f64>(" -inf "), f64::NEG_INFINITY);
+    assert!(d::<f64>("+nan").is_nan());
+}
+
+#[test]
+fn test_deserialize_complex() {
+    // repr(), the parenthesized literal, and the constructor are the same value.
+    for s in [
+        "1+2j",
+        "(1+2j)",
+        "complex(1, 2)",
+        "complex(1.0, 2.0,)",
+        "( 1e0+2.j )",
+    ]
+    .iter()
+    {
+        let v: (f64, f64) = d(s);
+        assert_eq!(v, (1.0, 2.0), "{}", s);
+    }
+
+    let v: Vec<(f64, f64)> = d("[4j, -4J, (-1.5-2j), 1e-3+1e3j, complex(3), complex()]");
+    assert_eq!(
+        v,
+        [
+            (0.0, 4.0),
+            (0.0, -4.0),
+            (-1.5, -2.0),
+            (1e-3, 1e3),
+            (3.0, 0.0),
+            (0.0, 0.0)
+        ]
+    );
+
+    let v: Value = d("{'z': (1-2j), 't': (1, 2)}");
+    assert_eq!(v.to_string(), r#"{"t":[1,2],"z":[1.0,-2.0]}"#);
+
+    // A complex number as the first item of a tuple.
+    let v: ((f64, f64), i32) = d("(1+2j, 3)");
+    assert_eq!(v, ((1.0, 2.0), 3));
+    let v: Value = d("(1+2j, 3)");
+    assert_eq!(v.to_string(), "[[1.0,2.0],3]");
+
+    assert!(crate::from_str::<(f64, f64)>("(1+2)").is_err());
+    assert!(crate::from_str::<(f64, f64)>("complex(1, 2, 3)").is_err());
+}
+
+#[test]
+fn test_deserialize_bytearray() {
+    let v: ByteBuf = d("bytearray(b'a\\x00b')");
+    assert_eq!(v, b(b"a\0b"));
+
+    let v: Vec<ByteBuf> = d("[bytearray(), bytearray(b'x' b\"y\"), b'z', bytearray( b'' )]");
+    assert_eq!(v, [b(b""), b(b"xy"), b(b"z"), b(b"")]);
+
+    let v: crate::Value = d("{'k': bytearray(b'\\xff')}");
+    assert_eq!(
+        v,
+        crate::Value::Dict(vec![(
+            crate::Value::Str("k".to_string()),
+            crate::Value::Bytes(vec![0xff])
+        )])
+    );
+
+    assert!(crate::from_str::<ByteBuf>("bytearray(b'a'").is_err());
+    assert!(crate::from_str::<ByteBuf>("bytearray('a')").is_err());
+}
+
+#[test]
+fn test_deserialize_raw_bytes() {
+    // Escapes are decoded in bytes, but kept as-is in raw bytes.
+    let v: ByteBuf = d(r#"b"\t\r""#);
+    assert_eq!(v, b(b"\t\r"));
+    let v: ByteBuf = d(r#"rb"\t\r""#);
+    assert_eq!(v, b(b"\\t\\r"));
+    assert_eq!(v.len(), 4);
+    let v: ByteBuf = d(r#"rb"\n""#);
+    assert_eq!(v, b(b"\\n"));
+
+    // Any case and order of `b` and `r`.
+    for prefix in ["rb", "rB", "Rb", "RB", "br", "bR", "Br", "BR"] {
+        let v: ByteBuf = d(&format!(r#"{}"\n" {}'\x41'"#, prefix, prefix));
+        assert_eq!(v, b(b"\\n\\x41"), "prefix {}", prefix);
+        let v: ByteBuf = d(&format!("bytearray({}'\\n')", prefix));
+        assert_eq!(v, b(b"\\n"), "prefix {}", prefix);
+        let v: crate::Value = d(&format!("{}'\\n'", prefix));
+        assert_eq!(v, crate::Value::Bytes(b"\\n".to_vec()), "prefix {}", prefix);
+    }
+
+    let v: Vec<ByteBuf> = d(r#"[Rb'\x00', bR"\\", BR'\'', B"\n", br"a\
+b"]"#);
+    assert_eq!(
+        v,
+        [b(b"\\x00"), b(b"\\\\"), b(b"\\'"), b(b"\n"), b(b"a\\\nb")]
+    );
+
+    // Adjacent literals can mix raw and non-raw.
+    let v: ByteBuf = d(r#"(b'\t' rb'\t'
+                          b"\t")"#);
+    assert_eq!(v, b(b"\t\\t\t"));
+
+    let v: crate::Value = d(r"(rb'\d', Rb'x')");
+    assert_eq!(
+        v,
+        crate::Value::Tuple(vec![
+            crate::Value::Bytes(b"\\d".to_vec()),
+            crate::Value::Bytes(b"x".to_vec())
+        ])
+    );
+    let v: crate::Value = d(r"(rb'\d' b'x')");
+    assert_eq!(v, crate::Value::Bytes(b"\\dx".to_vec()));
+
+    assert!(crate::from_str::<ByteBuf>("rr'x'").is_err());
+    assert!(crate::from_str::<ByteBuf>("bb'x'").is_err());
+    assert!(crate::from_str::<ByteBuf>("r'x'").is_err());
+    assert!(crate::from_str::<ByteBuf>(r"rb'\'").is_err());
+}
+
+#[test]
+fn test_deserialize_complex_types() {
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Complex {
+        re: f64,
+        im: f64,
+    }
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Complex2(f64, f64);
+
+    let v: Vec<Complex> = d("[3+4j, (1.5-2j), 4j, complex(-1)]");
+    assert_eq!(
+        v,
+        [
+            Complex { re: 3.0, im: 4.0 },
+            Complex { re: 1.5, im: -2.0 },
+            Complex { re: 0.0, im: 4.0 },
+            Complex { re: -1.0, im: 0.0 },
+        ]
+    );
+    let v: Complex2 = d("(-0-2j)");
+    assert_eq!(v, Complex2(0.0, -2.0));
+
+    // Real numbers are promoted to complex.
+    let v: Vec<(f64, f64)> = d("[3, -1.5, 2e1]");
+    assert_eq!(v, [(3.0, 0.0), (-1.5, 0.0), (20.0, 0.0)]);
+    let v: Complex2 = d("7");
+    assert_eq!(v, Complex2(7.0, 0.0));
+    let v: (f32, f32) = d("1.5");
+    assert_eq!(v, (1.5, 0.0));
+
+    // But only into a float pair.
+    let e = crate::from_str::<(i32, String)>("3").unwrap_err();
+    assert!(e.to_string().starts_with("expect tuple, got int"), "{}", e);
+    let e = crate::from_str::<(f64, i32)>("1.5").unwrap_err();
+    assert!(
+        e.to_string().starts_with("expect tuple, got float"),
+        "{}",
+        e
+    );
+
+    // Structs still read dicts.
+    let v: Complex = d("{'re': 1, 'im': 2}");
+    assert_eq!(v, Complex { re: 1.0, im: 2.0 });
+
+    assert!(crate::from_str::<(f64, f64, f64)>("1+2j").is_err());
+}
+
+#[test]
+fn test_deserialize_stream() {
+    use crate::de::Deserializer;
+
+    let input = "1\n-2 3 # three\n[4, (5,)]\n\n# end\n";
+    let values: Vec<crate::Value> = Deserializer::new(input.as_bytes())
+        .into_iter()
+        .collect::<crate::Result<_>>()
+        .unwrap();
+    assert_eq!(s(&values), "[1,-2,3,[4,(5,)]]");
+
+    // Typed.
+    let input = "(1, 'a')\n(2, 'b')";
+    let values: Vec<(i32, String)> = Deserializer::new(input.as_bytes())
+        .into_iter()
+        .collect::<crate::Result<_>>()
+        .unwrap();
+    assert_eq!(values, [(1, "a".to_string()), (2, "b".to_string())]);
+
+    // Empty input.
+    for input in ["", "  \n# nothing\n"] {
+        let mut iter = Deserializer::new(input.as_bytes()).into_iter::<i32>();
+        assert!(iter.next().is_none());
+    }
+
+    // Stop after an error.
+    let mut iter = Deserializer::new(&b"1 [2, x] 3"[..]).into_iter::<crate::Value>();
+    assert_eq!(iter.next().unwrap().unwrap(), crate::Value::Int(1));
+    let err = iter.next().unwrap().unwrap_err();
+    assert!(matches!(err, crate::Error::At { offset: 6, .. }));
+    assert!(iter.next().is_none());
+}
+
+#[test]
+fn test_deserialize_peek_kind() {
+    use crate::de::Deserializer;
+    use crate::de::ValueKind;
+
+    let kind = |s: &str| Deserializer::from_slice(s.as_bytes()).peek_kind().unwrap();
+    assert_eq!(kind("[1]"), ValueKind::List);
+    assert_eq!(kind("{1: 2}"), ValueKind::Map);
+    assert_eq!(kind("{}"), ValueKind::Map);
+    assert_eq!(kind("OrderedDict()"), ValueKind::Map);
+    assert_eq!(kind("{1, 2}"), ValueKind::Set);
+    assert_eq!(kind("frozenset()"), ValueKind::Set);
+    assert_eq!(kind("(1, 2)"), ValueKind::Tuple);
+    assert_eq!(kind("('a' 'b')"), ValueKind::Str);
+    assert_eq!(kind("u'a'"), ValueKind::Str);
+    assert_eq!(kind("rb'a'"), ValueKind::Bytes);
+    assert_eq!(kind("True"), ValueKind::Bool);
+    assert_eq!(kind("-1"), ValueKind::SignedInt);
+    assert_eq!(kind("0xff"), ValueKind::UnsignedInt);
+    assert_eq!(kind("1e3"), ValueKind::Float);
+    assert_eq!(kind("nan"), ValueKind::Float);
+    assert_eq!(kind("1+2j"), ValueKind::Complex);
+    assert_eq!(kind("None"), ValueKind::None);
+    assert_eq!(kind("..."), ValueKind::Ellipsis);
+    assert_eq!(kind(" # c\n"), ValueKind::Eof);
+    assert_eq!(kind("foo"), ValueKind::Unknown);
+
+    // Pick the type by the kind, in a heterogeneous stream.
+    let mut de = Deserializer::new(&b"'a' 1 [2, 3] # c\n{'b': 4}"[..]);
+    let mut out = Vec::new();
+    loop {
+        let item = match de.peek_kind().unwrap() {
+            ValueKind::Str => String::deserialize(&mut de).unwrap(),
+            ValueKind::UnsignedInt => u32::deserialize(&mut de).unwrap().to_string(),
+            ValueKind::List => format!("{:?}", Vec::<u8>::deserialize(&mut de).unwrap()),
+            ValueKind::Map => format!(
+                "{:?}",
+                BTreeMap::<String, u8>::deserialize(&mut de).unwrap()
+            ),
+            ValueKind::Eof => break,
+            kind => panic!("unexpected kind: {:?}", kind),
+        };
+        out.push(item);
+    }
+    assert_eq!(out, ["a", "1", "[2, 3]", "{\"b\": 4}"]);
+    assert_eq!(de.peek_kind().unwrap(), ValueKind::Eof);
+}
+
+#[test]
+fn test_deserialize_trailing_data() {
+    // The lenient functions stop after the value.
+    assert_eq!(d::<i32>("1 2"), 1);
+    assert_eq!(d::<Vec<i32>>("[1] x"), [1]);
+
+    let strict = |s: &str| crate::from_str_strict::<Vec<i32>>(s);
+    assert_eq!(strict("[1, 2]").unwrap(), [1, 2]);
+    assert_eq!(strict(" [1]  # comment\n\n").unwrap(), [1]);
+    assert_eq!(strict("[1]\n# a\n# b").unwrap(), [1]);
+    assert_eq!(crate::from_slice_strict::<i32>(b"3\r\n").unwrap(), 3);
+
+    for s in ["[1] 2", "[1]]", "[1],", "[1]\n# a\nx"] {
+        let err = strict(s).unwrap_err();
+        assert!(
+            err.to_string()
+                .starts_with("trailing data after the value at line "),
+            "{}",
+            s
+        );
+        assert!(matches!(err, crate::Error::At { .. }), "{}", s);
+        assert!(err.display_with_source(s).ends_with('^'), "{}", s);
+    }
+    let err = strict("[1]  2").unwrap_err();
+    assert!(matches!(err, crate::Error::At { offset: 5, .. }));
+    assert!(crate::from_str_strict::<i32>("1 2").is_err());
+
+    // Errors in the value are reported as usual.
+    assert!(strict("[1").is_err());
+}
+
+#[test]
+fn test_deserialize_brace_escapes() {
+    use crate::de::Deserializer;
+
+    // Braces are ordinary characters.
+    assert_eq!(d::<String>("'{{x}} {y}'"), "{{x}} {y}");
+
+    // `\{` is an unknown escape by default.
+    let err = crate::from_str::<String>(r"'\{x\}'").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        r"cannot parse string: unknown escape: \{ at line 1 column 3"
+    );
+
+    let lenient = |s: &str| {
+        let mut de = Deserializer::new(s.as_bytes()).lenient_brace_escapes(true);
+        Vec::<String>::deserialize(&mut de)
+    };
+    assert_eq!(
+        lenient(r#"['\{x\}', "\{\{", '{}' '\}', '\\{']"#).unwrap(),
+        ["{x}", "{{", "{}}", "\\{"]
+    );
+    assert!(lenient(r"['\[']").is_err());
+}
+
+#[test]
+fn test_deserialize_max_depth() {
+    use crate::de::Deserializer;
+
+    fn nest(open: &str, inner: &str, close: &str, n: usize) -> String {
+        format!("{}{}{}", open.repeat(n), inner, close.repeat(n))
+    }
+    fn is_depth_error(r: crate::Result<impl Sized>, depth: usize) -> bool {
+        match r {
+            Err(crate::Error::At { error, .. }) => {
+                matches!(*error, crate::Error::DepthLimitExceeded(d) if d == depth)
+            }
+            _ => false,
+        }
+    }
+
+    // The default limit is 128.
+    assert!(crate::from_str::<crate::Value>(&nest("[", "", "]", 128)).is_ok());
+    assert!(is_depth_error(
+        crate::from_str::<crate::Value>(&nest("[", "", "]", 129)),
+        128
+    ));
+
+    // Deep input does not overflow the stack.
+    for (open, inner, close) in [
+        ("[", "1", "]"),
+        ("(", "1", ",)"),
+        ("{1: ", "1", "}"),
+        ("{", "1", "}"),
+        ("frozenset(", "{1}", ")"),
+        ("OrderedDict({1: ", "1", "})"),
+        ("bytearray(", "b''", ")"),
+    ] {
+        let input = nest(open, inner, close, 100_000);
+        let r = crate::from_str::<crate::Value>(&input);
+        assert!(is_depth_error(r, 128), "{}", open);
+    }
+    assert!(is_depth_error(
+        crate::from_str::<ByteBuf>(&nest("(", "b''", ")", 200)),
+        128
+    ));
+    let v: String = d(&nest("(", "'a'", ")", 100_000));
+    assert_eq!(v, "a");
+
+    let limited = |s: &str, depth| {
+        let mut de = Deserializer::new(s.as_bytes()).max_depth(depth);
+        crate::Value::deserialize(&mut de).map_err(|e| crate::Error::At {
+            offset: 0,
+            line: 1,
+            column: 1,
+            error: Box::new(e),
+        })
+    };
+    assert!(limited("[[1]]", 2).is_ok());
+    assert!(is_depth_error(limited("[[[1]]]", 2), 2));
+    assert!(is_depth_error(limited("[frozenset({1})]", 2), 2));
+    assert!(limited("1", 0).is_ok());
+    assert!(is_depth_error(limited("[]", 0), 0));
+}
+
+#[test]
+fn test_deserialize_strict_numbers() {
+    use crate::de::Deserializer;
+
+    fn strict(s: &str) -> crate::Result<crate::Value> {
+        let mut de = Deserializer::new(s.as_bytes()).strict_numbers(true);
+        let value = serde::Deserialize::deserialize(&mut de)?;
+        de.end()?;
+        Ok(value)
+    }
+
+    let valid = [
+        "0",
+        "00",
+        "0_0",
+        "10",
+        "1_000",
+        "-1",
+        "0x_ff",
+        "0XfF",
+        "0o17",
+        "0b1_0",
+        "1.5",
+        "1.",
+        ".5",
+        "1e5",
+        "1E-5",
+        "1_0.0_1e+1_0",
+        "01.5",
+        "01e1",
+        "0e0",
+        "inf",
+    ];
+    for s in valid {
+        assert_eq!(
+            strict(s).unwrap(),
+            crate::from_str::<crate::Value>(s).unwrap(),
+            "{}",
+            s
+        );
+    }
+    assert_eq!(
+        strict("(1+2j)").unwrap(),
+        crate::from_str::<crate::Value>("(1+2j)").unwrap()
+    );
+    assert_eq!(
+        strict("[1-2j]").unwrap(),
+        crate::from_str::<crate::Value>("[1-2j]").unwrap()
+    );
+
+    let invalid = [
+        "007", "1__0", "1_", "_1", "+1", "0x", "0x_", "0x__1", "1e", "1e_1", ".e1", "1._5", "1_.5",
+        "0b2", "0o8", "1.2.3",
+    ];
+    for s in invalid {
+        assert!(strict(s).is_err(), "{}", s);
+    }
+    assert_eq!(
+        strict("[1__0]").unwrap_err().to_string(),
+        "invalid Python number: \"1__0\""
+    );
+
+    // The default mode is more lenient.
+    for s in ["007", "1__0", "+1"] {
+        assert!(crate::from_str::<crate::Value>(s).is_ok(), "{}", s);
+    }
+}
+
+#[test]
+fn test_deserialize_config() {
+    use crate::de::Config;
+
+    // Trailing data is allowed by default, like `from_str`.
+    assert_eq!(
+        crate::from_str_with_config::<i32>("1 2", Config::new()).unwrap(),
+        1
+    );
+    let strict = Config::new().allow_trailing_data(false);
+    assert_eq!(
+        crate::from_str_with_config::<i32>("1 2", strict.clone())
+            .unwrap_err()
+            .to_string(),
+        crate::from_str_strict::<i32>("1 2")
+            .unwrap_err()
+            .to_string(),
+    );
+    assert_eq!(
+        crate::from_reader_with_config::<_, i32>(&b"1 # x"[..], strict).unwrap(),
+        1
+    );
+
+    // Options that also have `Deserializer` setters.
+    let config = Config::new().stringify_keys(true).max_depth(2);
+    let v: BTreeMap<String, Vec<i32>> =
+        crate::from_str_with_config("{1: [2]}", config.clone()).unwrap();
+    assert_eq!(format!("{:?}", v), r#"{"1": [2]}"#);
+    assert!(crate::from_str_with_config::<crate::Value>("[[[]]]", config).is_err());
+}
+
+#[test]
+fn test_deserialize_strict_bool() {
+    use crate::de::Config;
+    use crate::Error;
+
+    fn strict(s: &str) -> crate::Result<Vec<bool>> {
+        crate::from_str_with_config(s, Config::new().strict_bool(true))
+    }
+
+    let all = "[True, False, true, false, 1, 0]";
+    assert_eq!(d::<Vec<bool>>(all), [true, false, true, false, true, false]);
+    assert_eq!(
+        strict("[True, False, true, false]").unwrap(),
+        [true, false, true, false]
+    );
+    for s in ["[1]", "[0]", "[True, 0]"] {
+        match strict(s) {
+            Err(Error::At { error, .. }) => match *error {
+                Error::TypeMismatch("bool", got) => assert_eq!(got, "int", "{}", s),
+                e => panic!("unexpected error for {}: {}", s, e),
+            },
+            r => panic!("unexpected result for {}: {:?}", s, r),
+        }
+    }
+    assert_eq!(
+        strict("[1]").unwrap_err().to_string(),
+        "expect bool, got int at line 1 column 2"
+    );
+
+    // Other types are unaffected.
+    let v: (bool, i32, crate::Value) =
+        crate::from_str_with_config("(True, 1, [true, 0])", Config::new().strict_bool(true))
+            .unwrap();
+    assert_eq!(
+        v.2,
+        crate::Value::List(vec![crate::Value::Bool(true), crate::Value::Int(0)])
+    );
+}
+
+#[test]
+fn test_deserialize_python_only() {
+    use crate::de::Config;
+    use crate::Error;
+
+    let c = || Config::new().python_only(true);
+    let v: Vec<Option<bool>> =
+        crate::from_str_with_config("[None, True, False, 1, 0]", c()).unwrap();
+    assert_eq!(v, [None, Some(true), Some(false), Some(true), Some(false)]);
+
+    // JSON spellings are accepted by default.
+    let json = "[null, true, false]";
+    assert_eq!(
+        d::<Vec<Option<bool>>>(json),
+        [None, Some(true), Some(false)]
+    );
+    assert_eq!(
+        d::<crate::Value>(json),
+        crate::Value::List(vec![
+            crate::Value::None,
+            crate::Value::Bool(true),
+            crate::Value::Bool(false)
+        ])
+    );
+
+    let error = |s: &str| match crate::from_str_with_config::<Option<bool>>(s, c()) {
+        Err(Error::At { error, .. }) => *error,
+        r => panic!("unexpected result for {}: {:?}", s, r),
+    };
+    assert!(matches!(error("null"), Error::TypeMismatch("None", got) if got == "null"));
+    assert!(matches!(error("true"), Error::UnknownType("bool", _)));
+    assert!(matches!(error("false"), Error::UnknownType("bool", _)));
+    for s in ["null", "true", "false", "[null]"] {
+        assert!(
+            crate::from_str_with_config::<crate::Value>(s, c()).is_err(),
+            "{}",
+            s
+        );
+    }
+
+    // With `strict_bool`, only `True` and `False` are bool.
+    let c = || c().strict_bool(true);
+    assert!(crate::from_str_with_config::<bool>("True", c()).unwrap());
+    for s in ["true", "1"] {
+        assert!(
+            crate::from_str_with_config::<bool>(s, c()).is_err(),
+            "{}",
+            s
+        );
+    }
+
+    // The serializer writes the Python spellings.
+    let v = vec![None, Some(true), Some(false)];
+    assert_eq!(
+        crate::from_str_with_config::<Vec<Option<bool>>>(&s(&v), c()).unwrap(),
+        v
+    );
+}
+
+#[test]
+fn test_deserialize_reject_nan_keys() {
+    use crate::de::Config;
+
+    let input = "{nan: 1, 2.5: 2}";
+    let c = || Config::new().reject_nan_keys(true);
+    let e = |input, config| {
+        crate::from_str_with_config::<crate::Value>(input, config)
+            .unwrap_err()
+            .to_string()
+    };
+
+    // By default, nan keys are read.
+    let v: crate::Value = d(input);
+    assert!(matches!(v, crate::Value::Dict(ref items) if items.len() == 2));
+
+    assert_eq!(e(input, c()), "nan cannot be a dict key at line 1 column 2");
+    assert_eq!(
+        e("{1: 1, -nan: 2}", c()),
+        "nan cannot be a dict key at line 1 column 8"
+    );
+    assert_eq!(
+        e("OrderedDict([(+nan, 1)])", c()),
+        "nan cannot be a dict key at line 1 column 15"
+    );
+    let err = crate::from_str_with_config::<BTreeMap<String, f64>>("{ nan: 0}", c()).unwrap_err();
+    assert!(
+        matches!(err, crate::Error::At { error, .. } if matches!(*error, crate::Error::NaNKey))
+    );
+
+    // Other keys, and nan values, are unaffected.
+    let v: crate::Value =
+        crate::from_str_with_config("{inf: nan, 'nan': 1, (nan,): 2}", c()).unwrap();
+    assert!(matches!(v, crate::Value::Dict(ref items) if items.len() == 3));
+}
+
+#[test]
+fn test_deserialize_max_string_len() {
+    use crate::de::Config;
+
+    let c = || Config::new().max_string_len(Some(4)).max_bytes_len(Some(3));
+    let str_ = |input| crate::from_str_with_config::<String>(input, c());
+    let bytes = |input| crate::from_str_with_config::<ByteBuf>(input, c());
+    let es = |input| str_(input).unwrap_err().to_string();
+    let eb = |input| bytes(input).unwrap_err().to_string();
+
+    // By default, there is no limit.
+    assert_eq!(
+        d::<String>(&format!("'{}'", "a".repeat(10000))).len(),
+        10000
+    );
+
+    assert_eq!(str_("'abcd'").unwrap(), "abcd");
+    assert_eq!(str_("'a\\x62' \"cd\"").unwrap(), "abcd");
+    assert_eq!(bytes("b'abc'").unwrap(), b"abc"[..]);
+    assert_eq!(bytes("b'a' rb'\\c'").unwrap(), b"a\\c"[..]);
+
+    assert_eq!(
+        es("'abcde'"),
+        "str length exceeds limit (4) at line 1 column 6"
+    );
+    // Borrowed.
+    let r = crate::from_slice_with_config::<&str>(b"'abcde'", c());
+    assert_eq!(
+        r.unwrap_err().to_string(),
+        "str length exceeds limit (4) at line 1 column 1"
+    );
+    // Checked in bytes of UTF-8, after escapes.
+    assert_eq!(
+        es("'\\xe9\\xe9\\xe9'"),
+        "str length exceeds limit (4) at line 1 column 13"
+    );
+    // Adjacent literals.
+    assert_eq!(
+        es("'abc' 'de'"),
+        "str length exceeds limit (4) at line 1 column 11"
+    );
+    // An unterminated literal errors at the limit, not at the end.
+    let input = format!("'{}", "a".repeat(10000));
+    assert_eq!(
+        es(&input),
+        "str length exceeds limit (4) at line 1 column 6"
+    );
+
+    assert_eq!(
+        eb("b'abcd'"),
+        "bytes length exceeds limit (3) at line 1 column 6"
+    );
+    assert_eq!(
+        eb("bytearray(b'abcd')"),
+        "bytes length exceeds limit (3) at line 1 column 16"
+    );
+    assert_eq!(
+        eb("b'ab' b'cd'"),
+        "bytes length exceeds limit (3) at line 1 column 12"
+    );
+    let err = bytes("b'abcd'").unwrap_err();
+    assert!(matches!(
+        err,
+        crate::Error::At { error, .. } if matches!(*error, crate::Error::LengthLimitExceeded("bytes", 3))
+    ));
+}
+
+#[test]
+fn test_deserialize_bom() {
+    use crate::de::Config;
+    use crate::de::Deserializer;
+
+    assert_eq!(d::<Vec<i32>>("\u{feff}[1, 2]"), [1, 2]);
+    let bytes = b"\xef\xbb\xbf# comment\n[1]";
+    assert_eq!(crate::from_slice::<Vec<i32>>(bytes).unwrap(), [1]);
+    assert_eq!(crate::from_reader::<_, Vec<i32>>(&bytes[..]).unwrap(), [1]);
+    assert_eq!(
+        crate::from_str_tuple::<Vec<i32>>("\u{feff}1, 2").unwrap(),
+        [1, 2]
+    );
+    let values: Vec<i32> = Deserializer::new(&b"\xef\xbb\xbf1\n2"[..])
+        .into_iter()
+        .collect::<crate::Result<_>>()
+        .unwrap();
+    assert_eq!(values, [1, 2]);
+
+    // Only at the start.
+    assert!(crate::from_str::<Vec<String>>("[\u{feff}'a']").is_err());
+    assert_eq!(d::<Vec<String>>("['\u{feff}']"), ["\u{feff}"]);
+
+    // Kept, and rejected, if disabled.
+    let c = Config::new().skip_utf8_bom(false);
+    assert!(crate::from_slice_with_config::<Vec<i32>>(bytes, c).is_err());
+
+    for (bytes, name) in [
+        (&b"\xff\xfe[\x001\x00]\x00"[..], "UTF-16 LE"),
+        (b"\xfe\xff\x00[\x001\x00]", "UTF-16 BE"),
+        (b"\xff\xfe\x00\x00[\x00\x00\x00", "UTF-32 LE"),
+        (b"\x00\x00\xfe\xff\x00\x00\x00[", "UTF-32 BE"),
+    ] {
+        let error = crate::from_slice::<Vec<i32>>(bytes).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "unsupported encoding {}, detected by its byte order mark; only UTF-8 is supported at line 1 column 1",
+                name
+            )
+        );
+    }
+}
+
+#[test]
+fn test_ellipsis() {
+    use crate::Ellipsis;
+
+    assert_eq!(s(&Ellipsis), "...");
+    assert_eq!(s(&vec![Some(Ellipsis), None]), "[...,None]");
+    assert_eq!(serde_json::to_string(&Ellipsis).unwrap(), "null");
+
+    assert_eq!(d::<Ellipsis>("..."), Ellipsis);
+    assert!(crate::from_str::<Ellipsis>("()").is_err());
+    assert!(crate::from_str::<Ellipsis>("....").is_err());
+
+    // Unit accepts `...` too.
+    assert_eq!(d::<(i32, (), f64)>("(1, ..., .5)"), (1, (), 0.5));
+    assert_eq!(d::<Vec<f64>>("[.5, 1.]"), [0.5, 1.0]);
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    #[serde(untagged)]
+    enum Item {
+        Int(i64),
+        Str(String),
+        Ellipsis(Ellipsis),
+    }
+    assert_eq!(
+        d::<Vec<Item>>("[1, ..., 'a', ...]"),
+        [
+            Item::Int(1),
+            Item::Ellipsis(Ellipsis),
+            Item::Str("a".to_string()),
+            Item::Ellipsis(Ellipsis)
+        ]
+    );
+    assert_eq!(
+        d::<Vec<crate::Value>>("[1, ..., 2]"),
+        [
+            crate::Value::Int(1),
+            crate::Value::Ellipsis,
+            crate::Value::Int(2)
+        ]
+    );
+    assert_eq!(d::<Value>("{'a': [...]}").to_string(), r#"{"a":[null]}"#);
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct S {
+        a: Ellipsis,
+        b: Vec<Ellipsis>,
+    }
+    let v = S {
+        a: Ellipsis,
+        b: vec![Ellipsis; 2],
+    };
+    assert_eq!(s(&v), r#"{"a":...,"b":[...,...]}"#);
+    assert_eq!(d::<S>(&p(&v)), v);
+}
+
+#[test]
+fn test_deserialize_bare_tuple() {
+    assert_eq!(
+        crate::from_str_tuple::<Vec<i32>>("1, 2, 3").unwrap(),
+        [1, 2, 3]
+    );
+    assert_eq!(
+        crate::from_str_tuple::<Vec<i32>>(" 1,\n2, # c\n3, ").unwrap(),
+        [1, 2, 3]
+    );
+    assert_eq!(crate::from_str_tuple::<Vec<i32>>("1").unwrap(), [1]);
+    assert_eq!(
+        crate::from_str_tuple::<Vec<i32>>("").unwrap(),
+        Vec::<i32>::new()
+    );
+    assert_eq!(
+        crate::from_str_tuple::<(i32, i32, i32)>("1, 2, 3").unwrap(),
+        (1, 2, 3)
+    );
+    assert_eq!(
+        crate::from_str_tuple::<(i32, (i32, i32), [i32; 1])>("1, (2, 3), [4]").unwrap(),
+        (1, (2, 3), [4])
+    );
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct T(i32, String);
+    assert_eq!(
+        crate::from_str_tuple::<T>("1, 'a'").unwrap(),
+        T(1, "a".to_string())
+    );
+
+    let v: Vec<crate::Value> = crate::from_str_tuple("1, 'a', True").unwrap();
+    assert_eq!(
+        v,
+        [
+            crate::Value::Int(1),
+            crate::Value::Str("a".to_string()),
+            crate::Value::Bool(true)
+        ]
+    );
+    let v: crate::Value = crate::from_str_tuple("1, [2]").unwrap();
+    assert_eq!(
+        v,
+        crate::Value::Tuple(vec![
+            crate::Value::Int(1),
+            crate::Value::List(vec![crate::Value::Int(2)])
+        ])
+    );
+
+    for s in ["1 2", "1,, 2", "(1, 2", "1, 2)", "1, 2\0, 3"] {
+        assert!(crate::from_str_tuple::<Vec<i32>>(s).is_err(), "{}", s);
+    }
+}
+
+#[test]
+fn test_deserialize_dataclass_repr() {
+    use crate::de::Deserializer;
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Point {
+        x: i32,
+        y: i32,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Config {
+        name: String,
+        size: u8,
+        origin: Option<Point>,
+    }
+
+    fn dataclass<T: de::DeserializeOwned>(s: &str) -> crate::Result<T> {
+        let mut de = Deserializer::new(s.as_bytes()).dataclass_repr(true);
+        T::deserialize(&mut de)
+    }
+
+    let point = |x, y| Point { x, y };
+    assert_eq!(dataclass::<Point>("Point(x=1, y=2)").unwrap(), point(1, 2));
+    assert_eq!(dataclass::<Point>("P (y=2,x=1 ,)").unwrap(), point(1, 2));
+    assert_eq!(
+        dataclass::<Point>("m.Point(\n  x=1, # c\n  y=2)").unwrap(),
+        point(1, 2)
+    );
+    assert_eq!(dataclass::<Point>("{'x': 1, 'y': 2}").unwrap(), point(1, 2));
+    assert_eq!(
+        dataclass::<Config>("Config(size=3, origin=None, name='a')").unwrap(),
+        Config {
+            name: "a".to_string(),
+            size: 3,
+            origin: None,
+        }
+    );
+    assert_eq!(
+        dataclass::<Vec<Config>>("[Config(name='b', size=4, origin=Point(x=-1, y=0))]").unwrap(),
+        [Config {
+            name: "b".to_string(),
+            size: 4,
+            origin: Some(point(-1, 0)),
+        }]
+    );
+
+    assert!(dataclass::<Point>("Point(x=1)").is_err());
+    assert!(dataclass::<Point>("Point(1, 2)").is_err());
+    assert!(dataclass::<Point>("Point(x=1, y=2").is_err());
+    assert!(crate::from_str::<Point>("Point(x=1, y=2)").is_err());
+}
+
+#[test]
+fn test_deserialize_stringify_keys() {
+    use crate::de::Deserializer;
+
+    fn stringify<T: de::DeserializeOwned>(s: &str) -> crate::Result<T> {
+        let mut de = Deserializer::new(s.as_bytes()).stringify_keys(true);
+        T::deserialize(&mut de)
+    }
+
+    let input = "{1: 2, (3, 4): 5, 'a': {None: [{True: 1.5}]}}";
+    assert!(crate::from_str::<Value>(input).is_err());
+    let v: Value = stringify(input).unwrap();
+    assert_eq!(
+        v.to_string(),
+        r#"{"(3,4)":5,"1":2,"a":{"None":[{"True":1.5}]}}"#
+    );
+
+    let v: BTreeMap<String, i32> = stringify("OrderedDict([(1.5, 1), ('b', 2)])").unwrap();
+    assert_eq!(format!("{:?}", v), r#"{"1.5": 1, "b": 2}"#);
+
+    // Keys are unchanged if the target does not expect str.
+    let v: BTreeMap<(i32, i32), i32> = stringify("{(1, 2): 3}").unwrap();
+    assert_eq!(v[&(1, 2)], 3);
+    let v: crate::Value = stringify("{1: 2}").unwrap();
+    assert_eq!(v.get(1), Some(&crate::Value::Int(2)));
+
+    // Values are unchanged.
+    assert!(stringify::<BTreeMap<String, String>>("{1: 2}").is_err());
+}
+
+#[test]
+fn test_deserialize_borrowed_str() {
+    use std::borrow::Cow;
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct S<'a> {
+        a: &'a str,
+        #[serde(borrow)]
+        b: Cow<'a, str>,
+    }
+
+    let input = r#"{'a': "x y", 'b': ""}"#;
+    let v: S = crate::from_str(input).unwrap();
+    assert_eq!(v.a, "x y");
+    assert!(matches!(v.b, Cow::Borrowed("")));
+
+    // Escapes, triple quotes, parentheses and concatenation need an owned str.
+    for input in [r"'\n'", r#""""e""""#, "('f')", "'c' 'd'", "'c'\n# x\n'd'"] {
+        let input = format!("{{'a': '', 'b': {}}}", input);
+        let v: S = crate::from_str(&input).unwrap();
+        assert!(matches!(v.b, Cow::Owned(_)), "{}", input);
+    }
+    let v: Vec<&str> = crate::from_str("['a', \"b\" ,'' # c\n]").unwrap();
+    assert_eq!(v, ["a", "b", ""]);
+
+    // The borrowed str points into the input.
+    let input = "[' a', 'b']";
+    let v: Vec<&str> = crate::from_str(input).unwrap();
+    assert_eq!(v[1].as_ptr(), input[8..].as_ptr());
+
+    // Escapes need an owned str.
+    let err = crate::from_str::<&str>(r"'a\tb'").unwrap_err();
+    assert!(err.to_string().contains("borrowed"), "{}", err);
+    assert_eq!(crate::from_slice::<&str>(b" 'ab' ").unwrap(), "ab");
+    assert!(crate::from_slice::<&str>(b"'\xff'").is_err());
+
+    // Readers cannot lend their input.
+    let mut de = crate::de::Deserializer::new(&b"'a'"[..]);
+    assert!(<&str>::deserialize(&mut de).is_err());
+    let mut de = crate::de::Deserializer::from_slice(b"'a'");
+    assert_eq!(<&str>::deserialize(&mut de).unwrap(), "a");
+}
+
+#[test]
+fn test_deserialize_adjacent_literals() {
+    let v: String = d(r#""a" 'b'"c""#);
+    assert_eq!(v, "abc");
+
+    let v: Vec<String> = d(r#"[
+        "foo "  # comment
+        'bar'
+        "", 'x' "y",
+        "z"]"#);
+    assert_eq!(v, ["foo bar", "xy", "z"]);
+
+    let v: BTreeMap<String, (ByteBuf, String)> = d(r#"{"a" "b": (b"1" b'2' b"", "c" "d")}"#);
+    assert_eq!(format!("{:?}", v), r#"{"ab": ([49, 50], "cd")}"#);
+
+    // str and bytes cannot be mixed.
+    assert!(crate::from_str::<Vec<String>>(r#"["a" b"b"]"#).is_err());
+    assert!(crate::from_str::<Vec<ByteBuf>>(r#"[b"a" "b"]"#).is_err());
+
+    // Literals with unsupported prefixes are not silently dropped, whether
+    // the str is owned, borrowed, or bytes.
+    let e = |r: crate::Result<()>| r.unwrap_err().to_string();
+    let message = r#"cannot parse string: adjacent literal with unsupported prefix "r""#;
+    let at = |column| format!("{} at line 1 column {}", message, column);
+    assert_eq!(e(crate::from_str::<String>("'a' r'b'").map(drop)), at(5));
+    assert_eq!(e(crate::from_str::<&str>("'a' r'b'").map(drop)), at(5));
+    assert_eq!(e(crate::from_str::<&str>("'a' 'b' r'c'").map(drop)), at(9));
+    assert_eq!(e(crate::from_str::<ByteBuf>("b'a' r'b'").map(drop)), at(6));
+    assert!(crate::from_str::<Value>(r#"['a' f"{b}"]"#).is_err());
+}
+
+#[test]
+fn test_deserialize_u_prefix() {
+    let v: String = d("u'abc'");
+    assert_eq!(v, "abc");
+    let v: String = d(r#"U"é" u'''x''' 'y'"#);
+    assert_eq!(v, "\u{e9}xy");
+
+    // Borrowed, and detected by `Value`.
+    assert_eq!(crate::from_str::<&str>(" u'ab' ").unwrap(), "ab");
+    let v: Value = d(r#"{u"a": [u'b', (u'c' 'd'), (u'e',)], u'f': {u'g': U''}}"#);
+    assert_eq!(
+        v,
+        serde_json::json!({"a": ["b", "cd", ["e"]], "f": {"g": ""}})
+    );
+
+    // Not a prefix.
+    assert!(crate::from_str::<String>("uu'a'").is_err());
+    assert!(crate::from_str::<String>("ub'a'").is_err());
+    assert!(crate::from_str::<String>("u 'a'").is_err());
+    assert!(crate::from_str::<Value>("u").is_err());
+}
+
+#[test]
+fn test_deserialize_unsupported_expression() {
+    let e = |input| crate::from_str::<Value>(input).unwrap_err().to_string();
+    assert_eq!(
+        e(r#"f"{x}""#),
+        "f-string is not a literal and cannot be evaluated at line 1 column 1"
+    );
+    assert_eq!(
+        e("[1, Rf'{x}']"),
+        "f-string is not a literal and cannot be evaluated at line 1 column 5"
+    );
+    assert_eq!(
+        e("{'a': max(1, 2)}"),
+        "call to max() is not a literal and cannot be evaluated at line 1 column 7"
+    );
+    assert_eq!(
+        e("os.path.join ('a', 'b')"),
+        "call to os.path.join() is not a literal and cannot be evaluated at line 1 column 1"
+    );
+
+    // Also reported when a concrete type is expected.
+    let err = crate::from_str::<String>("F'x'").unwrap_err();
+    assert!(matches!(
+        err,
+        crate::Error::At { ref error, .. } if matches!(**error, crate::Error::UnsupportedExpression(_))
+    ));
+    assert_eq!(err.classify(), crate::Category::Syntax);
+    let err = crate::from_str::<BTreeMap<String, i32>>("{'a': len('x')}").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "call to len() is not a literal and cannot be evaluated at line 1 column 7"
+    );
+
+    // Other unknown input is reported as before.
+    assert_eq!(e("f"), r#"cannot auto-detect type: "f" at line 1 column 1"#);
+    assert_eq!(
+        e("ff'x'"),
+        r#"cannot auto-detect type: "ff'x'" at line 1 column 1"#
+    );
+    assert!(crate::from_str::<String>("x")
+        .unwrap_err()
+        .to_string()
+        .contains("unknown type"));
+    let v: Value = d("[False, frozenset(), OrderedDict([('a', 2)]), complex(1, 2)]");
+    assert_eq!(v.as_array().map(|v| v.len()), Some(4));
+}
+
+#[test]
+fn test_deserialize_unicode_name() {
+    let r = crate::from_str::<String>(r"'\N{GREEK SMALL LETTER ALPHA}\N{digit one}'");
+    if cfg!(feature = "unicode-names") {
+        assert_eq!(r.unwrap(), "α1");
+        assert!(crate::from_str::<String>(r"'\N{NOT A NAME}'").is_err());
+    } else {
+        let e = r.unwrap_err().to_string();
+        assert!(e.contains("GREEK SMALL LETTER ALPHA"));
+        assert!(e.contains("unicode-names"));
+    }
+    assert!(crate::from_str::<String>(r"'\N'").is_err());
+    assert!(crate::from_str::<String>(r"'\N{abc'").is_err());
+}
+
 #[test]
 fn test_deserialize_any() {
     let v: Value = d(r#"
@@ -250,19 +2743,290 @@ fn test_deserialize_list() {
     let v: Vec<String> = d(r#"['a',"","b"," ",]"#);
     assert_eq!(v, ["a", "", "b", " "]);
 
+    let v: (u8,) = d("(1,)");
+    assert_eq!(v, (1,));
+    assert_eq!(d::<(u8,)>(&s(&(1u8,))), (1,));
+
+    let v: (u8, u8) = d("[1, 2, ]");
+    assert_eq!(v, (1, 2));
+
     let v: Vec<Vec<u8>> = d(r#"[[3,4,],[5],[]]"#);
     assert_eq!(v, [vec![3, 4], vec![5], vec![]]);
 }
 
+#[test]
+fn test_deserialize_whitespace() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct S {
+        a: Vec<(i32, String)>,
+        b: BTreeMap<String, Option<f64>>,
+    }
+    let value = S {
+        a: vec![(1, "x\ty".to_string()), (2, "z".to_string())],
+        b: vec![("c".to_string(), Some(1.5)), ("d".to_string(), None)]
+            .into_iter()
+            .collect(),
+    };
+    // Pretty output re-indented with tabs, mixed with spaces.
+    let pretty = p(&value)
+        .replace("\n  ", "\n\t")
+        .replace("\n\t  ", "\n\t\t");
+    assert!(pretty.contains("\n\t"));
+    assert_eq!(d::<S>(&pretty), value);
+
+    // Tab, form feed, CR and LF are whitespace, as in Python's tokenizer.
+    let v: Vec<i32> = d("[\t1,\x0c2 ,\r\n3\t]\x0c");
+    assert_eq!(v, [1, 2, 3]);
+    let v: Value = d("{\t'a'\t:\x0c(\r1\n,)}");
+    assert_eq!(v.to_string(), r#"{"a":[1]}"#);
+    // Escaped whitespace in strings is preserved.
+    let v: String = d("'\t\\f'");
+    assert_eq!(v, "\t\x0c");
+
+    // Vertical tab is not whitespace in Python.
+    assert!(crate::from_str::<Vec<i32>>("[1,\x0b2]").is_err());
+}
+
+#[test]
+fn test_deserialize_pprint() {
+    // pprint.pformat(..., width=40)
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct S {
+        description: String,
+        empty: BTreeMap<String, i32>,
+        name: String,
+        nested: Nested,
+        numbers: Vec<i32>,
+        t: (i32,),
+    }
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Nested {
+        a: (i32, f64, Option<i32>),
+        b: Vec<bool>,
+        c: ByteBuf,
+    }
+    let v: S = d(r#"{'description': 'A long description '
+                'string that pprint '
+                'will wrap into '
+                'several adjacent '
+                'literals for sure',
+ 'empty': {},
+ 'name': 'serde_pyliteral',
+ 'nested': {'a': (1, 2.5, None),
+            'b': [True, False],
+            'c': b'bytes value that is '
+                 b'long enough to be wr'
+                 b'apped by pprint too,'
+                 b' hopefully yes'},
+ 'numbers': [0,
+             1,
+             2],
+ 't': (1,)}"#);
+    assert_eq!(
+        v.description,
+        "A long description string that pprint will wrap into several adjacent literals for sure"
+    );
+    assert_eq!(v.nested.a, (1, 2.5, None));
+    assert_eq!(
+        v.nested.c,
+        b(b"bytes value that is long enough to be wrapped by pprint too, hopefully yes")
+    );
+    assert_eq!(v.numbers, [0, 1, 2]);
+    assert_eq!(v.t, (1,));
+
+    // pprint.pformat(..., width=60, compact=True, sort_dicts=False),
+    // with trailing spaces added.
+    let v: Value = d("{'numbers': [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13,  \n             14, 15],\t\n 'empty': {}}  \n");
+    assert_eq!(
+        v.to_string(),
+        r#"{"empty":{},"numbers":[0,1,2,3,4,5,6,7,8,9,10,11,12,13,14,15]}"#
+    );
+
+    // Top-level long strings are wrapped in parentheses.
+    let s = "('a long string '\n 'that is going to '\n 'be wrapped by '\n 'pprint ok')";
+    let expected = "a long string that is going to be wrapped by pprint ok";
+    assert_eq!(d::<String>(s), expected);
+    assert_eq!(d::<Value>(s), Value::String(expected.to_string()));
+    assert_eq!(
+        d::<crate::Value>(s),
+        crate::Value::Str(expected.to_string())
+    );
+    let v: ByteBuf = d("(b'a'  # comment\n b\"b\")");
+    assert_eq!(v, b(b"ab"));
+    assert_eq!(
+        d::<crate::Value>("(b'a' b'()')"),
+        crate::Value::Bytes(b"a()".to_vec())
+    );
+
+    // But a tuple is still a tuple.
+    let s = "('a long string '\n 'that is going to '\n 'be wrapped by '\n 'pprint ok',)";
+    assert_eq!(d::<(String,)>(s), (expected.to_string(),));
+    assert_eq!(
+        d::<crate::Value>(s),
+        crate::Value::Tuple(vec![crate::Value::Str(expected.to_string())])
+    );
+    assert_eq!(
+        d::<crate::Value>("('a', 'b')"),
+        crate::Value::Tuple(vec![
+            crate::Value::Str("a".to_string()),
+            crate::Value::Str("b".to_string())
+        ])
+    );
+}
+
+#[test]
+fn test_deserialize_glued_comments() {
+    let v: Vec<i32> = d("[#c\n1,#c\n2#c\n,#c\n-3#c\n]#c");
+    assert_eq!(v, [1, 2, -3]);
+
+    let v: (f64, bool, String, ByteBuf, Option<u8>, ()) =
+        d("#c\n(#c\n1.5#c\n,#c\nTrue#c\n,#c\n'a'#c\n,#c\nb'b'#c\n,#c\nNone#c\n,#c\n()#c\n,#c\n)");
+    assert_eq!(v, (1.5, true, "a".to_string(), b(b"b"), None, ()));
+
+    let v: BTreeMap<String, Vec<u8>> = d("{#c\n'a'#c\n:#c\n[#c\n]#c\n,#c\n'b':[1]#c\n}");
+    assert_eq!(format!("{:?}", v), r#"{"a": [], "b": [1]}"#);
+
+    let v: Value = d("{#c\n'a'#c\n:#c\n(#c\n1#c\n,#c\n)#c\n}");
+    assert_eq!(v.to_string(), r#"{"a":[1]}"#);
+}
+
+#[test]
+fn test_deserialize_set() {
+    let v: BTreeSet<i32> = d("{3, 1, 2, 1,}");
+    assert_eq!(v.into_iter().collect::<Vec<_>>(), [1, 2, 3]);
+
+    let v: HashSet<String> = d("{'a', \"b\"}");
+    assert_eq!(v.len(), 2);
+    assert!(v.contains("a") && v.contains("b"));
+
+    let v: BTreeSet<(i32, i32)> = d("{(1, 2), (0, 1)}");
+    assert_eq!(d::<BTreeSet<(i32, i32)>>(&s(&v)), v);
+
+    let v: BTreeMap<String, BTreeSet<i32>> = d("{'a': {1}, 'b': {}}");
+    assert_eq!(format!("{:?}", v), r#"{"a": {1}, "b": {}}"#);
+
+    let v: Vec<i32> = d("{1, 2}");
+    assert_eq!(v, [1, 2]);
+
+    // Dicts are not sets.
+    assert!(crate::from_str::<BTreeSet<i32>>("{1: 2}").is_err());
+}
+
+#[test]
+fn test_deserialize_frozenset() {
+    let v: BTreeSet<i32> = d("frozenset({1, 2, 3})");
+    assert_eq!(v.into_iter().collect::<Vec<_>>(), [1, 2, 3]);
+
+    let v: Vec<i32> = d("frozenset()");
+    assert!(v.is_empty());
+
+    let v: (bool, BTreeSet<String>, bool, Vec<i32>, Vec<i32>) =
+        d("(False, frozenset({'a'}), false, frozenset([1]), set())");
+    assert_eq!(format!("{:?}", v), r#"(false, {"a"}, false, [1], [])"#);
+
+    let v: Value = d("[frozenset({1, 2}), frozenset(), set(), False]");
+    assert_eq!(v.to_string(), "[[1,2],[],[],false]");
+
+    assert!(crate::from_str::<Vec<i32>>("frozenset({1, 2}").is_err());
+    assert!(crate::from_str::<Vec<i32>>("frozenset").is_err());
+}
+
 #[test]
 fn test_deserialize_map() {
     let v: BTreeMap<u8, u8> = d("{1:2,3 : 4 }");
     assert_eq!(format!("{:?}", v), "{1: 2, 3: 4}");
 
+    let v: BTreeMap<bool, i32> = d("{True: 1, False: 0}");
+    assert_eq!(format!("{:?}", v), "{false: 0, true: 1}");
+
+    let v: BTreeMap<bool, String> = d("{ True :'a',False:'b' , }");
+    assert_eq!(format!("{:?}", v), r#"{false: "b", true: "a"}"#);
+
+    let v: BTreeMap<bool, BTreeMap<bool, bool>> = d("{False:{True:False}}");
+    assert_eq!(format!("{:?}", v), "{false: {true: false}}");
+
     let v: BTreeMap<Vec<bool>, String> = d("{(True,):'a', (False,True):'b'}");
     assert_eq!(format!("{:?}", v), "{[false, true]: \"b\", [true]: \"a\"}");
 }
 
+#[test]
+fn test_deserialize_ordered_dict() {
+    let v: BTreeMap<String, i32> = d("OrderedDict([('b', 2), ('a', 1)])");
+    assert_eq!(format!("{:?}", v), r#"{"a": 1, "b": 2}"#);
+    let v: BTreeMap<String, i32> = d("OrderedDict( [ ['a', 1] , ('b',2,), ] )");
+    assert_eq!(v.len(), 2);
+    let v: BTreeMap<String, i32> = d("OrderedDict((('a', 1),))");
+    assert_eq!(v.len(), 1);
+    let v: BTreeMap<String, i32> = d("OrderedDict({'a': 1})");
+    assert_eq!(v.len(), 1);
+    assert!(d::<BTreeMap<String, i32>>("OrderedDict()").is_empty());
+    assert!(d::<BTreeMap<String, i32>>("OrderedDict([])").is_empty());
+
+    // Order is kept by `Value`, and `deserialize_any` detects the map.
+    let v: crate::Value = d("[OrderedDict([('b', OrderedDict()), (1, None)]), 2]");
+    assert_eq!(s(&v), r#"[{"b":{},1:None},2]"#);
+    let v: Value = d("OrderedDict([('b', 1), ('a', [2])])");
+    assert_eq!(v.to_string(), r#"{"a":[2],"b":1}"#);
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct S {
+        a: i32,
+        b: Vec<(i32, i32)>,
+    }
+    let v: S = d("OrderedDict([('b', [(1, 2)]), ('a', 3)])");
+    assert_eq!(
+        v,
+        S {
+            a: 3,
+            b: vec![(1, 2)]
+        }
+    );
+
+    for s in [
+        "OrderedDict([('a',)])",
+        "OrderedDict([()])",
+        "OrderedDict([('a', 1, 2)])",
+        "OrderedDict([1])",
+        "OrderedDict([('a', 1)]",
+        "OrderedDict(1)",
+        "OrderedDict",
+    ] {
+        assert!(
+            crate::from_str::<BTreeMap<String, i32>>(s).is_err(),
+            "{}",
+            s
+        );
+    }
+}
+
+#[cfg(feature = "indexmap")]
+#[test]
+fn test_indexmap() {
+    use indexmap::IndexMap;
+
+    let input = "{'c': 1, 'a': 2, 'b': 3}";
+    let v: IndexMap<String, i32> = d(input);
+    assert_eq!(v.keys().collect::<Vec<_>>(), ["c", "a", "b"]);
+    assert_eq!(s(&v), r#"{"c":1,"a":2,"b":3}"#);
+    assert_eq!(d::<IndexMap<String, i32>>(&p(&v)), v);
+    let c = Config::new().pretty(true).pretty_width(Some(80));
+    assert_eq!(sc(&v, c), r#"{"c": 1, "a": 2, "b": 3}"#);
+
+    // Order of OrderedDict, and of nested maps, is kept too.
+    let v: IndexMap<String, IndexMap<i32, ()>> =
+        d("OrderedDict([('z', {3: (), 1: ()}), ('y', {})])");
+    assert_eq!(s(&v), r#"{"z":{3:(),1:()},"y":{}}"#);
+
+    let v: IndexMap<&str, i32> = vec![("b", 1), ("a", 2)].into_iter().collect();
+    assert_eq!(
+        crate::Value::from(v),
+        crate::Value::Dict(vec![
+            (crate::Value::from("b"), crate::Value::from(1)),
+            (crate::Value::from("a"), crate::Value::from(2)),
+        ])
+    );
+}
+
 #[test]
 fn test_deserialize_struct() {
     #[derive(Deserialize, Eq, PartialEq, Debug)]
@@ -307,12 +3071,339 @@ fn test_deserialize_struct() {
     assert_eq!(a, b);
 }
 
+#[test]
+fn test_roundtrip_struct() {
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct A {
+        a: i32,
+        b: bool,
+        c: String,
+        d: ByteBuf,
+        e: (u8, u8),
+        f: Option<B>,
+        g: C,
+        h: Vec<Option<D>>,
+        i: E,
+        mtime: (f64, i32),
+        j: Option<D>,
+        k: Option<Option<D>>,
+        l: Vec<F>,
+        m: (),
+    }
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct B(i32);
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct C(char, Option<bool>);
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct D;
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct E {
+        inner: u32,
+    }
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    enum F {
+        Unit,
+        NewType(Option<D>),
+        Tuple(D, Option<i32>),
+        Struct { x: Option<()> },
+    }
+
+    let a = A {
+        a: -10,
+        b: false,
+        c: "名称\u{2029}".to_string(),
+        d: b("数据".as_bytes()),
+        e: (2, 5),
+        f: Some(B(0)),
+        g: C(' ', None),
+        h: vec![Some(D), None, Some(D)],
+        i: E { inner: 1 },
+        mtime: (1635745617.7, -25200),
+        j: Some(D),
+        k: Some(None),
+        l: vec![
+            F::Unit,
+            F::NewType(Some(D)),
+            F::NewType(None),
+            F::Tuple(D, None),
+            F::Struct { x: Some(()) },
+            F::Struct { x: None },
+        ],
+        m: (),
+    };
+    let (compact, pretty) = (s(&a), p(&a));
+    // `Some(None)` is serialized as `None` and cannot be told apart.
+    let a = A { k: None, ..a };
+    assert_eq!(d::<A>(&compact), a);
+    assert_eq!(d::<A>(&pretty), a);
+}
+
+#[test]
+fn test_roundtrip_serde_attributes() {
+    use std::fmt::Debug;
+
+    fn check<T: Serialize + de::DeserializeOwned + PartialEq + Debug>(v: &T) {
+        for text in [s(v), p(v), sc(v, Config::default().indent(Some(2)))] {
+            assert_eq!(&d::<T>(&text), v, "{}", text);
+        }
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug, Default)]
+    struct Renamed {
+        #[serde(rename = "type")]
+        kind: String,
+        #[serde(default)]
+        count: u32,
+        #[serde(skip)]
+        cache: Option<u32>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        maybe: Option<Vec<Option<i8>>>,
+    }
+    check(&Renamed {
+        kind: "x".to_string(),
+        count: 3,
+        cache: None,
+        maybe: Some(vec![Some(1), None]),
+    });
+    check(&Renamed::default());
+    assert_eq!(
+        d::<Renamed>("{'type': 'y'}"),
+        Renamed {
+            kind: "y".to_string(),
+            ..Default::default()
+        }
+    );
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    #[serde(rename_all = "camelCase")]
+    struct Flattened {
+        first_name: String,
+        #[serde(flatten)]
+        inner: Inner,
+        #[serde(flatten)]
+        extra: BTreeMap<String, i32>,
+    }
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Inner {
+        a: i32,
+        b: Option<bool>,
+    }
+    check(&Flattened {
+        first_name: "n".to_string(),
+        inner: Inner { a: 1, b: None },
+        extra: vec![("z".to_string(), 2)].into_iter().collect(),
+    });
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    enum External {
+        Unit,
+        NewType(Vec<u8>),
+        Tuple(i32, Option<String>),
+        Struct {
+            a: (i32, i32),
+            b: Option<Box<External>>,
+        },
+        #[serde(rename = "renamed")]
+        Other,
+    }
+    check(&vec![
+        External::Unit,
+        External::NewType(vec![1, 2]),
+        External::Tuple(-1, None),
+        External::Tuple(1, Some("s".to_string())),
+        External::Struct {
+            a: (1, 2),
+            b: Some(Box::new(External::Unit)),
+        },
+        External::Other,
+    ]);
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    #[serde(tag = "kind")]
+    enum Internal {
+        Unit,
+        NewType(Inner),
+        Struct { x: i32, y: Vec<String> },
+    }
+    check(&vec![
+        Internal::Unit,
+        Internal::NewType(Inner {
+            a: 1,
+            b: Some(true),
+        }),
+        Internal::Struct {
+            x: 1,
+            y: vec!["a".to_string()],
+        },
+    ]);
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    #[serde(tag = "t", content = "c")]
+    enum Adjacent {
+        Unit,
+        NewType(i32),
+        Tuple(i32, bool),
+        Struct { x: Option<i32> },
+    }
+    check(&vec![
+        Adjacent::Unit,
+        Adjacent::NewType(1),
+        Adjacent::Tuple(1, false),
+        Adjacent::Struct { x: None },
+    ]);
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    #[serde(untagged)]
+    enum Untagged {
+        Int(i64),
+        Float(f64),
+        Str(String),
+        List(Vec<Untagged>),
+        Pair(bool, String),
+        Map(BTreeMap<String, Untagged>),
+        Null,
+    }
+    check(&Untagged::List(vec![
+        Untagged::Int(1),
+        Untagged::Float(1.5),
+        Untagged::Str("s".to_string()),
+        Untagged::Pair(true, "x".to_string()),
+        Untagged::Map(
+            vec![("k".to_string(), Untagged::Null)]
+                .into_iter()
+                .collect(),
+        ),
+        Untagged::List(vec![]),
+    ]));
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    #[serde(deny_unknown_fields)]
+    struct Strict {
+        #[serde(alias = "old")]
+        new: i32,
+        #[serde(default = "default_seven", skip_serializing)]
+        seven: i32,
+        unit: (),
+    }
+    fn default_seven() -> i32 {
+        7
+    }
+    check(&Strict {
+        new: 1,
+        seven: 7,
+        unit: (),
+    });
+    assert_eq!(d::<Strict>("{'old': 2, 'unit': ()}").new, 2);
+    assert!(crate::from_str::<Strict>("{'new': 2, 'unit': (), 'x': 1}").is_err());
+
+    // Buffered content: internally tagged and flattened structs with
+    // units, tuples and nested enums.
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    #[serde(tag = "kind")]
+    enum Buffered {
+        A {
+            unit: (),
+            pair: (i32, Option<i8>),
+            e: External,
+            #[serde(flatten)]
+            inner: Inner,
+        },
+    }
+    check(&Buffered::A {
+        unit: (),
+        pair: (1, Some(-1)),
+        e: External::Tuple(1, None),
+        inner: Inner {
+            a: 1,
+            b: Some(false),
+        },
+    });
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Collections {
+        map: BTreeMap<(i32, String), Vec<Option<u8>>>,
+        set: BTreeSet<i32>,
+        nested: Vec<Vec<Option<Vec<i32>>>>,
+        opt: Option<Option<i32>>,
+        unit: (),
+        newtype: Newtype,
+    }
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Newtype(BTreeMap<String, ()>);
+    check(&Collections {
+        map: vec![((1, "a".to_string()), vec![Some(1), None])]
+            .into_iter()
+            .collect(),
+        set: vec![1, 2].into_iter().collect(),
+        nested: vec![vec![], vec![None, Some(vec![]), Some(vec![1])]],
+        opt: Some(Some(1)),
+        unit: (),
+        newtype: Newtype(vec![("u".to_string(), ())].into_iter().collect()),
+    });
+
+    // `()` is unit to self-describing targets. Empty containers of other
+    // brackets are still seqs.
+    assert_eq!(d::<Value>("[(), [], (1,)]").to_string(), "[null,[],[1]]");
+    assert_eq!(d::<Vec<i32>>("()"), Vec::<i32>::new());
+}
+
+#[test]
+fn test_deserialize_flatten() {
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct S {
+        known: i32,
+        #[serde(flatten)]
+        rest: BTreeMap<String, i32>,
+    }
+    let v: S = d(r#"{"known": 1, "extra1": 2, "extra2": 3}"#);
+    assert_eq!(v.known, 1);
+    assert_eq!(format!("{:?}", v.rest), r#"{"extra1": 2, "extra2": 3}"#);
+
+    // Known key last, trailing comma, comments.
+    let v: S = d("{'a': -1, # c\n 'known': 2,}");
+    assert_eq!(v.known, 2);
+    assert_eq!(format!("{:?}", v.rest), r#"{"a": -1}"#);
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct T {
+        name: String,
+        #[serde(flatten)]
+        rest: BTreeMap<String, crate::Value>,
+    }
+    // Flattened values are buffered by serde, which turns tuples into lists.
+    let v: T = d("{'x': (1, b'2'), 'name': 'n', 'y': {1: [None]}}");
+    assert_eq!(v.name, "n");
+    assert_eq!(
+        format!("{:?}", v.rest),
+        r#"{"x": List([Int(1), Bytes([50])]), "y": Dict([(Int(1), List([None]))])}"#
+    );
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct U {
+        #[serde(flatten)]
+        inner: Inner,
+        #[serde(flatten)]
+        rest: BTreeMap<String, i32>,
+    }
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Inner {
+        a: (i32, i32),
+    }
+    let v: U = d("{'b': 1, 'a': (2, 3)}");
+    assert_eq!(v.inner, Inner { a: (2, 3) });
+    assert_eq!(format!("{:?}", v.rest), r#"{"b": 1}"#);
+
+    assert!(crate::from_str::<S>("{'extra': 1}").is_err());
+    assert!(crate::from_str::<S>("{'known': 1, 'extra': 'x'}").is_err());
+}
+
 #[test]
 fn test_deserialize_enum() {
     #[derive(Deserialize, Eq, PartialEq, Debug)]
     struct D;
 
     #[derive(Deserialize, Eq, PartialEq, Debug)]
+    #[allow(clippy::enum_variant_names)]
     enum A {
         A,
         B(u32),
@@ -336,3 +3427,444 @@ fn test_deserialize_enum() {
     let v: A = d("{'E':{'a':1,'b':2}}");
     assert_eq!(v, A::E { a: 1, b: 2 });
 }
+
+#[test]
+fn test_enum_member_access() {
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    enum Color {
+        Red,
+        #[serde(rename = "GREEN")]
+        Green,
+        Rgb(u8, u8, u8),
+    }
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct S {
+        color: Color,
+        others: Vec<Color>,
+        maybe: Option<Color>,
+    }
+    let c = || Config::new().enum_member_access(true);
+
+    let v = S {
+        color: Color::Red,
+        others: vec![Color::Green, Color::Rgb(1, 2, 3)],
+        maybe: Some(Color::Green),
+    };
+    let text = sc(&v, c());
+    assert_eq!(
+        text,
+        r#"{"color":Color.Red,"others":[Color.GREEN,{"Rgb":(1,2,3)}],"maybe":Color.GREEN}"#
+    );
+    assert_eq!(d::<S>(&text), v);
+    assert_eq!(d::<S>(&sc(&v, c().pretty(true))), v);
+    assert_eq!(s(&Color::Red), r#"{"Red":()}"#);
+
+    // The class name can be qualified, and is not checked.
+    assert_eq!(d::<Color>("colors.Color.Red"), Color::Red);
+    assert_eq!(d::<Color>("Other.GREEN"), Color::Green);
+    assert!(crate::from_str::<Color>("Color.Blue").is_err());
+    assert!(crate::from_str::<Color>("Color.Rgb").is_err());
+    assert!(crate::from_str::<Color>("Red").is_err());
+    assert!(crate::from_str::<Color>("Color.").is_err());
+}
+
+#[test]
+fn test_result_as_tuple() {
+    type R = Result<u32, String>;
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct S {
+        results: Vec<R>,
+        last: R,
+    }
+    let c = || Config::new().result_as_tuple(true);
+
+    // The default form.
+    let ok: R = Ok(1);
+    let err: R = Err("x".to_string());
+    assert_eq!(s(&ok), r#"{"Ok":1}"#);
+    assert_eq!(s(&err), r#"{"Err":"x"}"#);
+    assert_eq!(d::<R>(&s(&ok)), ok);
+    assert_eq!(d::<R>(&s(&err)), err);
+
+    // The tuple form.
+    assert_eq!(sc(&ok, c()), r#"("ok",1)"#);
+    assert_eq!(sc(&err, c()), r#"("err","x")"#);
+    assert_eq!(d::<R>(&sc(&ok, c())), ok);
+    assert_eq!(d::<R>(&sc(&err, c())), err);
+    assert_eq!(d::<R>("('ok', 1, )"), ok);
+
+    let v = S {
+        results: vec![Ok(2), Err("y".to_string())],
+        last: Ok(3),
+    };
+    let text = sc(&v, c());
+    assert_eq!(
+        text,
+        r#"{"results":[("ok",2),("err","y")],"last":("ok",3)}"#
+    );
+    assert_eq!(d::<S>(&text), v);
+    assert_eq!(d::<S>(&sc(&v, c().pretty(true))), v);
+
+    assert!(crate::from_str::<R>("('OK', 1)").is_err());
+    assert!(crate::from_str::<R>("('ok',)").is_err());
+    assert!(crate::from_str::<R>("()").is_err());
+
+    // Only the "Ok" and "Err" variants use the tuple form.
+    mod other {
+        #[derive(serde::Serialize, serde::Deserialize, PartialEq, Debug)]
+        pub enum Result {
+            Ok(u32),
+            Warn(String),
+            Err(String),
+        }
+    }
+    let v = vec![
+        other::Result::Ok(1),
+        other::Result::Warn("w".to_string()),
+        other::Result::Err("e".to_string()),
+    ];
+    let text = sc(&v, c());
+    assert_eq!(text, r#"[("ok",1),{"Warn":"w"},("err","e")]"#);
+    assert_eq!(d::<Vec<other::Result>>(&text), v);
+
+    // Enums without both "Ok" and "Err" do not read the tuple form.
+    mod single {
+        #[derive(serde::Deserialize, PartialEq, Debug)]
+        pub enum Result {
+            Ok(u32),
+        }
+    }
+    assert!(crate::from_str::<single::Result>("('ok', 1)").is_err());
+    assert_eq!(d::<single::Result>("{'Ok': 1}"), single::Result::Ok(1));
+}
+
+#[test]
+fn test_tagged_enum() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    enum E {
+        A,
+        B(u32),
+        C(u32, (i8, String)),
+        D { a: ByteBuf, b: Option<f64> },
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct S {
+        #[serde(with = "crate::tagged")]
+        e: E,
+        n: u8,
+    }
+
+    let check = |e: E, expected: &str| {
+        let v = S { e, n: 1 };
+        let s = s(&v);
+        assert_eq!(s, expected);
+        assert_eq!(d::<S>(&s), v);
+    };
+    check(E::A, r#"{"e":{"type":"A"},"n":1}"#);
+    check(E::B(2), r#"{"e":{"type":"B","data":2},"n":1}"#);
+    check(
+        E::C(2, (-3, "x".to_string())),
+        r#"{"e":{"type":"C","data":(2,(-3,"x"))},"n":1}"#,
+    );
+    check(
+        E::D {
+            a: b(b"a"),
+            b: None,
+        },
+        r#"{"e":{"type":"D","data":{"a":b"a","b":None}},"n":1}"#,
+    );
+
+    // Hand-written input, in any key order.
+    let v: S = d("{'n': 1, 'e': {'data': 3, 'type': 'B'}}");
+    assert_eq!(v.e, E::B(3));
+    assert!(crate::from_str::<S>("{'n': 1, 'e': {'data': 3}}").is_err());
+    assert!(crate::from_str::<S>("{'n': 1, 'e': {'type': 'B', 'x': 3}}").is_err());
+    assert!(crate::from_str::<S>("{'n': 1, 'e': {'type': 'X'}}").is_err());
+}
+
+#[test]
+fn test_serialize_set() {
+    use crate::Set;
+
+    let set: BTreeSet<i32> = vec![3, 1, 2].into_iter().collect();
+    assert_eq!(s(&Set(&set)), "{1,2,3}");
+    assert_eq!(s(&Set(vec!["a"])), "{\"a\"}");
+    assert_eq!(s(&Set(BTreeSet::<i32>::new())), "set()");
+    assert_eq!(p(&Set(&set)), "\n{1,\n 2,\n 3}");
+    assert_eq!(p(&vec![Set(vec![1, 2])]), "\n[{1,\n  2}]");
+
+    let mut m = BTreeMap::new();
+    m.insert("a", Set(vec![(1, 2)]));
+    m.insert("b", Set(vec![]));
+    assert_eq!(s(&m), "{\"a\":{(1,2)},\"b\":set()}");
+    assert_eq!(p(&m), "\n{\"a\": {(1,\n        2)},\n \"b\": set()}");
+
+    let v: BTreeMap<String, Set<BTreeSet<(i32, i32)>>> = d(&s(&m));
+    assert_eq!(s(&v), s(&m));
+
+    // Other serializers see a list.
+    assert_eq!(serde_json::to_string(&Set(&set)).unwrap(), "[1,2,3]");
+}
+
+#[test]
+fn test_pyset() {
+    use crate::PySet;
+
+    let set: PySet<i32> = vec![3, 1, 2, 1].into_iter().collect();
+    assert_eq!(s(&set), "{1,2,3}");
+    assert_eq!(d::<PySet<i32>>("{3, 2, 1}"), set);
+    assert_eq!(d::<PySet<i32>>("frozenset({1, 2, 3})"), set);
+
+    let empty = PySet::<String>::default();
+    assert_eq!(s(&empty), "set()");
+    assert_eq!(d::<PySet<String>>("set()"), empty);
+    assert_eq!(d::<PySet<String>>("frozenset()"), empty);
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct S {
+        tags: PySet<String>,
+        pairs: Vec<PySet<(u8, u8)>>,
+    }
+    let v = S {
+        tags: vec!["b".to_string(), "a".to_string()].into_iter().collect(),
+        pairs: vec![PySet::default(), vec![(2, 1), (1, 2)].into_iter().collect()],
+    };
+    let text = s(&v);
+    assert_eq!(text, r#"{"tags":{"a","b"},"pairs":[set(),{(1,2),(2,1)}]}"#);
+    assert_eq!(d::<S>(&text), v);
+    assert_eq!(d::<S>(&p(&v)), v);
+}
+
+#[test]
+fn test_error_display_with_source() {
+    #[derive(Deserialize, Debug)]
+    #[allow(dead_code)]
+    struct S {
+        a: Vec<i32>,
+        b: bool,
+    }
+    let src = "{\n    'a': [1, 2],\n    'b': 'x',\n}";
+    let err = crate::from_str::<S>(src).unwrap_err();
+    assert!(matches!(
+        err,
+        crate::Error::At {
+            offset: 28,
+            line: 3,
+            column: 10,
+            ..
+        }
+    ));
+    assert_eq!(err.to_string(), "expect bool, got str at line 3 column 10");
+    assert_eq!(
+        err.display_with_source(src),
+        r#"error: expect bool, got str
+ --> line 3, column 10
+  |
+3 |     'b': 'x',
+  |          ^"#
+    );
+
+    // Columns count chars. Tabs are kept.
+    let src = "['文字',\r\n\t1.5]";
+    let err = crate::from_str::<Vec<String>>(src).unwrap_err();
+    assert_eq!(
+        err.display_with_source(src),
+        "error: expect str, got float\n --> line 2, column 2\n  |\n2 | \t1.5]\n  | \t^"
+    );
+
+    // The gutter grows with the line number.
+    let src = format!("[{}x]", "1,\n".repeat(10));
+    let err = crate::from_str::<Vec<i32>>(&src).unwrap_err();
+    assert!(err
+        .display_with_source(&src)
+        .ends_with("  --> line 11, column 1\n   |\n11 | x]\n   | ^"));
+
+    // Errors at EOF, and errors without a position.
+    let err = crate::from_str::<Vec<i32>>("[1,").unwrap_err();
+    assert!(err
+        .display_with_source("[1,")
+        .ends_with("1 | [1,\n  |    ^"));
+    assert_eq!(
+        crate::Error::NaN.display_with_source(""),
+        "error: cannot serialize nan"
+    );
+}
+
+#[test]
+fn test_error_classify() {
+    use crate::Category;
+    use crate::Error;
+    use serde_json::error::Category as JsonCategory;
+
+    #[derive(Deserialize, Debug)]
+    #[allow(dead_code)]
+    struct S {
+        a: u8,
+        b: Vec<bool>,
+    }
+
+    fn check<T: de::DeserializeOwned + std::fmt::Debug>(src: &str, category: Category) {
+        let err = crate::from_str_strict::<T>(src).unwrap_err();
+        let json_err = serde_json::from_str::<T>(src).unwrap_err();
+        let json_category = match json_err.classify() {
+            JsonCategory::Io => Category::Io,
+            JsonCategory::Syntax => Category::Syntax,
+            JsonCategory::Data => Category::Data,
+            JsonCategory::Eof => Category::Eof,
+        };
+        assert_eq!(err.classify(), category, "{:?}: {}", src, err);
+        assert_eq!(json_category, category, "{:?}: {}", src, json_err);
+        assert_eq!(err.line(), Some(json_err.line()), "{:?}", src);
+    }
+
+    check::<Vec<u8>>("[1, 2", Category::Eof);
+    check::<Vec<u8>>("[\n1,\n", Category::Eof);
+    check::<S>("{\"a\": ", Category::Eof);
+    check::<String>("\"abc", Category::Eof);
+    check::<Vec<u8>>("[1 2]", Category::Syntax);
+    check::<Vec<u8>>("[\n1,\n x]", Category::Syntax);
+    check::<String>("\"\\q\"", Category::Syntax);
+    check::<Vec<u8>>("[1] 2", Category::Syntax);
+    check::<u8>("true", Category::Data);
+    check::<u8>("256", Category::Data);
+    check::<S>("{\"a\": 1,\n\"b\": [true, \"x\"]}", Category::Data);
+    check::<S>("{\"a\": 1}", Category::Data);
+
+    fn error(src: &str) -> Error {
+        match crate::from_str::<Vec<u8>>(src).unwrap_err() {
+            Error::At { error, .. } => *error,
+            e => e,
+        }
+    }
+    assert!(matches!(error("[1,"), Error::UnexpectedEnd("number")));
+    assert!(matches!(error("[x]"), Error::UnknownType("number", s) if s == "x]"));
+    let err = crate::from_str::<String>("'ab").unwrap_err();
+    assert!(matches!(err, Error::At { error, .. } if matches!(*error, Error::IncompleteStr)));
+    assert_eq!(error("[1,").to_string(), "expect number, got end");
+    assert_eq!(
+        error("[x]").to_string(),
+        r#"expect number, got unknown type ("x]")"#
+    );
+
+    let err = crate::from_str::<u8>("[\n  1]").unwrap_err();
+    assert_eq!(
+        (err.line(), err.column(), err.offset()),
+        (Some(1), Some(1), Some(0))
+    );
+    let err = crate::to_string(&f64::NAN).unwrap_err();
+    assert_eq!((err.line(), err.column(), err.offset()), (None, None, None));
+    assert_eq!(err.classify(), Category::Data);
+}
+
+#[cfg(feature = "datetime")]
+#[test]
+fn test_datetime() {
+    use std::time::Duration;
+    use std::time::SystemTime;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct S {
+        #[serde(with = "crate::datetime::timedelta")]
+        d: Duration,
+        #[serde(with = "crate::datetime::fromtimestamp")]
+        t: SystemTime,
+    }
+    let epoch = SystemTime::UNIX_EPOCH;
+    let v = S {
+        d: Duration::new(86400 * 2 + 5, 1_500),
+        t: epoch + Duration::new(1_700_000_000, 250_000_000),
+    };
+    let text = s(&v);
+    assert_eq!(
+        text,
+        r#"{"d":datetime.timedelta(days=2, seconds=5, microseconds=1),"t":datetime.datetime.fromtimestamp(1700000000.25)}"#
+    );
+    assert_eq!(
+        d::<S>(&text),
+        S {
+            d: Duration::new(86400 * 2 + 5, 1_000),
+            ..v
+        }
+    );
+    assert_eq!(d::<S>(&p(&v)), d::<S>(&text));
+
+    // Other serializers see a str.
+    let json = serde_json::to_string(&v).unwrap();
+    assert!(json.contains(r#""d":"datetime.timedelta(days=2, seconds=5, microseconds=1)""#));
+    assert_eq!(serde_json::from_str::<S>(&json).unwrap(), d::<S>(&text));
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct D(#[serde(with = "crate::datetime::timedelta")] Duration);
+    for (v, expected) in [
+        (0, "datetime.timedelta(0)"),
+        (1, "datetime.timedelta(microseconds=1)"),
+        (60_000_000, "datetime.timedelta(seconds=60)"),
+        (86_400_000_000, "datetime.timedelta(days=1)"),
+    ] {
+        let v = D(Duration::from_micros(v));
+        assert_eq!(s(&v), expected);
+        assert_eq!(d::<D>(expected), v);
+    }
+    let dur = |s: &str| d::<D>(s).0;
+    assert_eq!(dur("timedelta(1, 2)"), Duration::from_secs(86402));
+    assert_eq!(
+        dur("datetime.timedelta(hours=1, minutes=1.5,)"),
+        Duration::from_secs(3690)
+    );
+    assert_eq!(
+        dur("datetime.timedelta(weeks=1, milliseconds=1)"),
+        Duration::new(604800, 1_000_000)
+    );
+    assert_eq!(
+        dur("datetime.timedelta(days=1, seconds=-1)"),
+        Duration::from_secs(86399)
+    );
+    for s in [
+        "datetime.timedelta(days=-1)",
+        "datetime.timedelta(years=1)",
+        "datetime.timedelta(1, 2, 3, 4, 5, 6, 7, 8)",
+        "datetime.timedelta(x)",
+        "datetime.date(1)",
+        "datetime.timedelta(1",
+        "'datetime.timedelta(1)'",
+        "1",
+    ] {
+        assert!(crate::from_str::<D>(s).is_err(), "{}", s);
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct T(#[serde(with = "crate::datetime::fromtimestamp")] SystemTime);
+    for (v, expected) in [
+        (epoch, "datetime.datetime.fromtimestamp(0)"),
+        (
+            epoch + Duration::from_micros(1),
+            "datetime.datetime.fromtimestamp(0.000001)",
+        ),
+        (
+            epoch - Duration::from_millis(1500),
+            "datetime.datetime.fromtimestamp(-1.5)",
+        ),
+    ] {
+        let v = T(v);
+        assert_eq!(s(&v), expected);
+        assert_eq!(d::<T>(expected), v);
+    }
+    let time = |s: &str| d::<T>(s).0;
+    assert_eq!(
+        time("datetime.datetime.fromtimestamp(1.5, tz=datetime.timezone(datetime.timedelta(0)))"),
+        epoch + Duration::from_millis(1500)
+    );
+    assert_eq!(
+        time("datetime.fromtimestamp(timestamp=.5)"),
+        epoch + Duration::from_millis(500)
+    );
+    for s in [
+        "datetime.datetime.fromtimestamp()",
+        "datetime.datetime.fromtimestamp(1e3)",
+        "datetime.datetime.fromtimestamp(x=1)",
+        "datetime.datetime(2020, 1, 1)",
+    ] {
+        assert!(crate::from_str::<T>(s).is_err(), "{}", s);
+    }
+}
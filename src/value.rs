@@ -0,0 +1,816 @@
+//! Dynamic representation of Python literals.
+//!
+//! Unlike [`serde_json::Value`](https://docs.rs/serde_json), [`Value`] keeps
+//! the distinction between tuples, sets and lists, and allows non-string dict
+//! keys.
+
+use crate::ellipsis::ELLIPSIS_TOKEN;
+use crate::ser::Config;
+use crate::ser::Serializer;
+use crate::Result;
+use serde::de;
+use serde::ser::SerializeTuple;
+use serde::ser::SerializeTupleStruct;
+use serde::Deserialize;
+use serde::Serialize;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::convert::TryFrom;
+use std::fmt;
+use std::iter::FromIterator;
+use std::ops::Index;
+
+/// A parsed Python literal.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// `None`
+    None,
+    /// `True` or `False`
+    Bool(bool),
+    /// `1`
+    Int(i128),
+    /// `1.0`
+    Float(f64),
+    /// `(1+2j)`, as `(real, imag)`. Other serializers see a 2-tuple.
+    Complex(f64, f64),
+    /// `'a'`
+    Str(String),
+    /// `b'a'`
+    Bytes(Vec<u8>),
+    /// `[1, 2]`
+    List(Vec<Value>),
+    /// `(1, 2)`
+    Tuple(Vec<Value>),
+    /// `{1: 2}`, in the order written.
+    Dict(Vec<(Value, Value)>),
+    /// `{1, 2}`, in the order written. Also `set()` and `frozenset(...)`.
+    Set(Vec<Value>),
+    /// `...`
+    Ellipsis,
+}
+
+/// Alias of [`Value`].
+pub type PyLiteral = Value;
+
+/// Name passed to `deserialize_newtype_struct` by `Value`. The crate's
+/// `Deserializer` uses it to report tuples, sets, complex numbers and `...`
+/// via `visit_enum`.
+pub(crate) const VALUE_TOKEN: &str = "$serde_pyliteral::private::Value";
+
+/// Variant name used by `Deserializer` to report a tuple.
+pub(crate) const TUPLE_TOKEN: &str = "$serde_pyliteral::private::Tuple";
+
+/// Variant name used by `Deserializer` to report a set.
+pub(crate) const SET_TOKEN: &str = "$serde_pyliteral::private::SetVariant";
+
+/// Name passed to `serialize_tuple_struct` by `Value::Complex`, and variant
+/// name used by `Deserializer` to report a complex number.
+pub(crate) const COMPLEX_TOKEN: &str = "$serde_pyliteral::private::Complex";
+
+impl Value {
+    pub fn is_none(&self) -> bool {
+        matches!(self, Value::None)
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Bool(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    pub fn as_int(&self) -> Option<i128> {
+        match self {
+            Value::Int(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    /// Float, or int converted to float.
+    pub fn as_float(&self) -> Option<f64> {
+        match self {
+            Value::Float(v) => Some(*v),
+            Value::Int(v) => Some(*v as f64),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::Str(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Value::Bytes(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn as_list(&self) -> Option<&[Value]> {
+        match self {
+            Value::List(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn as_tuple(&self) -> Option<&[Value]> {
+        match self {
+            Value::Tuple(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn as_set(&self) -> Option<&[Value]> {
+        match self {
+            Value::Set(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn as_dict(&self) -> Option<&[(Value, Value)]> {
+        match self {
+            Value::Dict(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Look up `key` in a dict. Keys can be any value, like `1` or
+    /// `(1, 2)`. Like in Python, the last duplicated key wins.
+    ///
+    /// ```
+    /// use serde_pyliteral::Value;
+    ///
+    /// let v: Value = serde_pyliteral::from_str("{'a': 1, (2, 3): 4}").unwrap();
+    /// assert_eq!(v.get("a"), Some(&Value::Int(1)));
+    /// let key = Value::Tuple(vec![2.into(), 3.into()]);
+    /// assert_eq!(v.get(key), Some(&Value::Int(4)));
+    /// assert_eq!(v.get(5), None);
+    /// ```
+    pub fn get(&self, key: impl Into<Value>) -> Option<&Value> {
+        let key = key.into();
+        let entries = self.as_dict()?;
+        entries
+            .iter()
+            .rev()
+            .find(|(k, _)| *k == key)
+            .map(|(_, v)| v)
+    }
+
+    /// Look up a nested value by a path like `/a/0/b`, similar to
+    /// `serde_json::Value::pointer`. Each segment is an index for lists
+    /// and tuples, and a str key, or an int key if no str key matches, for
+    /// dicts. `~1` and `~0` in segments mean `/` and `~`. An empty path
+    /// refers to `self`.
+    ///
+    /// ```
+    /// use serde_pyliteral::Value;
+    ///
+    /// let v: Value = serde_pyliteral::from_str("{'a': [(1, {2: 'x'})]}").unwrap();
+    /// assert_eq!(v.pointer("/a/0/1/2"), Some(&Value::from("x")));
+    /// assert_eq!(v.pointer("/a/1"), None);
+    /// ```
+    pub fn pointer(&self, pointer: &str) -> Option<&Value> {
+        if pointer.is_empty() {
+            return Some(self);
+        }
+        let path = pointer.strip_prefix('/')?;
+        let mut value = self;
+        for segment in path.split('/') {
+            let segment = segment.replace("~1", "/").replace("~0", "~");
+            value = match value {
+                Value::List(v) | Value::Tuple(v) => v.get(segment.parse::<usize>().ok()?)?,
+                Value::Dict(_) => match value.get(segment.as_str()) {
+                    Some(v) => v,
+                    None => value.get(segment.parse::<i128>().ok()?)?,
+                },
+                _ => return None,
+            };
+        }
+        Some(value)
+    }
+}
+
+static NONE: Value = Value::None;
+
+/// Index into a list or tuple. Return `Value::None` if the index is out of
+/// range, or the value is not a list or tuple.
+impl Index<usize> for Value {
+    type Output = Value;
+
+    fn index(&self, index: usize) -> &Value {
+        match self {
+            Value::List(v) | Value::Tuple(v) => v.get(index).unwrap_or(&NONE),
+            _ => &NONE,
+        }
+    }
+}
+
+/// Parse a Python literal and serialize it again using `config`.
+///
+/// Ints, floats, tuples and dict order are preserved. Comments are dropped.
+pub fn reformat(input: &str, config: &Config) -> Result<String> {
+    let value: Value = crate::from_str(input)?;
+    let mut out = Vec::with_capacity(input.len());
+    let mut ser = Serializer::from_writer(&mut out).with_config(config.clone());
+    value.serialize(&mut ser)?;
+    let string = unsafe {
+        // We do not emit invalid UTF-8.
+        String::from_utf8_unchecked(out)
+    };
+    Ok(string)
+}
+
+/// Parse a Python dict, like `{'a': 1, 'a': 2}`, into its entries in the
+/// order written. Unlike deserializing into a map, duplicated keys are all
+/// kept. `OrderedDict(...)` is accepted too.
+///
+/// ```
+/// use serde_pyliteral::Value;
+///
+/// let entries = serde_pyliteral::parse_dict_entries("{'a': 1, 'a': 2}").unwrap();
+/// assert_eq!(entries, [("a".into(), 1.into()), ("a".into(), 2.into())]);
+/// ```
+pub fn parse_dict_entries(input: &str) -> Result<Vec<(Value, Value)>> {
+    struct Entries(Vec<(Value, Value)>);
+
+    impl<'de> Deserialize<'de> for Entries {
+        fn deserialize<D: de::Deserializer<'de>>(d: D) -> std::result::Result<Self, D::Error> {
+            d.deserialize_map(EntriesVisitor)
+        }
+    }
+
+    struct EntriesVisitor;
+
+    impl<'de> de::Visitor<'de> for EntriesVisitor {
+        type Value = Entries;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("a dict")
+        }
+
+        fn visit_map<A: de::MapAccess<'de>>(
+            self,
+            mut map: A,
+        ) -> std::result::Result<Entries, A::Error> {
+            let mut entries = Vec::with_capacity(map.size_hint().unwrap_or_default());
+            while let Some(entry) = map.next_entry()? {
+                entries.push(entry);
+            }
+            Ok(Entries(entries))
+        }
+    }
+
+    let entries: Entries = crate::from_str(input)?;
+    Ok(entries.0)
+}
+
+macro_rules! impl_from {
+    ($($t:ty => $variant:ident,)*) => {$(
+        impl From<$t> for Value {
+            fn from(v: $t) -> Self {
+                Value::$variant(v.into())
+            }
+        }
+    )*};
+}
+
+impl_from! {
+    bool => Bool,
+    i8 => Int,
+    i16 => Int,
+    i32 => Int,
+    i64 => Int,
+    i128 => Int,
+    u8 => Int,
+    u16 => Int,
+    u32 => Int,
+    u64 => Int,
+    f32 => Float,
+    f64 => Float,
+    String => Str,
+    &str => Str,
+    Cow<'_, str> => Str,
+    char => Str,
+}
+
+impl From<usize> for Value {
+    fn from(v: usize) -> Self {
+        Value::Int(v as i128)
+    }
+}
+
+impl From<isize> for Value {
+    fn from(v: isize) -> Self {
+        Value::Int(v as i128)
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(v: Option<T>) -> Self {
+        v.map_or(Value::None, Into::into)
+    }
+}
+
+/// A `Vec` becomes a list. Use `Value::Bytes` for bytes.
+impl<T: Into<Value>> From<Vec<T>> for Value {
+    fn from(v: Vec<T>) -> Self {
+        v.into_iter().collect()
+    }
+}
+
+impl<K: Into<Value>, V: Into<Value>> From<BTreeMap<K, V>> for Value {
+    fn from(v: BTreeMap<K, V>) -> Self {
+        Value::Dict(v.into_iter().map(|(k, v)| (k.into(), v.into())).collect())
+    }
+}
+
+impl<K: Into<Value>, V: Into<Value>, S> From<HashMap<K, V, S>> for Value {
+    fn from(v: HashMap<K, V, S>) -> Self {
+        Value::Dict(v.into_iter().map(|(k, v)| (k.into(), v.into())).collect())
+    }
+}
+
+/// Entries keep their order.
+#[cfg(feature = "indexmap")]
+impl<K: Into<Value>, V: Into<Value>, S> From<indexmap::IndexMap<K, V, S>> for Value {
+    fn from(v: indexmap::IndexMap<K, V, S>) -> Self {
+        Value::Dict(v.into_iter().map(|(k, v)| (k.into(), v.into())).collect())
+    }
+}
+
+impl<T: Into<Value>> From<BTreeSet<T>> for Value {
+    fn from(v: BTreeSet<T>) -> Self {
+        Value::Set(v.into_iter().map(Into::into).collect())
+    }
+}
+
+impl<T: Into<Value>, S> From<HashSet<T, S>> for Value {
+    fn from(v: HashSet<T, S>) -> Self {
+        Value::Set(v.into_iter().map(Into::into).collect())
+    }
+}
+
+/// Collect into a list.
+impl<T: Into<Value>> FromIterator<T> for Value {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Value::List(iter.into_iter().map(Into::into).collect())
+    }
+}
+
+impl Serialize for Value {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        match self {
+            Value::None => serializer.serialize_none(),
+            Value::Bool(v) => serializer.serialize_bool(*v),
+            Value::Int(v) => {
+                // Prefer 64-bit types. Not all serializers support 128-bit ints.
+                if let Ok(v) = i64::try_from(*v) {
+                    serializer.serialize_i64(v)
+                } else if let Ok(v) = u64::try_from(*v) {
+                    serializer.serialize_u64(v)
+                } else {
+                    serializer.serialize_i128(*v)
+                }
+            }
+            Value::Float(v) => serializer.serialize_f64(*v),
+            Value::Complex(real, imag) => {
+                let mut complex = serializer.serialize_tuple_struct(COMPLEX_TOKEN, 2)?;
+                complex.serialize_field(real)?;
+                complex.serialize_field(imag)?;
+                complex.end()
+            }
+            Value::Str(v) => serializer.serialize_str(v),
+            Value::Bytes(v) => serializer.serialize_bytes(v),
+            Value::List(v) => serializer.collect_seq(v),
+            Value::Tuple(v) => {
+                let mut tuple = serializer.serialize_tuple(v.len())?;
+                for item in v {
+                    tuple.serialize_element(item)?;
+                }
+                tuple.end()
+            }
+            Value::Dict(v) => serializer.collect_map(v.iter().map(|(k, v)| (k, v))),
+            Value::Set(v) => crate::Set(v).serialize(serializer),
+            Value::Ellipsis => crate::Ellipsis.serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D: de::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        deserializer.deserialize_newtype_struct(VALUE_TOKEN, ValueVisitor)
+    }
+}
+
+struct ValueVisitor;
+
+impl<'de> de::Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a Python literal")
+    }
+
+    fn visit_bool<E>(self, v: bool) -> std::result::Result<Value, E> {
+        Ok(Value::Bool(v))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> std::result::Result<Value, E> {
+        Ok(Value::Int(v.into()))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> std::result::Result<Value, E> {
+        Ok(Value::Int(v.into()))
+    }
+
+    fn visit_i128<E>(self, v: i128) -> std::result::Result<Value, E> {
+        Ok(Value::Int(v))
+    }
+
+    fn visit_u128<E: de::Error>(self, v: u128) -> std::result::Result<Value, E> {
+        match i128::try_from(v) {
+            Ok(v) => Ok(Value::Int(v)),
+            Err(_) => Err(E::custom(format!("int {} is out of range", v))),
+        }
+    }
+
+    fn visit_f64<E>(self, v: f64) -> std::result::Result<Value, E> {
+        Ok(Value::Float(v))
+    }
+
+    fn visit_str<E>(self, v: &str) -> std::result::Result<Value, E> {
+        Ok(Value::Str(v.to_string()))
+    }
+
+    fn visit_string<E>(self, v: String) -> std::result::Result<Value, E> {
+        Ok(Value::Str(v))
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> std::result::Result<Value, E> {
+        Ok(Value::Bytes(v.to_vec()))
+    }
+
+    fn visit_byte_buf<E>(self, v: Vec<u8>) -> std::result::Result<Value, E> {
+        Ok(Value::Bytes(v))
+    }
+
+    fn visit_none<E>(self) -> std::result::Result<Value, E> {
+        Ok(Value::None)
+    }
+
+    fn visit_unit<E>(self) -> std::result::Result<Value, E> {
+        Ok(Value::None)
+    }
+
+    fn visit_some<D: de::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> std::result::Result<Value, D::Error> {
+        Value::deserialize(deserializer)
+    }
+
+    fn visit_newtype_struct<D: de::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> std::result::Result<Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> std::result::Result<Value, A::Error> {
+        let mut values = Vec::with_capacity(seq.size_hint().unwrap_or_default());
+        while let Some(v) = seq.next_element()? {
+            values.push(v);
+        }
+        Ok(Value::List(values))
+    }
+
+    fn visit_map<A: de::MapAccess<'de>>(self, mut map: A) -> std::result::Result<Value, A::Error> {
+        let mut entries = Vec::with_capacity(map.size_hint().unwrap_or_default());
+        while let Some(entry) = map.next_entry()? {
+            entries.push(entry);
+        }
+        Ok(Value::Dict(entries))
+    }
+
+    // Used by the crate's `Deserializer` to report tuples, sets, complex
+    // numbers and `...`.
+    fn visit_enum<A: de::EnumAccess<'de>>(self, data: A) -> std::result::Result<Value, A::Error> {
+        use de::VariantAccess;
+        let (name, variant): (String, _) = data.variant()?;
+        if name == TUPLE_TOKEN {
+            Ok(Value::Tuple(variant.newtype_variant()?))
+        } else if name == SET_TOKEN {
+            Ok(Value::Set(variant.newtype_variant()?))
+        } else if name == COMPLEX_TOKEN {
+            let (real, imag) = variant.newtype_variant()?;
+            Ok(Value::Complex(real, imag))
+        } else if name == ELLIPSIS_TOKEN {
+            variant.unit_variant()?;
+            Ok(Value::Ellipsis)
+        } else {
+            Err(de::Error::custom(format!("unexpected enum {:?}", name)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn d(s: &str) -> Value {
+        crate::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn test_value_deserialize() {
+        assert_eq!(d("None"), Value::None);
+        assert_eq!(d("True"), Value::Bool(true));
+        assert_eq!(d("-3"), Value::Int(-3));
+        assert_eq!(d("0xffffffffffffffffff"), Value::Int(0xffffffffffffffffff));
+        assert_eq!(d("3."), Value::Float(3.0));
+        assert_eq!(d("'a'"), Value::Str("a".to_string()));
+        assert_eq!(d("b'a'"), Value::Bytes(b"a".to_vec()));
+        assert_eq!(d("()"), Value::Tuple(vec![]));
+        assert_eq!(
+            d("[(1,), [2]]"),
+            Value::List(vec![
+                Value::Tuple(vec![Value::Int(1)]),
+                Value::List(vec![Value::Int(2)])
+            ])
+        );
+        assert_eq!(
+            d("{(1, 2): None, 'b': 1.5}"),
+            Value::Dict(vec![
+                (
+                    Value::Tuple(vec![Value::Int(1), Value::Int(2)]),
+                    Value::None
+                ),
+                (Value::Str("b".to_string()), Value::Float(1.5)),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_value_set() {
+        let set = |v: Vec<i128>| Value::Set(v.into_iter().map(Value::Int).collect());
+        assert_eq!(d("{2, 1}"), set(vec![2, 1]));
+        assert_eq!(d("{1,}"), set(vec![1]));
+        assert_eq!(d("set()"), set(vec![]));
+        assert_eq!(d("frozenset({3})"), set(vec![3]));
+        assert_eq!(d("{}"), Value::Dict(vec![]));
+        assert_eq!(d("{ # c\n}"), Value::Dict(vec![]));
+        assert_eq!(
+            d("{'a,': {1}, (1, 2): '}'}"),
+            Value::Dict(vec![
+                (Value::from("a,"), set(vec![1])),
+                (Value::Tuple(vec![1.into(), 2.into()]), Value::from("}")),
+            ])
+        );
+        assert_eq!(
+            d("{'a:', (1, {2: 3}), b'}'}"),
+            Value::Set(vec![
+                Value::from("a:"),
+                Value::Tuple(vec![1.into(), Value::Dict(vec![(2.into(), 3.into())])]),
+                Value::Bytes(b"}".to_vec()),
+            ])
+        );
+
+        assert_eq!(crate::to_string(&set(vec![2, 1])).unwrap(), "{2,1}");
+        assert_eq!(crate::to_string(&set(vec![])).unwrap(), "set()");
+        let input = "[{(1,), 'a'}, set(), {1: {2}}]";
+        let value = d(input);
+        assert_eq!(d(&crate::to_string(&value).unwrap()), value);
+    }
+
+    #[test]
+    fn test_value_ellipsis() {
+        assert_eq!(d("..."), Value::Ellipsis);
+        let value = d("[..., None, (...,)]");
+        assert_eq!(
+            value,
+            Value::List(vec![
+                Value::Ellipsis,
+                Value::None,
+                Value::Tuple(vec![Value::Ellipsis])
+            ])
+        );
+        let text = crate::to_string(&value).unwrap();
+        assert_eq!(text, "[...,None,(...,)]");
+        assert_eq!(d(&text), value);
+        assert_eq!(
+            d("{...: 1}"),
+            Value::Dict(vec![(Value::Ellipsis, 1.into())])
+        );
+    }
+
+    #[test]
+    fn test_value_complex() {
+        assert_eq!(d("1+2j"), Value::Complex(1.0, 2.0));
+        assert_eq!(d("complex(3)"), Value::Complex(3.0, 0.0));
+        let value = d("[(1.5-2j), 4j, (-0-1e999j), (1, 2j)]");
+        assert_eq!(
+            value,
+            Value::List(vec![
+                Value::Complex(1.5, -2.0),
+                Value::Complex(0.0, 4.0),
+                Value::Complex(0.0, f64::NEG_INFINITY),
+                Value::Tuple(vec![1.into(), Value::Complex(0.0, 2.0)]),
+            ])
+        );
+        let text = crate::to_string(&value).unwrap();
+        assert_eq!(text, "[(1.5-2.j),(0.+4.j),(-0.-1e999j),(1,(0.+2.j))]");
+        assert_eq!(d(&text), value);
+        assert_eq!(
+            reformat("{-1-0j: 1}", &Config::default()).unwrap(),
+            "{(-1.-0.j):1}"
+        );
+        assert!(crate::to_string(&Value::Complex(f64::NAN, 0.0)).is_err());
+
+        // Other serializers see a 2-tuple.
+        let json = serde_json::to_string(&Value::Complex(1.0, -2.0)).unwrap();
+        assert_eq!(json, "[1.0,-2.0]");
+    }
+
+    #[test]
+    fn test_value_tuple_keys() {
+        let map: BTreeMap<(i32, i32), Vec<i32>> = vec![((1, 2), vec![3]), ((-1, 0), vec![])]
+            .into_iter()
+            .collect();
+        let text = crate::to_string(&map).unwrap();
+        assert_eq!(text, "{(-1,0):[],(1,2):[3]}");
+        let tuple = |a: i128, b: i128| Value::Tuple(vec![a.into(), b.into()]);
+        let value = d(&text);
+        assert_eq!(
+            value,
+            Value::Dict(vec![
+                (tuple(-1, 0), Value::List(vec![])),
+                (tuple(1, 2), Value::List(vec![3.into()])),
+            ])
+        );
+        assert_eq!(crate::to_string(&value).unwrap(), text);
+        let back: BTreeMap<(i32, i32), Vec<i32>> = crate::from_str(&text).unwrap();
+        assert_eq!(back, map);
+
+        // Nested tuples, and pretty output.
+        let map: BTreeMap<(i32, (i32, i32)), i32> = vec![((1, (2, 4)), 5)].into_iter().collect();
+        let text = crate::to_string_pretty(&map).unwrap();
+        // Keys are written compactly.
+        assert_eq!(text, "{(1,(2,4)): 5}");
+        let value = d(&text);
+        let key = Value::Tuple(vec![1.into(), tuple(2, 4)]);
+        assert_eq!(value, Value::Dict(vec![(key, 5.into())]));
+        assert_eq!(crate::to_string_pretty(&value).unwrap(), text);
+    }
+
+    #[test]
+    fn test_value_from() {
+        assert_eq!(Value::from(true), Value::Bool(true));
+        assert_eq!(Value::from(-3i8), Value::Int(-3));
+        assert_eq!(Value::from(u64::MAX), Value::Int(u64::MAX as i128));
+        assert_eq!(Value::from(3usize), Value::Int(3));
+        assert_eq!(Value::from(1.5f32), Value::Float(1.5));
+        assert_eq!(Value::from("a"), Value::Str("a".to_string()));
+        assert_eq!(Value::from('a'), Value::Str("a".to_string()));
+        assert_eq!(Value::from(None::<i32>), Value::None);
+        assert_eq!(Value::from(Some(1)), Value::Int(1));
+        assert_eq!(
+            Value::from(vec![1, 2]),
+            Value::List(vec![Value::Int(1), Value::Int(2)])
+        );
+        let map: BTreeMap<_, _> = vec![("a", vec![true])].into_iter().collect();
+        assert_eq!(
+            crate::to_string(&Value::from(map)).unwrap(),
+            r#"{"a":[True]}"#
+        );
+        let set: BTreeSet<_> = vec![2, 1].into_iter().collect();
+        assert_eq!(crate::to_string(&Value::from(set)).unwrap(), "{1,2}");
+        let list: Value = (0..3).collect();
+        assert_eq!(crate::to_string(&list).unwrap(), "[0,1,2]");
+    }
+
+    #[test]
+    fn test_value_accessors() {
+        let v = d("{'a': [1, 2.5, 'x', b'y', None, True], 1: (3,), 'a': {4}}");
+        assert_eq!(
+            v.get("a").and_then(|v| v.as_set()),
+            Some(&[Value::Int(4)][..])
+        );
+        assert_eq!(
+            v.get(1).and_then(|v| v.as_tuple()).map(|v| v.len()),
+            Some(1)
+        );
+        assert_eq!(v.get(2), None);
+        assert_eq!(v.get("b"), None);
+        assert!(v.as_dict().is_some());
+        assert!(v.as_list().is_none());
+
+        let list = v.pointer("/a").unwrap();
+        assert_eq!(list, v.get("a").unwrap());
+        let list = &v.as_dict().unwrap()[0].1;
+        assert_eq!(list.as_list().map(|v| v.len()), Some(6));
+        assert_eq!(list[0].as_int(), Some(1));
+        assert_eq!(list[0].as_float(), Some(1.0));
+        assert_eq!(list[1].as_float(), Some(2.5));
+        assert_eq!(list[1].as_int(), None);
+        assert_eq!(list[2].as_str(), Some("x"));
+        assert_eq!(list[3].as_bytes(), Some(&b"y"[..]));
+        assert!(list[4].is_none());
+        assert_eq!(list[5].as_bool(), Some(true));
+        assert!(list[6].is_none());
+        assert!(list[0][0].is_none());
+        assert_eq!(v.get(1).unwrap()[0], Value::Int(3));
+    }
+
+    #[test]
+    fn test_value_pointer() {
+        let v = d("{'a': [(1, {2: 'x', '2': 'y'})], 'b/c': {'~': 1}, 3: None}");
+        assert_eq!(v.pointer(""), Some(&v));
+        assert_eq!(v.pointer("/a/0/0"), Some(&Value::Int(1)));
+        assert_eq!(v.pointer("/a/0/1/2"), Some(&Value::from("y")));
+        assert_eq!(v.pointer("/b~1c/~0"), Some(&Value::Int(1)));
+        assert_eq!(v.pointer("/3"), Some(&Value::None));
+        assert_eq!(v.pointer("a"), None);
+        assert_eq!(v.pointer("/a/1"), None);
+        assert_eq!(v.pointer("/a/x"), None);
+        assert_eq!(v.pointer("/a/0/0/0"), None);
+        assert_eq!(v.pointer("/c"), None);
+    }
+
+    #[test]
+    fn test_reformat() {
+        let config = Config::default();
+        let input = "{ 'b' : (1 ,) , 'a': [ 1.0,2 , 0x10 ], (1,2) :b'x', 'c': None, }";
+        assert_eq!(
+            reformat(input, &config).unwrap(),
+            r#"{"b":(1,),"a":[1.,2,16],(1,2):b"x","c":None}"#
+        );
+
+        let config = Config::default().pretty(true);
+        let input = "{'z': {'y': [1, (2, 3)], 'x': ()}, 'a': 1e10}";
+        assert_eq!(
+            reformat(input, &config).unwrap(),
+            r#"{"z": {"y": [1, (2, 3)], "x": ()}, "a": 10000000000.}"#
+        );
+        let config = config.pretty_width(None);
+        assert_eq!(
+            reformat(input, &config).unwrap(),
+            r#"{"z": {"y": [1,
+             (2,
+              3)],
+       "x": ()},
+ "a": 10000000000.}"#
+        );
+
+        // Reformatting is idempotent.
+        let output = reformat(input, &config).unwrap();
+        assert_eq!(reformat(&output, &config).unwrap(), output);
+
+        assert!(reformat("[1, 2", &config).is_err());
+
+        let config = Config::default().sort_keys(true);
+        let input = "{'b': {3: 1, (1,): 2}, 'a': [{'y': 1, 'x': 2}]}";
+        assert_eq!(
+            reformat(input, &config).unwrap(),
+            r#"{"a":[{"x":2,"y":1}],"b":{(1,):2,3:1}}"#
+        );
+    }
+
+    #[test]
+    fn test_parse_dict_entries() {
+        let input = "{'b': 1, 'a': [2], 'b': 3, (1,): None, 'a': [2]}";
+        let entries = parse_dict_entries(input).unwrap();
+        assert_eq!(
+            entries,
+            [
+                ("b".into(), 1.into()),
+                ("a".into(), Value::List(vec![2.into()])),
+                ("b".into(), 3.into()),
+                (Value::Tuple(vec![1.into()]), Value::None),
+                ("a".into(), Value::List(vec![2.into()])),
+            ]
+        );
+
+        // Nested dicts keep duplicates too, as `Value::Dict`.
+        let entries = parse_dict_entries("{'x': {1: 1, 1: 2}}").unwrap();
+        assert_eq!(entries[0].1.as_dict().map(|d| d.len()), Some(2));
+
+        let entries = parse_dict_entries("OrderedDict([('a', 1), ('a', 2)])").unwrap();
+        assert_eq!(entries, [("a".into(), 1.into()), ("a".into(), 2.into())]);
+        assert_eq!(parse_dict_entries("{}").unwrap(), []);
+
+        assert!(parse_dict_entries("[('a', 1)]").is_err());
+        assert!(parse_dict_entries("{1, 2}").is_err());
+        assert!(parse_dict_entries("{'a': 1").is_err());
+    }
+}